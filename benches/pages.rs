@@ -0,0 +1,83 @@
+//! Rendering a page per request against serving it from `PageCache`.
+//!
+//! Run from the repository root with `cargo bench --bench pages`. Before the
+//! timings it prints the allocations one request for each page makes.
+
+use actix_web::http::header::HeaderMap;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use rustdev::bench::Pages;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Counts allocations so each request's share can be reported.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// A landing page, the largest listing, two smaller listings and a detail
+/// page.
+const PATHS: [&str; 5] = ["/", "/tools", "/news", "/events", "/tools/anchor"];
+
+fn allocations<T>(f: impl FnOnce() -> T) -> (usize, usize) {
+    let (count, bytes) = (
+        ALLOCATIONS.load(Ordering::Relaxed),
+        ALLOCATED_BYTES.load(Ordering::Relaxed),
+    );
+    black_box(f());
+    (
+        ALLOCATIONS.load(Ordering::Relaxed) - count,
+        ALLOCATED_BYTES.load(Ordering::Relaxed) - bytes,
+    )
+}
+
+fn pages(c: &mut Criterion) {
+    let pages = Pages::load().expect("run from the repository root");
+    let headers = HeaderMap::new();
+
+    println!(
+        "{:<16}{:>26}{:>26}",
+        "path", "rendered (allocs / bytes)", "cached"
+    );
+    for path in PATHS {
+        let rendered = allocations(|| pages.render(path).expect("page renders"));
+        let cached = allocations(|| pages.serve(path, &headers).expect("page is cached"));
+        println!(
+            "{path:<16}{:>26}{:>26}",
+            format!("{} / {}", rendered.0, rendered.1),
+            format!("{} / {}", cached.0, cached.1),
+        );
+    }
+
+    let mut group = c.benchmark_group("pages");
+    for path in PATHS {
+        group.bench_with_input(BenchmarkId::new("rendered", path), path, |b, path| {
+            b.iter(|| pages.render(path))
+        });
+        group.bench_with_input(BenchmarkId::new("cached", path), path, |b, path| {
+            b.iter(|| pages.serve(path, &headers))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, pages);
+criterion_main!(benches);
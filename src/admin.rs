@@ -0,0 +1,923 @@
+use actix_web::{
+    body::BoxBody,
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    web, Error, HttpResponse,
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{env, future::Future, io, sync::Arc};
+use tokio::sync::Mutex;
+
+use crate::{
+    api::api_error,
+    audit::{AuditLog, Entry},
+    content::{slug_of, Collection},
+    git::GitContent,
+    is_allowed_host, migrate, reload_site,
+    store::{ContentStore, Mutation, Snapshot},
+    SiteState,
+};
+
+/// Who API edits are attributed to.
+const API_ACTOR: &str = "api-token";
+
+/// Slugs become URL segments: lowercase ASCII letters, digits, `-` and `_`.
+fn valid_slug(slug: &str) -> bool {
+    slug.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && slug
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || matches!(b, b'-' | b'_'))
+}
+
+/// A change to one entity.
+pub(crate) enum Edit {
+    Create(Value),
+    Update(String, Value),
+    Delete(String),
+    /// Puts back the version of `slug` recorded before audit log entry
+    /// `entry`, which is `Null` if the entity did not exist yet.
+    Revert {
+        entry: String,
+        slug: String,
+        version: Value,
+    },
+}
+
+impl Edit {
+    fn action(&self) -> &'static str {
+        match self {
+            Edit::Create(_) => "create",
+            Edit::Update(..) => "update",
+            Edit::Delete(_) => "delete",
+            Edit::Revert { .. } => "revert",
+        }
+    }
+
+    fn reverts(&self) -> Option<String> {
+        match self {
+            Edit::Revert { entry, .. } => Some(entry.clone()),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) enum EditError {
+    /// The request body is not JSON.
+    Malformed(String),
+    NotFound(String),
+    Conflict(String),
+    Invalid(String),
+    Storage(io::Error),
+}
+
+impl EditError {
+    pub(crate) fn status(&self) -> StatusCode {
+        match self {
+            EditError::Malformed(_) => StatusCode::BAD_REQUEST,
+            EditError::NotFound(_) => StatusCode::NOT_FOUND,
+            EditError::Conflict(_) => StatusCode::CONFLICT,
+            EditError::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            EditError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub(crate) fn message(&self) -> String {
+        match self {
+            EditError::Malformed(message)
+            | EditError::NotFound(message)
+            | EditError::Conflict(message)
+            | EditError::Invalid(message) => message.clone(),
+            EditError::Storage(err) => err.to_string(),
+        }
+    }
+
+    fn response(&self) -> HttpResponse {
+        api_error(self.status(), self.message())
+    }
+}
+
+/// Validates an entity on its own: an object with a well-formed slug that
+/// reads as the collection's struct. Returns the slug.
+fn validate(collection: Collection, entity: &Value) -> Result<String, EditError> {
+    if !entity.is_object() {
+        return Err(EditError::Invalid("Expected a JSON object".into()));
+    }
+    let slug =
+        slug_of(entity).ok_or_else(|| EditError::Invalid("`slug` must be a string".into()))?;
+    if !valid_slug(slug) {
+        return Err(EditError::Invalid(format!(
+            "Invalid slug `{slug}`: use lowercase letters, digits, `-` and `_`"
+        )));
+    }
+    collection
+        .check(entity)
+        .map_err(|err| EditError::Invalid(format!("Invalid {}: {err}", collection.path())))?;
+    Ok(slug.to_string())
+}
+
+fn capitalized(text: &str) -> String {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// The bearer token `/admin/api` requires.
+pub(crate) struct ApiToken {
+    digest: [u8; 32],
+}
+
+impl ApiToken {
+    /// `None` unless `ADMIN_TOKEN` is set, which leaves `/admin/api` off.
+    pub(crate) fn from_env() -> Option<Self> {
+        let token = env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty())?;
+        Some(Self::new(&token))
+    }
+
+    pub(crate) fn new(token: &str) -> Self {
+        Self {
+            digest: Sha256::digest(token.as_bytes()).into(),
+        }
+    }
+
+    fn accepts(&self, req: &ServiceRequest) -> bool {
+        req.headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .is_some_and(|token| digests_match(&self.digest, token.trim()))
+    }
+}
+
+/// Compares digests so the time taken does not depend on how much of a
+/// secret matched.
+pub(crate) fn digests_match(expected: &[u8; 32], presented: &str) -> bool {
+    let digest: [u8; 32] = Sha256::digest(presented.as_bytes()).into();
+    digest
+        .iter()
+        .zip(expected.iter())
+        .fold(0, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+/// The seed after an edit, checked but not yet written.
+pub(crate) struct Edited {
+    pub(crate) seed: Value,
+    pub(crate) slug: String,
+    /// The entity before the edit, or `Null` if it did not exist.
+    pub(crate) before: Value,
+    /// The entity as stored, or `Null` for a deletion.
+    pub(crate) stored: Value,
+}
+
+/// Applies `edit` to a copy of the seed. Fails unless the entity is valid
+/// and the whole seed still deserializes.
+pub(crate) fn edited_seed(
+    mut seed: Value,
+    collection: Collection,
+    edit: Edit,
+) -> Result<Edited, EditError> {
+    let items = collection
+        .items_mut(&mut seed)
+        .map_err(|err| EditError::Invalid(err.to_string()))?;
+    let position =
+        |items: &[Value], slug: &str| items.iter().position(|item| slug_of(item) == Some(slug));
+    let missing =
+        |slug: &str| EditError::NotFound(format!("No {} with slug `{slug}`", collection.path()));
+
+    let (slug, before, stored) = match edit {
+        Edit::Create(entity) => {
+            let slug = validate(collection, &entity)?;
+            if position(items, &slug).is_some() {
+                return Err(EditError::Conflict(format!(
+                    "`{slug}` already exists in {}",
+                    collection.path()
+                )));
+            }
+            items.push(entity.clone());
+            (slug, Value::Null, entity)
+        }
+        Edit::Update(slug, mut entity) => {
+            let index = position(items, &slug).ok_or_else(|| missing(&slug))?;
+            if let Some(fields) = entity.as_object_mut() {
+                fields
+                    .entry("slug")
+                    .or_insert_with(|| Value::String(slug.clone()));
+            }
+            if validate(collection, &entity)? != slug {
+                return Err(EditError::Invalid(
+                    "`slug` cannot be changed; create the entity under the new slug and delete the old one"
+                        .into(),
+                ));
+            }
+            let before = std::mem::replace(&mut items[index], entity.clone());
+            (slug, before, entity)
+        }
+        Edit::Delete(slug) => {
+            let index = position(items, &slug).ok_or_else(|| missing(&slug))?;
+            let before = items.remove(index);
+            (slug, before, Value::Null)
+        }
+        Edit::Revert { slug, version, .. } => {
+            let index = position(items, &slug);
+            let before = match (index, version.is_null()) {
+                (Some(index), true) => items.remove(index),
+                (None, true) => {
+                    return Err(EditError::Conflict(format!(
+                        "`{slug}` has already been removed from {}",
+                        collection.path()
+                    )))
+                }
+                (index, false) => {
+                    if validate(collection, &version)? != slug {
+                        return Err(EditError::Invalid(format!(
+                            "The recorded version is not `{slug}`"
+                        )));
+                    }
+                    match index {
+                        Some(index) => std::mem::replace(&mut items[index], version.clone()),
+                        None => {
+                            items.push(version.clone());
+                            Value::Null
+                        }
+                    }
+                }
+            };
+            (slug, before, version)
+        }
+    };
+
+    // The entity checks cannot see the rest of the file.
+    migrate::read_seed(seed.clone())
+        .map_err(|err| EditError::Invalid(format!("Seed would not load: {err}")))?;
+    Ok(Edited {
+        seed,
+        slug,
+        before,
+        stored,
+    })
+}
+
+/// Applies edits to the content store, records them in the audit log and
+/// swaps in a `Site` rebuilt from the result.
+pub(crate) struct ContentEditor {
+    store: Arc<dyn ContentStore>,
+    audit: AuditLog,
+    /// Serializes edits, so each one starts from the content the previous
+    /// one wrote.
+    lock: Mutex<()>,
+}
+
+impl ContentEditor {
+    pub(crate) fn new(store: Arc<dyn ContentStore>, audit: AuditLog) -> Self {
+        Self {
+            store,
+            audit,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// The recorded edits of one entity, newest first.
+    pub(crate) async fn history(
+        &self,
+        collection: Collection,
+        slug: &str,
+    ) -> Result<Vec<Entry>, EditError> {
+        self.audit
+            .history(collection, slug)
+            .await
+            .map_err(EditError::Storage)
+    }
+
+    /// The git repository content is committed to, if any.
+    pub(crate) fn git(&self) -> Option<&GitContent> {
+        self.store.git()
+    }
+
+    /// Where the content is kept, for the dashboard.
+    pub(crate) fn describe(&self) -> String {
+        self.store.describe()
+    }
+
+    pub(crate) async fn read_seed(&self) -> io::Result<Value> {
+        Ok(self.snapshot().await?.seed)
+    }
+
+    async fn snapshot(&self) -> io::Result<Snapshot> {
+        let store = self.store.clone();
+        tokio::task::spawn_blocking(move || store.load())
+            .await
+            .map_err(io::Error::other)?
+    }
+
+    /// Stores `entity` as `slug` of `collection`, or deletes it when
+    /// `None`. Returns the new revision.
+    #[allow(clippy::too_many_arguments)]
+    async fn store(
+        &self,
+        collection: Collection,
+        slug: &str,
+        entity: Option<Value>,
+        seed: &Value,
+        base: Option<String>,
+        author: &str,
+        message: String,
+    ) -> io::Result<Option<String>> {
+        let store = self.store.clone();
+        let (slug, seed, author) = (slug.to_string(), seed.clone(), author.to_string());
+        tokio::task::spawn_blocking(move || {
+            store.apply(&Mutation {
+                collection,
+                slug: &slug,
+                entity: entity.as_ref(),
+                seed: &seed,
+                base: base.as_deref(),
+                author: &author,
+                message: &message,
+            })
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+
+    /// Applies `edit` on behalf of `actor`, persists the seed, records the
+    /// edit in the audit log and rebuilds the site. Nothing is written
+    /// unless the edited seed passes `edited_seed`, and a seed whose edit
+    /// could not be recorded is put back. An update that changes nothing is
+    /// neither written nor recorded. Returns the entity as stored, or `Null`
+    /// for a deletion.
+    pub(crate) async fn apply(
+        &self,
+        state: &SiteState,
+        actor: &str,
+        collection: Collection,
+        edit: Edit,
+    ) -> Result<Value, EditError> {
+        if let Some(reason) = self.store.read_only() {
+            return Err(EditError::Conflict(format!(
+                "{reason}, so it cannot be edited"
+            )));
+        }
+        let _editing = self.lock.lock().await;
+        let snapshot = self.snapshot().await.map_err(EditError::Storage)?;
+        let (action, reverts) = (edit.action(), edit.reverts());
+        let edited = edited_seed(snapshot.seed.clone(), collection, edit)?;
+        if edited.before == edited.stored {
+            return Ok(edited.stored);
+        }
+        let subject = format!("{action} {}/{}", collection.path(), edited.slug);
+        let revision = self
+            .store(
+                collection,
+                &edited.slug,
+                Some(edited.stored.clone()).filter(|entity| !entity.is_null()),
+                &edited.seed,
+                snapshot.revision,
+                actor,
+                capitalized(&subject),
+            )
+            .await
+            .map_err(EditError::Storage)?;
+
+        let entry = Entry::new(
+            actor,
+            action,
+            collection,
+            &edited.slug,
+            reverts,
+            edited.before.clone(),
+            edited.stored.clone(),
+        );
+        if let Err(err) = self.audit.append(&entry).await {
+            tracing::error!(%err, "audit log append failed, restoring the previous version");
+            self.store(
+                collection,
+                &edited.slug,
+                Some(edited.before).filter(|entity| !entity.is_null()),
+                &snapshot.seed,
+                revision,
+                actor,
+                format!("Undo {subject}: the audit log could not record it"),
+            )
+            .await
+            .map_err(EditError::Storage)?;
+            return Err(EditError::Storage(io::Error::new(
+                err.kind(),
+                format!("Not saved: recording the edit in the audit log failed: {err}"),
+            )));
+        }
+        tracing::info!(
+            collection = collection.path(),
+            slug = %edited.slug,
+            action,
+            actor,
+            entry = %entry.id,
+            revision,
+            "content edited"
+        );
+
+        reload_site(state).await.map_err(|err| {
+            EditError::Storage(io::Error::new(
+                err.kind(),
+                format!("Saved, but rebuilding the site failed: {err}"),
+            ))
+        })?;
+        Ok(edited.stored)
+    }
+
+    /// Undoes audit log entry `id` of `slug` by putting back the version it
+    /// replaced, through the same checks as any other edit.
+    pub(crate) async fn revert(
+        &self,
+        state: &SiteState,
+        actor: &str,
+        collection: Collection,
+        slug: &str,
+        id: &str,
+    ) -> Result<Value, EditError> {
+        let entry = self
+            .audit
+            .entry(collection, slug, id)
+            .await
+            .map_err(EditError::Storage)?
+            .ok_or_else(|| EditError::NotFound(format!("No recorded edit `{id}` of `{slug}`")))?;
+        let edit = Edit::Revert {
+            entry: entry.id,
+            slug: entry.slug,
+            version: entry.before,
+        };
+        self.apply(state, actor, collection, edit).await
+    }
+}
+
+/// Middleware guarding `/admin/api`: the request must come for one of the
+/// site's hosts and carry the admin token. Responses are never cached.
+pub(crate) fn require_token<S>(
+    mut req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error>,
+{
+    let rejection = if !is_allowed_host(req.parts_mut().0) {
+        Some(api_error(StatusCode::NOT_FOUND, "Not found"))
+    } else if !req
+        .app_data::<web::Data<ApiToken>>()
+        .is_some_and(|token| token.accepts(&req))
+    {
+        let mut res = api_error(StatusCode::UNAUTHORIZED, "Missing or invalid admin token");
+        res.headers_mut()
+            .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        Some(res)
+    } else {
+        None
+    };
+    let outcome = match rejection {
+        None => Ok(srv.call(req)),
+        Some(res) => Err(req.into_response(res)),
+    };
+    async move {
+        let mut res = match outcome {
+            Ok(fut) => fut.await?,
+            Err(res) => res,
+        };
+        res.headers_mut()
+            .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+        Ok(res)
+    }
+}
+
+fn collection(path: &str) -> Result<Collection, EditError> {
+    Collection::from_path(path)
+        .ok_or_else(|| EditError::NotFound(format!("Unknown collection `{path}`")))
+}
+
+fn parse_body(body: &[u8]) -> Result<Value, EditError> {
+    serde_json::from_slice(body).map_err(|err| EditError::Malformed(format!("Invalid JSON: {err}")))
+}
+
+fn respond(result: Result<HttpResponse, EditError>) -> HttpResponse {
+    result.unwrap_or_else(|err| err.response())
+}
+
+/// The admin API's resources, mounted under `/admin/api` behind
+/// `require_token`.
+pub(crate) fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/{collection}")
+            .route(web::get().to(list))
+            .route(web::post().to(create)),
+    )
+    .service(
+        web::resource("/{collection}/{slug}")
+            .route(web::get().to(item))
+            .route(web::put().to(update))
+            .route(web::delete().to(delete)),
+    )
+    .service(web::resource("/{collection}/{slug}/history").route(web::get().to(history)))
+    .service(
+        web::resource("/{collection}/{slug}/history/{id}/revert").route(web::post().to(revert)),
+    );
+}
+
+pub(crate) async fn list(
+    path: web::Path<String>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    respond(
+        async {
+            let collection = collection(&path)?;
+            let seed = editor.read_seed().await.map_err(EditError::Storage)?;
+            Ok(HttpResponse::Ok().json(json!({ "data": collection.items(&seed) })))
+        }
+        .await,
+    )
+}
+
+pub(crate) async fn item(
+    path: web::Path<(String, String)>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    respond(
+        async {
+            let (collection, slug) = (self::collection(&path.0)?, &path.1);
+            let seed = editor.read_seed().await.map_err(EditError::Storage)?;
+            let item = collection
+                .items(&seed)
+                .iter()
+                .find(|item| slug_of(item) == Some(slug.as_str()))
+                .ok_or_else(|| {
+                    EditError::NotFound(format!("No {} with slug `{slug}`", collection.path()))
+                })?;
+            Ok(HttpResponse::Ok().json(json!({ "data": item })))
+        }
+        .await,
+    )
+}
+
+pub(crate) async fn create(
+    path: web::Path<String>,
+    body: web::Bytes,
+    editor: web::Data<ContentEditor>,
+    state: web::Data<SiteState>,
+) -> HttpResponse {
+    respond(
+        async {
+            let collection = collection(&path)?;
+            let entity = parse_body(&body)?;
+            let stored = editor
+                .apply(&state, API_ACTOR, collection, Edit::Create(entity))
+                .await?;
+            let location = format!(
+                "/admin/api/{}/{}",
+                collection.path(),
+                slug_of(&stored).unwrap_or_default()
+            );
+            Ok(HttpResponse::Created()
+                .append_header((header::LOCATION, location))
+                .json(json!({ "data": stored })))
+        }
+        .await,
+    )
+}
+
+pub(crate) async fn update(
+    path: web::Path<(String, String)>,
+    body: web::Bytes,
+    editor: web::Data<ContentEditor>,
+    state: web::Data<SiteState>,
+) -> HttpResponse {
+    respond(
+        async {
+            let (collection, slug) = path.into_inner();
+            let collection = self::collection(&collection)?;
+            let entity = parse_body(&body)?;
+            let stored = editor
+                .apply(&state, API_ACTOR, collection, Edit::Update(slug, entity))
+                .await?;
+            Ok(HttpResponse::Ok().json(json!({ "data": stored })))
+        }
+        .await,
+    )
+}
+
+pub(crate) async fn history(
+    path: web::Path<(String, String)>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    respond(
+        async {
+            let (collection, slug) = (self::collection(&path.0)?, &path.1);
+            let entries = editor.history(collection, slug).await?;
+            Ok(HttpResponse::Ok().json(json!({ "data": entries })))
+        }
+        .await,
+    )
+}
+
+pub(crate) async fn revert(
+    path: web::Path<(String, String, String)>,
+    editor: web::Data<ContentEditor>,
+    state: web::Data<SiteState>,
+) -> HttpResponse {
+    respond(
+        async {
+            let (collection, slug, id) = path.into_inner();
+            let collection = self::collection(&collection)?;
+            let stored = editor
+                .revert(&state, API_ACTOR, collection, &slug, &id)
+                .await?;
+            Ok(HttpResponse::Ok().json(json!({ "data": stored })))
+        }
+        .await,
+    )
+}
+
+pub(crate) async fn delete(
+    path: web::Path<(String, String)>,
+    editor: web::Data<ContentEditor>,
+    state: web::Data<SiteState>,
+) -> HttpResponse {
+    respond(
+        async {
+            let (collection, slug) = path.into_inner();
+            let collection = self::collection(&collection)?;
+            editor
+                .apply(&state, API_ACTOR, collection, Edit::Delete(slug))
+                .await?;
+            Ok(HttpResponse::NoContent().finish())
+        }
+        .await,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use actix_web::{
+        http::Method,
+        test::{call_service, init_service, read_body, TestRequest},
+        App,
+    };
+    use std::path::Path;
+
+    const TOKEN: &str = "s3cret";
+
+    fn seed() -> Value {
+        json!({
+            "version": "rustdev-hub-seed-v7.0",
+            "tools": [{"slug": "anchor", "name": "Anchor"}],
+        })
+    }
+
+    fn stored(path: &Path) -> Value {
+        serde_json::from_slice(&std::fs::read(path).unwrap()).unwrap()
+    }
+
+    fn api(method: Method, uri: &str) -> TestRequest {
+        TestRequest::default()
+            .method(method)
+            .uri(uri)
+            .insert_header((header::HOST, "localhost"))
+            .insert_header((header::AUTHORIZATION, format!("Bearer {TOKEN}")))
+    }
+
+    #[actix_web::test]
+    async fn requests_without_the_token_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = testing::site_state(&dir.path().join("seed.json"), &seed()).await;
+        let app = init_service(
+            App::new()
+                .app_data(state.clone())
+                .app_data(testing::editor(&state, &dir.path().join("audit.jsonl")))
+                .service(
+                    web::scope("/admin/api")
+                        .app_data(web::Data::new(ApiToken::new(TOKEN)))
+                        .wrap_fn(require_token)
+                        .configure(routes),
+                ),
+        )
+        .await;
+        let get = |authorization: Option<&str>| {
+            let mut req = TestRequest::get()
+                .uri("/admin/api/tools")
+                .insert_header((header::HOST, "localhost"));
+            if let Some(authorization) = authorization {
+                req = req.insert_header((header::AUTHORIZATION, authorization));
+            }
+            req.to_request()
+        };
+
+        for authorization in [
+            None,
+            Some("Bearer wrong"),
+            Some(TOKEN),
+            Some("Basic s3cret"),
+        ] {
+            let res = call_service(&app, get(authorization)).await;
+            assert_eq!(res.status(), StatusCode::UNAUTHORIZED, "{authorization:?}");
+            assert_eq!(
+                res.headers().get(header::CACHE_CONTROL).unwrap(),
+                "no-store"
+            );
+            assert!(res.headers().contains_key(header::WWW_AUTHENTICATE));
+        }
+        let res = call_service(&app, get(Some("Bearer s3cret"))).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let res = call_service(
+            &app,
+            TestRequest::get()
+                .uri("/admin/api/tools")
+                .insert_header((header::HOST, "evil.example"))
+                .insert_header((header::AUTHORIZATION, "Bearer s3cret"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn invalid_edits_leave_the_seed_untouched() {
+        let dir = tempfile::tempdir().unwrap();
+        let seed_path = dir.path().join("seed.json");
+        let audit_path = dir.path().join("audit.jsonl");
+        let state = testing::site_state(&seed_path, &seed()).await;
+        let app = init_service(
+            App::new()
+                .app_data(state.clone())
+                .app_data(testing::editor(&state, &audit_path))
+                .service(
+                    web::scope("/admin/api")
+                        .app_data(web::Data::new(ApiToken::new(TOKEN)))
+                        .wrap_fn(require_token)
+                        .configure(routes),
+                ),
+        )
+        .await;
+        let before = std::fs::read(&seed_path).unwrap();
+
+        for (uri, body, status) in [
+            ("/admin/api/tools", "{not json", StatusCode::BAD_REQUEST),
+            (
+                "/admin/api/tools",
+                r#"["anchor"]"#,
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+            (
+                "/admin/api/tools",
+                r#"{"slug": "Bad Slug", "name": "Bad"}"#,
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+            (
+                "/admin/api/tools",
+                r#"{"slug": "no-name"}"#,
+                StatusCode::UNPROCESSABLE_ENTITY,
+            ),
+            (
+                "/admin/api/tools",
+                r#"{"slug": "anchor", "name": "Again"}"#,
+                StatusCode::CONFLICT,
+            ),
+            (
+                "/admin/api/nonsense",
+                r#"{"slug": "x"}"#,
+                StatusCode::NOT_FOUND,
+            ),
+        ] {
+            let res =
+                call_service(&app, api(Method::POST, uri).set_payload(body).to_request()).await;
+            assert_eq!(res.status(), status, "{body}");
+        }
+        let res = call_service(
+            &app,
+            api(Method::PUT, "/admin/api/tools/anchor")
+                .set_payload(r#"{"slug": "renamed", "name": "Anchor"}"#)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        assert_eq!(std::fs::read(&seed_path).unwrap(), before);
+        assert!(!dir.path().join("seed.json.bak").exists());
+        assert!(!audit_path.exists());
+    }
+
+    #[actix_web::test]
+    async fn edits_are_undone_when_the_audit_log_cannot_record_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let seed_path = dir.path().join("seed.json");
+        let state = testing::site_state(&seed_path, &seed()).await;
+        // A directory cannot be appended to.
+        let editor = testing::editor(&state, dir.path());
+
+        let err = editor
+            .apply(
+                &state,
+                "ed",
+                Collection::Tools,
+                Edit::Update("anchor".into(), json!({"name": "Anchor X"})),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(err.message().starts_with("Not saved"), "{}", err.message());
+        assert_eq!(stored(&seed_path), seed());
+        let served = state.snapshot().unwrap();
+        assert_eq!(served.rustdev.tools[0].name, "Anchor");
+    }
+
+    #[actix_web::test]
+    async fn api_edits_are_written_and_served() {
+        let dir = tempfile::tempdir().unwrap();
+        let seed_path = dir.path().join("seed.json");
+        let audit_path = dir.path().join("audit.jsonl");
+        let state = testing::site_state(&seed_path, &seed()).await;
+        let app = init_service(
+            App::new()
+                .app_data(state.clone())
+                .app_data(testing::editor(&state, &audit_path))
+                .service(
+                    web::scope("/admin/api")
+                        .app_data(web::Data::new(ApiToken::new(TOKEN)))
+                        .wrap_fn(require_token)
+                        .configure(routes),
+                )
+                .service(web::resource("/tools/{slug}").route(web::get().to(crate::page))),
+        )
+        .await;
+        let page = || {
+            TestRequest::get()
+                .uri("/tools/borsh")
+                .insert_header((header::HOST, "localhost"))
+                .to_request()
+        };
+        let edit = |method, uri: &str, body: &str| {
+            api(method, uri).set_payload(body.to_string()).to_request()
+        };
+        assert_eq!(
+            call_service(&app, page()).await.status(),
+            StatusCode::NOT_FOUND
+        );
+
+        let res = call_service(
+            &app,
+            edit(
+                Method::POST,
+                "/admin/api/tools",
+                r#"{"slug": "borsh", "name": "Borsh"}"#,
+            ),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::CREATED);
+        assert_eq!(
+            res.headers().get(header::LOCATION).unwrap(),
+            "/admin/api/tools/borsh"
+        );
+        assert!(dir.path().join("seed.json.bak").exists());
+        let res = call_service(&app, page()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let html = String::from_utf8(read_body(res).await.to_vec()).unwrap();
+        assert!(html.contains("Borsh"));
+
+        let res = call_service(
+            &app,
+            edit(
+                Method::PUT,
+                "/admin/api/tools/borsh",
+                r#"{"name": "Borsh Serializer"}"#,
+            ),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(stored(&seed_path)["tools"][1]["name"], "Borsh Serializer");
+        let html =
+            String::from_utf8(read_body(call_service(&app, page()).await).await.to_vec()).unwrap();
+        assert!(html.contains("Borsh Serializer"));
+
+        let res = call_service(&app, edit(Method::DELETE, "/admin/api/tools/borsh", "")).await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert_eq!(stored(&seed_path), seed());
+        assert_eq!(
+            call_service(&app, page()).await.status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            stored(&dir.path().join("seed.json.bak"))["tools"][1]["name"],
+            "Borsh Serializer"
+        );
+
+        let history = std::fs::read_to_string(&audit_path).unwrap();
+        let actions: Vec<String> = history
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap()["action"].to_string())
+            .collect();
+        assert_eq!(actions, [r#""create""#, r#""update""#, r#""delete""#]);
+    }
+}
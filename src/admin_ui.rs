@@ -0,0 +1,1390 @@
+use actix_web::{http::StatusCode, web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    admin::{self, ContentEditor, Edit, EditError},
+    auth::{self, see_other, Session},
+    content::{slug_of, Collection, COLLECTIONS},
+    formats::heading_for,
+    git::HISTORY_LIMIT,
+    lint::{self, LintConfig},
+    load_promo_content, migrate, page_sources,
+    security::CspNonce,
+    starting_up,
+    store::ContentStore,
+    RustDevContent, SiteState, HTML_CONTENT_TYPE, PROMO_PATH,
+};
+
+/// Form inputs for entity fields are named with this prefix, so they cannot
+/// clash with the form's own `csrf` and `_slug`.
+const FIELD_PREFIX: &str = "f.";
+/// String fields edited in a multi-line box even when every value is short.
+const LONG_TEXT_KEYS: [&str; 10] = [
+    "about",
+    "body_md",
+    "date_notes",
+    "deck",
+    "description",
+    "one_liner",
+    "schedule_note",
+    "summary",
+    "teaser",
+    "why_it_matters",
+];
+/// String maps whose keys editors choose freely; other string maps get one
+/// input per known key.
+const MAP_KEYS: [&str; 3] = ["links", "official_links", "updates"];
+/// Strings at least this long are edited in a multi-line box.
+const LONG_TEXT_LEN: usize = 100;
+
+/// Renders an admin template with the site's handlebars registry. The
+/// context gains a fresh `csp_nonce` for inline scripts.
+pub(crate) fn render(
+    state: &SiteState,
+    status: StatusCode,
+    template: &str,
+    context: &Value,
+) -> HttpResponse {
+    let Some(site) = state.snapshot() else {
+        return starting_up();
+    };
+    let nonce = CspNonce::new();
+    let mut context = context.clone();
+    if let Some(fields) = context.as_object_mut() {
+        fields.insert("csp_nonce".into(), nonce.as_str().into());
+    }
+    match site.hb.render(template, &context) {
+        Ok(html) => {
+            let mut res = HttpResponse::build(status)
+                .content_type(HTML_CONTENT_TYPE)
+                .body(html);
+            nonce.attach(&mut res);
+            res
+        }
+        Err(err) => {
+            tracing::error!(%err, template, "admin template failed");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Context every signed-in page shares: the editor, their CSRF token and
+/// the collection navigation.
+fn page_context(session: &Session, title: &str) -> Map<String, Value> {
+    let collections: Vec<Value> = COLLECTIONS
+        .iter()
+        .map(|c| json!({ "path": c.path(), "title": c.title() }))
+        .collect();
+    let mut context = Map::new();
+    context.insert("title".into(), title.into());
+    context.insert("user".into(), session.user.clone().into());
+    context.insert("csrf".into(), session.csrf.clone().into());
+    context.insert("collections".into(), collections.into());
+    context
+}
+
+fn message(state: &SiteState, session: &Session, status: StatusCode, text: &str) -> HttpResponse {
+    let mut context = page_context(session, status.canonical_reason().unwrap_or("Error"));
+    context.insert("message".into(), text.into());
+    render(state, status, "admin/message", &Value::Object(context))
+}
+
+/// The widget an entity field is edited with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Text,
+    LongText,
+    Number,
+    Bool,
+    /// A list of strings, one per line.
+    List,
+    /// Label slugs, picked from the taxonomy.
+    Labels,
+    /// One label slug, picked from the taxonomy.
+    PrimaryLabel,
+    /// Free-form `name = value` lines.
+    Map,
+    /// `featured_media`: `name | title | url` lines.
+    Media,
+    /// A string map with one input per known key.
+    Group,
+    /// Anything else, edited as JSON.
+    Json,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Text => "text",
+            Kind::LongText => "textarea",
+            Kind::Number => "number",
+            Kind::Bool => "bool",
+            Kind::List => "list",
+            Kind::Labels => "labels",
+            Kind::PrimaryLabel => "primary_label",
+            Kind::Map => "map",
+            Kind::Media => "media",
+            Kind::Group => "group",
+            Kind::Json => "json",
+        }
+    }
+
+    fn hint(self) -> Option<&'static str> {
+        match self {
+            Kind::List => Some("One per line."),
+            Kind::Map => Some("One per line: name = URL"),
+            Kind::Media => Some("One per line: name | title | URL"),
+            Kind::Json => Some("JSON."),
+            _ => None,
+        }
+    }
+}
+
+fn is_string_list(value: &Value) -> bool {
+    value
+        .as_array()
+        .is_some_and(|items| items.iter().all(Value::is_string))
+}
+
+fn is_string_map(value: &Value) -> bool {
+    value
+        .as_object()
+        .is_some_and(|map| map.values().all(Value::is_string))
+}
+
+fn is_media(value: &Value) -> bool {
+    value.as_object().is_some_and(|map| {
+        map.values().all(|item| {
+            item.as_object().is_some_and(|item| {
+                item.iter().all(|(key, value)| {
+                    matches!(key.as_str(), "title" | "url") && value.is_string()
+                })
+            })
+        })
+    })
+}
+
+/// One field of a collection's form.
+#[derive(Debug)]
+struct FieldSpec {
+    key: String,
+    kind: Kind,
+    /// Inputs of a `Group`.
+    subkeys: Vec<String>,
+}
+
+impl FieldSpec {
+    /// Picks the widget from the values the collection's entities hold.
+    fn infer(key: &str, values: &[&Value]) -> Self {
+        let all = |check: fn(&Value) -> bool| values.iter().all(|v| check(v));
+        let kind = match key {
+            "slug" => Kind::Text,
+            "labels" if all(is_string_list) => Kind::Labels,
+            "primary_label" if all(Value::is_string) => Kind::PrimaryLabel,
+            "featured_media" if all(is_media) => Kind::Media,
+            _ if values.is_empty() => Kind::Text,
+            _ if all(Value::is_string) => {
+                let long = values
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .any(|s| s.len() >= LONG_TEXT_LEN || s.contains('\n'));
+                if long || LONG_TEXT_KEYS.contains(&key) {
+                    Kind::LongText
+                } else {
+                    Kind::Text
+                }
+            }
+            _ if all(Value::is_boolean) => Kind::Bool,
+            _ if all(Value::is_number) => Kind::Number,
+            _ if all(is_string_list) => Kind::List,
+            _ if all(is_string_map) && MAP_KEYS.contains(&key) => Kind::Map,
+            _ if all(is_string_map) => Kind::Group,
+            _ => Kind::Json,
+        };
+        let mut subkeys: Vec<String> = Vec::new();
+        if kind == Kind::Group {
+            for key in values
+                .iter()
+                .filter_map(|v| v.as_object())
+                .flat_map(Map::keys)
+            {
+                if !subkeys.contains(key) {
+                    subkeys.push(key.clone());
+                }
+            }
+        }
+        Self {
+            key: key.to_string(),
+            kind,
+            subkeys,
+        }
+    }
+
+    fn input_name(&self) -> String {
+        format!("{FIELD_PREFIX}{}", self.key)
+    }
+
+    fn subkey_name(&self, subkey: &str) -> String {
+        format!("{FIELD_PREFIX}{}.{subkey}", self.key)
+    }
+}
+
+/// The form's fields: every key the collection's entities use, `slug` first,
+/// in the order they first appear.
+fn field_specs(items: &[Value]) -> Vec<FieldSpec> {
+    let mut keys: Vec<&str> = vec!["slug"];
+    for key in items
+        .iter()
+        .filter_map(Value::as_object)
+        .flat_map(Map::keys)
+    {
+        if !keys.contains(&key.as_str()) {
+            keys.push(key);
+        }
+    }
+    keys.into_iter()
+        .map(|key| {
+            let values: Vec<&Value> = items
+                .iter()
+                .filter_map(|item| item.get(key))
+                .filter(|v| !v.is_null())
+                .collect();
+            FieldSpec::infer(key, &values)
+        })
+        .collect()
+}
+
+/// A field as the form shows it, either from a stored entity or as the
+/// editor submitted it.
+#[derive(Debug, Default)]
+struct RawField {
+    text: String,
+    /// Picked options of a `Labels` field.
+    selected: Vec<String>,
+    checked: bool,
+    /// Inputs of a `Group`, by subkey.
+    sub: Vec<(String, String)>,
+}
+
+impl RawField {
+    fn from_value(spec: &FieldSpec, value: Option<&Value>) -> Self {
+        let Some(value) = value.filter(|v| !v.is_null()) else {
+            return Self {
+                sub: spec
+                    .subkeys
+                    .iter()
+                    .map(|k| (k.clone(), String::new()))
+                    .collect(),
+                ..Self::default()
+            };
+        };
+        let strings = |value: &Value| -> Vec<String> {
+            value
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::to_string)
+                .collect()
+        };
+        let text = match spec.kind {
+            Kind::Text | Kind::LongText | Kind::PrimaryLabel => {
+                value.as_str().unwrap_or_default().to_string()
+            }
+            Kind::Number => value.to_string(),
+            Kind::List => strings(value).join("\n"),
+            Kind::Map => value
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(name, url)| format!("{name} = {}", url.as_str().unwrap_or_default()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Kind::Media => value
+                .as_object()
+                .into_iter()
+                .flatten()
+                .map(|(name, item)| {
+                    let field = |key| item.get(key).and_then(Value::as_str).unwrap_or_default();
+                    format!("{name} | {} | {}", field("title"), field("url"))
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Kind::Json => serde_json::to_string_pretty(value).unwrap_or_default(),
+            Kind::Bool | Kind::Labels | Kind::Group => String::new(),
+        };
+        Self {
+            text,
+            selected: strings(value),
+            checked: value.as_bool().unwrap_or(false),
+            sub: spec
+                .subkeys
+                .iter()
+                .map(|k| {
+                    let v = value.get(k).and_then(Value::as_str).unwrap_or_default();
+                    (k.clone(), v.to_string())
+                })
+                .collect(),
+        }
+    }
+
+    fn from_form(spec: &FieldSpec, form: &[(String, String)]) -> Self {
+        let name = spec.input_name();
+        let values: Vec<&String> = form
+            .iter()
+            .filter(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+            .collect();
+        Self {
+            text: values
+                .first()
+                .map(|v| v.replace("\r\n", "\n"))
+                .unwrap_or_default(),
+            selected: values.iter().map(|v| v.to_string()).collect(),
+            checked: !values.is_empty(),
+            sub: spec
+                .subkeys
+                .iter()
+                .map(|subkey| {
+                    let name = spec.subkey_name(subkey);
+                    let value = form
+                        .iter()
+                        .find(|(key, _)| *key == name)
+                        .map(|(_, value)| value.trim().to_string())
+                        .unwrap_or_default();
+                    (subkey.clone(), value)
+                })
+                .collect(),
+        }
+    }
+
+    /// The value to store, or `None` to leave the key out. `previous` is the
+    /// stored value, so an unticked box only writes `false` over a value
+    /// that was there.
+    fn parse(&self, spec: &FieldSpec, previous: Option<&Value>) -> Result<Option<Value>, String> {
+        let lines = || {
+            self.text
+                .lines()
+                .map(str::trim)
+                .enumerate()
+                .filter(|(_, line)| !line.is_empty())
+        };
+        let value = match spec.kind {
+            Kind::Text | Kind::PrimaryLabel => {
+                let text = self.text.trim();
+                (!text.is_empty()).then(|| json!(text))
+            }
+            Kind::LongText => {
+                let text = self.text.trim_end();
+                (!text.trim().is_empty()).then(|| json!(text))
+            }
+            Kind::Number => {
+                let text = self.text.trim();
+                if text.is_empty() {
+                    None
+                } else if let Ok(n) = text.parse::<i64>() {
+                    Some(json!(n))
+                } else {
+                    let n: f64 = text.parse().map_err(|_| "Must be a number.".to_string())?;
+                    Some(json!(n))
+                }
+            }
+            Kind::Bool => (self.checked || previous.is_some()).then(|| json!(self.checked)),
+            Kind::List => {
+                let items: Vec<&str> = lines().map(|(_, line)| line).collect();
+                (!items.is_empty()).then(|| json!(items))
+            }
+            Kind::Labels => (!self.selected.is_empty()).then(|| json!(self.selected)),
+            Kind::Map => {
+                let mut map = Map::new();
+                for (index, line) in lines() {
+                    let (name, url) = line
+                        .split_once('=')
+                        .ok_or_else(|| format!("Line {}: expected `name = URL`.", index + 1))?;
+                    map.insert(name.trim().to_string(), json!(url.trim()));
+                }
+                (!map.is_empty()).then_some(Value::Object(map))
+            }
+            Kind::Media => {
+                let mut map = Map::new();
+                for (index, line) in lines() {
+                    let parts: Vec<&str> = line.splitn(3, '|').map(str::trim).collect();
+                    let [name, title, url] = parts[..] else {
+                        return Err(format!(
+                            "Line {}: expected `name | title | URL`.",
+                            index + 1
+                        ));
+                    };
+                    if name.is_empty() || url.is_empty() {
+                        return Err(format!("Line {}: name and URL are required.", index + 1));
+                    }
+                    let mut item = Map::new();
+                    if !title.is_empty() {
+                        item.insert("title".into(), json!(title));
+                    }
+                    item.insert("url".into(), json!(url));
+                    map.insert(name.to_string(), Value::Object(item));
+                }
+                (!map.is_empty()).then_some(Value::Object(map))
+            }
+            Kind::Group => {
+                let map: Map<String, Value> = self
+                    .sub
+                    .iter()
+                    .filter(|(_, value)| !value.is_empty())
+                    .map(|(key, value)| (key.clone(), json!(value)))
+                    .collect();
+                (!map.is_empty()).then_some(Value::Object(map))
+            }
+            Kind::Json => {
+                let text = self.text.trim();
+                if text.is_empty() {
+                    None
+                } else {
+                    Some(serde_json::from_str(text).map_err(|err| format!("Invalid JSON: {err}"))?)
+                }
+            }
+        };
+        Ok(value)
+    }
+}
+
+/// An editable entity's form: its fields as last shown, with any messages.
+struct EntityForm {
+    collection: Collection,
+    /// The stored entity's slug; `None` for a new one.
+    slug: Option<String>,
+    specs: Vec<FieldSpec>,
+    raw: Vec<RawField>,
+    errors: Vec<Option<String>>,
+    labels: Vec<(String, String)>,
+}
+
+impl EntityForm {
+    fn new(collection: Collection, seed: &Value, slug: Option<String>) -> Self {
+        let specs = field_specs(collection.items(seed));
+        let labels = Collection::Labels
+            .items(seed)
+            .iter()
+            .filter_map(|label| {
+                let slug = slug_of(label)?;
+                let name = label.get("name").and_then(Value::as_str).unwrap_or(slug);
+                Some((slug.to_string(), name.to_string()))
+            })
+            .collect();
+        Self {
+            collection,
+            slug,
+            errors: specs.iter().map(|_| None).collect(),
+            raw: Vec::new(),
+            specs,
+            labels,
+        }
+    }
+
+    fn with_entity(mut self, entity: Option<&Value>) -> Self {
+        self.raw = self
+            .specs
+            .iter()
+            .map(|spec| RawField::from_value(spec, entity.and_then(|e| e.get(&spec.key))))
+            .collect();
+        self
+    }
+
+    fn with_submission(mut self, form: &[(String, String)]) -> Self {
+        self.raw = self
+            .specs
+            .iter()
+            .map(|spec| RawField::from_form(spec, form))
+            .collect();
+        self
+    }
+
+    /// Builds the entity from the submission on top of `stored`, keeping
+    /// its key order. Field errors are kept for the form.
+    fn entity(&mut self, stored: Option<&Value>) -> Option<Value> {
+        let mut entity = stored
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default();
+        let mut valid = true;
+        for ((spec, raw), error) in self.specs.iter().zip(&self.raw).zip(&mut self.errors) {
+            match raw.parse(spec, stored.and_then(|s| s.get(&spec.key))) {
+                Ok(Some(value)) => {
+                    entity.insert(spec.key.clone(), value);
+                }
+                Ok(None) => {
+                    entity.shift_remove(&spec.key);
+                }
+                Err(message) => {
+                    *error = Some(message);
+                    valid = false;
+                }
+            }
+        }
+        valid.then_some(Value::Object(entity))
+    }
+
+    fn context(&self, session: &Session, notice: Option<&str>, error: Option<&str>) -> Value {
+        let collection = self.collection;
+        let heading = match &self.slug {
+            Some(slug) => format!("Edit {slug}"),
+            None => format!("New in {}", collection.title()),
+        };
+        let fields: Vec<Value> = self
+            .specs
+            .iter()
+            .zip(&self.raw)
+            .zip(&self.errors)
+            .map(|((spec, raw), error)| {
+                let options: Vec<Value> = self
+                    .labels
+                    .iter()
+                    .map(|(slug, name)| {
+                        let selected = match spec.kind {
+                            Kind::Labels => raw.selected.contains(slug),
+                            _ => raw.text.trim() == slug,
+                        };
+                        json!({ "value": slug, "label": name, "selected": selected })
+                    })
+                    .collect();
+                let subfields: Vec<Value> = raw
+                    .sub
+                    .iter()
+                    .map(|(key, value)| {
+                        json!({
+                            "name": spec.subkey_name(key),
+                            "label": heading_for(key),
+                            "value": value,
+                        })
+                    })
+                    .collect();
+                json!({
+                    "name": spec.input_name(),
+                    "label": heading_for(&spec.key),
+                    "kind": spec.kind.name(),
+                    "value": raw.text,
+                    "checked": raw.checked,
+                    "options": options,
+                    "subfields": subfields,
+                    "hint": spec.kind.hint(),
+                    "error": error,
+                    "readonly": spec.key == "slug" && self.slug.is_some(),
+                })
+            })
+            .collect();
+
+        let mut context = page_context(session, &heading);
+        context.insert("heading".into(), heading.into());
+        context.insert(
+            "collection".into(),
+            json!({ "path": collection.path(), "title": collection.title() }),
+        );
+        context.insert("slug".into(), json!(self.slug));
+        context.insert(
+            "action".into(),
+            match &self.slug {
+                Some(slug) => format!("/admin/{}/{slug}", collection.path()),
+                None => format!("/admin/new/{}", collection.path()),
+            }
+            .into(),
+        );
+        context.insert(
+            "preview".into(),
+            collection
+                .has_pages()
+                .then(|| format!("/admin/preview/{}", collection.path()))
+                .into(),
+        );
+        context.insert(
+            "view".into(),
+            self.slug
+                .as_deref()
+                .and_then(|slug| collection.page_path(slug))
+                .into(),
+        );
+        context.insert("fields".into(), fields.into());
+        context.insert("notice".into(), notice.into());
+        context.insert("error".into(), error.into());
+        Value::Object(context)
+    }
+
+    fn render(
+        &self,
+        state: &SiteState,
+        session: &Session,
+        status: StatusCode,
+        notice: Option<&str>,
+        error: Option<&str>,
+    ) -> HttpResponse {
+        render(
+            state,
+            status,
+            "admin/edit",
+            &self.context(session, notice, error),
+        )
+    }
+}
+
+/// What every admin handler starts from: the editor's session and the
+/// collection named in the path.
+fn begin(req: &HttpRequest, collection: &str) -> Result<(Session, Collection), Box<HttpResponse>> {
+    let session = auth::session(req).ok_or_else(|| Box::new(see_other("/admin/login")))?;
+    let collection = Collection::from_path(collection)
+        .ok_or_else(|| Box::new(HttpResponse::NotFound().finish()))?;
+    Ok((session, collection))
+}
+
+fn form_value<'a>(form: &'a [(String, String)], name: &str) -> &'a str {
+    form.iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+        .unwrap_or_default()
+}
+
+fn title_of(entity: &Value) -> &str {
+    ["name", "title"]
+        .iter()
+        .find_map(|key| entity.get(key).and_then(Value::as_str))
+        .or_else(|| slug_of(entity))
+        .unwrap_or_default()
+}
+
+/// The editor's pages, mounted under `/admin` behind
+/// `auth::require_session`.
+pub(crate) fn routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::resource(["", "/"]).route(web::get().to(dashboard)))
+        .service(
+            web::resource("/login")
+                .route(web::get().to(auth::login_form))
+                .route(web::post().to(auth::login)),
+        )
+        .service(web::resource("/logout").route(web::post().to(auth::logout)))
+        .service(
+            web::resource("/new/{collection}")
+                .route(web::get().to(new_form))
+                .route(web::post().to(create)),
+        )
+        .service(web::resource("/history").route(web::get().to(commit_log)))
+        .service(web::resource("/health").route(web::get().to(content_health)))
+        .service(web::resource("/preview/{collection}").route(web::post().to(preview)))
+        .service(web::resource("/{collection}").route(web::get().to(list)))
+        .service(
+            web::resource("/{collection}/{slug}")
+                .route(web::get().to(edit_form))
+                .route(web::post().to(update)),
+        )
+        .service(web::resource("/{collection}/{slug}/delete").route(web::post().to(delete)))
+        .service(web::resource("/{collection}/{slug}/history").route(web::get().to(history)))
+        .service(
+            web::resource("/{collection}/{slug}/history/{id}/revert").route(web::post().to(revert)),
+        );
+}
+
+pub(crate) async fn dashboard(
+    req: HttpRequest,
+    state: web::Data<SiteState>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    let Some(session) = auth::session(&req) else {
+        return see_other("/admin/login");
+    };
+    let seed = match editor.read_seed().await {
+        Ok(seed) => seed,
+        Err(err) => {
+            return message(
+                &state,
+                &session,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &err.to_string(),
+            )
+        }
+    };
+    let counts: Vec<Value> = COLLECTIONS
+        .iter()
+        .map(|c| json!({ "path": c.path(), "title": c.title(), "count": c.items(&seed).len() }))
+        .collect();
+    let mut context = page_context(&session, "Content");
+    context.insert("counts".into(), counts.into());
+    context.insert("source".into(), editor.describe().into());
+    context.insert("git".into(), editor.git().is_some().into());
+    render(
+        &state,
+        StatusCode::OK,
+        "admin/index",
+        &Value::Object(context),
+    )
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct ListQuery {
+    deleted: Option<String>,
+}
+
+pub(crate) async fn list(
+    path: web::Path<String>,
+    query: web::Query<ListQuery>,
+    req: HttpRequest,
+    state: web::Data<SiteState>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    let (session, collection) = match begin(&req, &path) {
+        Ok(begun) => begun,
+        Err(res) => return *res,
+    };
+    let seed = match editor.read_seed().await {
+        Ok(seed) => seed,
+        Err(err) => {
+            return message(
+                &state,
+                &session,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &err.to_string(),
+            )
+        }
+    };
+
+    #[derive(Serialize)]
+    struct Row<'a> {
+        slug: &'a str,
+        title: &'a str,
+        view: Option<String>,
+    }
+    let rows: Vec<Row> = collection
+        .items(&seed)
+        .iter()
+        .filter_map(|item| {
+            let slug = slug_of(item)?;
+            Some(Row {
+                slug,
+                title: title_of(item),
+                view: collection.page_path(slug),
+            })
+        })
+        .collect();
+    let mut context = page_context(&session, collection.title());
+    context.insert(
+        "collection".into(),
+        json!({ "path": collection.path(), "title": collection.title() }),
+    );
+    context.insert("rows".into(), json!(rows));
+    context.insert("deleted".into(), query.deleted.clone().into());
+    render(
+        &state,
+        StatusCode::OK,
+        "admin/collection",
+        &Value::Object(context),
+    )
+}
+
+pub(crate) async fn new_form(
+    path: web::Path<String>,
+    req: HttpRequest,
+    state: web::Data<SiteState>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    let (session, collection) = match begin(&req, &path) {
+        Ok(begun) => begun,
+        Err(res) => return *res,
+    };
+    match editor.read_seed().await {
+        Ok(seed) => EntityForm::new(collection, &seed, None)
+            .with_entity(None)
+            .render(&state, &session, StatusCode::OK, None, None),
+        Err(err) => message(
+            &state,
+            &session,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &err.to_string(),
+        ),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct EditQuery {
+    saved: Option<String>,
+}
+
+pub(crate) async fn edit_form(
+    path: web::Path<(String, String)>,
+    query: web::Query<EditQuery>,
+    req: HttpRequest,
+    state: web::Data<SiteState>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    let (collection, slug) = path.into_inner();
+    let (session, collection) = match begin(&req, &collection) {
+        Ok(begun) => begun,
+        Err(res) => return *res,
+    };
+    let seed = match editor.read_seed().await {
+        Ok(seed) => seed,
+        Err(err) => {
+            return message(
+                &state,
+                &session,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &err.to_string(),
+            )
+        }
+    };
+    let Some(entity) = collection
+        .items(&seed)
+        .iter()
+        .find(|item| slug_of(item) == Some(slug.as_str()))
+    else {
+        return message(
+            &state,
+            &session,
+            StatusCode::NOT_FOUND,
+            &format!("No {} with slug {slug}.", collection.path()),
+        );
+    };
+    let notice = query
+        .saved
+        .is_some()
+        .then_some("Saved. The change is live.");
+    EntityForm::new(collection, &seed, Some(slug.clone()))
+        .with_entity(Some(entity))
+        .render(&state, &session, StatusCode::OK, notice, None)
+}
+
+/// Saves a submitted form: `slug` is `None` for a new entity. Invalid input
+/// shows the form again with messages.
+async fn save(
+    session: Session,
+    collection: Collection,
+    slug: Option<String>,
+    form: Vec<(String, String)>,
+    state: &SiteState,
+    editor: &ContentEditor,
+) -> HttpResponse {
+    if !session.csrf_ok(form_value(&form, "csrf")) {
+        return message(
+            state,
+            &session,
+            StatusCode::FORBIDDEN,
+            "The form expired. Go back, reload the page and try again.",
+        );
+    }
+    let seed = match editor.read_seed().await {
+        Ok(seed) => seed,
+        Err(err) => {
+            return message(
+                state,
+                &session,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &err.to_string(),
+            )
+        }
+    };
+    let stored = slug.as_deref().and_then(|slug| {
+        collection
+            .items(&seed)
+            .iter()
+            .find(|item| slug_of(item) == Some(slug))
+            .cloned()
+    });
+    let mut entity_form = EntityForm::new(collection, &seed, slug.clone()).with_submission(&form);
+    let Some(entity) = entity_form.entity(stored.as_ref()) else {
+        return entity_form.render(
+            state,
+            &session,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            None,
+            Some("Some fields need attention."),
+        );
+    };
+
+    let edit = match &slug {
+        Some(slug) => Edit::Update(slug.clone(), entity),
+        None => Edit::Create(entity),
+    };
+    match editor.apply(state, &session.user, collection, edit).await {
+        Ok(stored) => see_other(&format!(
+            "/admin/{}/{}?saved=1",
+            collection.path(),
+            slug_of(&stored).unwrap_or_default()
+        )),
+        Err(err) => entity_form.render(state, &session, err.status(), None, Some(&err.message())),
+    }
+}
+
+pub(crate) async fn create(
+    path: web::Path<String>,
+    form: web::Form<Vec<(String, String)>>,
+    req: HttpRequest,
+    state: web::Data<SiteState>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    match begin(&req, &path) {
+        Ok((session, collection)) => {
+            save(
+                session,
+                collection,
+                None,
+                form.into_inner(),
+                &state,
+                &editor,
+            )
+            .await
+        }
+        Err(res) => *res,
+    }
+}
+
+pub(crate) async fn update(
+    path: web::Path<(String, String)>,
+    form: web::Form<Vec<(String, String)>>,
+    req: HttpRequest,
+    state: web::Data<SiteState>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    let (collection, slug) = path.into_inner();
+    match begin(&req, &collection) {
+        Ok((session, collection)) => {
+            save(
+                session,
+                collection,
+                Some(slug),
+                form.into_inner(),
+                &state,
+                &editor,
+            )
+            .await
+        }
+        Err(res) => *res,
+    }
+}
+
+pub(crate) async fn delete(
+    path: web::Path<(String, String)>,
+    form: web::Form<Vec<(String, String)>>,
+    req: HttpRequest,
+    state: web::Data<SiteState>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    let (collection, slug) = path.into_inner();
+    let (session, collection) = match begin(&req, &collection) {
+        Ok(begun) => begun,
+        Err(res) => return *res,
+    };
+    if !session.csrf_ok(form_value(&form, "csrf")) {
+        return message(
+            &state,
+            &session,
+            StatusCode::FORBIDDEN,
+            "The form expired. Go back, reload the page and try again.",
+        );
+    }
+    if form_value(&form, "confirm").is_empty() {
+        return message(
+            &state,
+            &session,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Tick the confirmation box to delete.",
+        );
+    }
+    match editor
+        .apply(
+            &state,
+            &session.user,
+            collection,
+            Edit::Delete(slug.clone()),
+        )
+        .await
+    {
+        Ok(_) => {
+            let query = url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("deleted", &slug)
+                .finish();
+            see_other(&format!("/admin/{}?{query}", collection.path()))
+        }
+        Err(err) => message(&state, &session, err.status(), &err.message()),
+    }
+}
+
+/// The commits that changed the seed, for git storage.
+pub(crate) async fn commit_log(
+    req: HttpRequest,
+    state: web::Data<SiteState>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    let Some(session) = auth::session(&req) else {
+        return see_other("/admin/login");
+    };
+    let Some(git) = editor.git().cloned() else {
+        return message(
+            &state,
+            &session,
+            StatusCode::NOT_FOUND,
+            "History is kept in git. Set CONTENT_GIT_REPO to serve content from a repository.",
+        );
+    };
+    let (source, read_only) = (git.describe(), git.read_only().is_some());
+    let commits = match tokio::task::spawn_blocking(move || git.log(HISTORY_LIMIT)).await {
+        Ok(Ok(commits)) => commits,
+        Ok(Err(err)) => {
+            return message(
+                &state,
+                &session,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &err.to_string(),
+            )
+        }
+        Err(err) => {
+            return message(
+                &state,
+                &session,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &err.to_string(),
+            )
+        }
+    };
+    let mut context = page_context(&session, "History");
+    context.insert("source".into(), source.into());
+    context.insert("read_only".into(), read_only.into());
+    context.insert("commits".into(), json!(commits));
+    render(&state, StatusCode::OK, "admin/log", &Value::Object(context))
+}
+
+/// What `rustdev lint` reports on the content being edited.
+pub(crate) async fn content_health(
+    req: HttpRequest,
+    state: web::Data<SiteState>,
+    editor: web::Data<ContentEditor>,
+    config: web::Data<LintConfig>,
+) -> HttpResponse {
+    let Some(session) = auth::session(&req) else {
+        return see_other("/admin/login");
+    };
+    let seed = match editor.read_seed().await.and_then(migrate::read_seed) {
+        Ok(seed) => seed,
+        Err(err) => {
+            return message(
+                &state,
+                &session,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &err.to_string(),
+            )
+        }
+    };
+    let report = lint::lint(&seed, &config);
+    let mut context = page_context(&session, "Content health");
+    context.insert("summary".into(), report.summary().into());
+    context.insert("config".into(), config.describe().into());
+    context.insert("findings".into(), json!(report.findings));
+    render(
+        &state,
+        StatusCode::OK,
+        "admin/health",
+        &Value::Object(context),
+    )
+}
+
+/// How a value is shown in a history diff: strings as they are, anything
+/// else as indented JSON, and an absent value as nothing.
+fn shown(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        value => serde_json::to_string_pretty(value).unwrap_or_default(),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct HistoryQuery {
+    reverted: Option<String>,
+}
+
+/// The recorded edits of one entity, newest first, each with a button that
+/// undoes it.
+pub(crate) async fn history(
+    path: web::Path<(String, String)>,
+    query: web::Query<HistoryQuery>,
+    req: HttpRequest,
+    state: web::Data<SiteState>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    let (collection, slug) = path.into_inner();
+    let (session, collection) = match begin(&req, &collection) {
+        Ok(begun) => begun,
+        Err(res) => return *res,
+    };
+    let (seed, entries) = match tokio::try_join!(
+        async { editor.read_seed().await.map_err(EditError::Storage) },
+        editor.history(collection, &slug),
+    ) {
+        Ok(loaded) => loaded,
+        Err(err) => return message(&state, &session, err.status(), &err.message()),
+    };
+
+    let entries: Vec<Value> = entries
+        .iter()
+        .map(|entry| {
+            let changes: Vec<Value> = entry
+                .diff
+                .iter()
+                .map(|change| {
+                    json!({
+                        "field": if change.path.is_empty() { "(whole entry)" } else { &change.path },
+                        "before": shown(&change.before),
+                        "after": shown(&change.after),
+                    })
+                })
+                .collect();
+            json!({
+                "id": entry.id,
+                "at": entry.at,
+                "actor": entry.actor,
+                "action": entry.action,
+                "reverts": entry.reverts,
+                "changes": changes,
+            })
+        })
+        .collect();
+    let exists = collection
+        .items(&seed)
+        .iter()
+        .any(|item| slug_of(item) == Some(slug.as_str()));
+    let mut context = page_context(&session, &format!("History of {slug}"));
+    context.insert(
+        "collection".into(),
+        json!({ "path": collection.path(), "title": collection.title() }),
+    );
+    context.insert("slug".into(), slug.into());
+    context.insert("exists".into(), exists.into());
+    context.insert("entries".into(), entries.into());
+    context.insert(
+        "notice".into(),
+        query
+            .reverted
+            .is_some()
+            .then_some("Reverted. The change is live.")
+            .into(),
+    );
+    render(
+        &state,
+        StatusCode::OK,
+        "admin/history",
+        &Value::Object(context),
+    )
+}
+
+/// Undoes one recorded edit through the same checks as a save.
+pub(crate) async fn revert(
+    path: web::Path<(String, String, String)>,
+    form: web::Form<Vec<(String, String)>>,
+    req: HttpRequest,
+    state: web::Data<SiteState>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    let (collection, slug, id) = path.into_inner();
+    let (session, collection) = match begin(&req, &collection) {
+        Ok(begun) => begun,
+        Err(res) => return *res,
+    };
+    if !session.csrf_ok(form_value(&form, "csrf")) {
+        return message(
+            &state,
+            &session,
+            StatusCode::FORBIDDEN,
+            "The form expired. Go back, reload the page and try again.",
+        );
+    }
+    match editor
+        .revert(&state, &session.user, collection, &slug, &id)
+        .await
+    {
+        Ok(_) => see_other(&format!(
+            "/admin/{}/{slug}/history?reverted=1",
+            collection.path()
+        )),
+        Err(err) => message(&state, &session, err.status(), &err.message()),
+    }
+}
+
+/// Marks a previewed page so it cannot be mistaken for the live one.
+const PREVIEW_BANNER: &str = "<div style=\"position:sticky;top:0;z-index:10000;\
+     padding:8px 16px;background:#b7410e;color:#fff;font:600 14px/1.4 system-ui,sans-serif\">\
+     Preview: not saved</div>";
+
+fn with_banner(html: String) -> String {
+    let Some(start) = html.find("<body") else {
+        return format!("{PREVIEW_BANNER}{html}");
+    };
+    match html[start..].find('>') {
+        Some(end) => {
+            let at = start + end + 1;
+            format!("{}{PREVIEW_BANNER}{}", &html[..at], &html[at..])
+        }
+        None => html,
+    }
+}
+
+/// Renders the page the submitted entity would produce, through the same
+/// templates and contexts as the live site, without saving anything.
+pub(crate) async fn preview(
+    path: web::Path<String>,
+    form: web::Form<Vec<(String, String)>>,
+    req: HttpRequest,
+    state: web::Data<SiteState>,
+    editor: web::Data<ContentEditor>,
+) -> HttpResponse {
+    let (session, collection) = match begin(&req, &path) {
+        Ok(begun) => begun,
+        Err(res) => return *res,
+    };
+    if !session.csrf_ok(form_value(&form, "csrf")) {
+        return message(
+            &state,
+            &session,
+            StatusCode::FORBIDDEN,
+            "The form expired. Go back, reload the page and try again.",
+        );
+    }
+    let Some(site) = state.snapshot() else {
+        return starting_up();
+    };
+    let seed = match editor.read_seed().await {
+        Ok(seed) => seed,
+        Err(err) => {
+            return message(
+                &state,
+                &session,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &err.to_string(),
+            )
+        }
+    };
+
+    let slug = Some(form_value(&form, "_slug"))
+        .filter(|slug| !slug.is_empty())
+        .map(str::to_string);
+    let stored = slug.as_deref().and_then(|slug| {
+        collection
+            .items(&seed)
+            .iter()
+            .find(|item| slug_of(item) == Some(slug))
+            .cloned()
+    });
+    let mut entity_form = EntityForm::new(collection, &seed, slug.clone()).with_submission(&form);
+    let Some(entity) = entity_form.entity(stored.as_ref()) else {
+        return entity_form.render(
+            &state,
+            &session,
+            StatusCode::UNPROCESSABLE_ENTITY,
+            None,
+            Some("Some fields need attention."),
+        );
+    };
+    let edit = match slug {
+        Some(slug) => Edit::Update(slug, entity),
+        None => Edit::Create(entity),
+    };
+    let edited = match admin::edited_seed(seed, collection, edit) {
+        Ok(edited) => edited,
+        Err(err) => return message(&state, &session, err.status(), &err.message()),
+    };
+    let Some(page_path) = collection.page_path(&edited.slug) else {
+        return message(
+            &state,
+            &session,
+            StatusCode::NOT_FOUND,
+            &format!("{} have no page to preview.", collection.title()),
+        );
+    };
+
+    let promo = load_promo_content(PROMO_PATH).await.unwrap_or_default();
+    let rendered = tokio::task::spawn_blocking(move || {
+        let seed = migrate::read_seed(edited.seed).map_err(|err| err.to_string())?;
+        let rustdev = RustDevContent::from_seed(seed);
+        let page = page_sources(&site.hb, &rustdev, &promo)
+            .into_iter()
+            .find(|page| page.path == page_path)
+            .ok_or_else(|| format!("No page at {page_path}"))?;
+        site.hb
+            .render(page.template, &page.context)
+            .map_err(|err| err.to_string())
+    })
+    .await;
+    match rendered {
+        Ok(Ok(html)) => HttpResponse::Ok()
+            .content_type(HTML_CONTENT_TYPE)
+            .body(with_banner(html)),
+        Ok(Err(err)) => message(&state, &session, StatusCode::UNPROCESSABLE_ENTITY, &err),
+        Err(err) => message(
+            &state,
+            &session,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &err.to_string(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{auth::Accounts, testing};
+    use actix_web::{
+        http::header,
+        test::{call_service, init_service, TestRequest},
+        App,
+    };
+    use std::collections::HashMap;
+
+    #[actix_web::test]
+    async fn edits_without_the_session_csrf_token_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let seed_path = dir.path().join("seed.json");
+        let seed = json!({
+            "version": "rustdev-hub-seed-v7.0",
+            "tools": [{"slug": "anchor", "name": "Anchor"}],
+        });
+        let state = testing::site_state(&seed_path, &seed).await;
+        let accounts = web::Data::new(Accounts::new(HashMap::new()).unwrap());
+        let app = init_service(
+            App::new()
+                .app_data(state.clone())
+                .app_data(testing::editor(&state, &dir.path().join("audit.jsonl")))
+                .service(testing::admin_scope(accounts.clone())),
+        )
+        .await;
+        let (cookie, csrf) = testing::sign_in(&accounts, "ed");
+        let stored = std::fs::read(&seed_path).unwrap();
+        let post = |uri: &str, body: String| {
+            TestRequest::post()
+                .uri(uri)
+                .insert_header((header::HOST, "localhost"))
+                .insert_header((header::CONTENT_TYPE, "application/x-www-form-urlencoded"))
+                .cookie(cookie.clone())
+                .set_payload(body)
+                .to_request()
+        };
+
+        for uri in [
+            "/admin/new/tools",
+            "/admin/tools/anchor",
+            "/admin/tools/anchor/delete",
+        ] {
+            for token in ["", "&csrf=guess"] {
+                let body = format!("_slug=anchor&f.name=Changed&confirm=1{token}");
+                let res = call_service(&app, post(uri, body)).await;
+                assert_eq!(res.status(), StatusCode::FORBIDDEN, "{uri} {token}");
+            }
+        }
+        assert_eq!(std::fs::read(&seed_path).unwrap(), stored);
+
+        let res = call_service(
+            &app,
+            post(
+                "/admin/tools/anchor/delete",
+                format!("confirm=1&csrf={csrf}"),
+            ),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert_ne!(std::fs::read(&seed_path).unwrap(), stored);
+    }
+}
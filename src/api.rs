@@ -0,0 +1,1269 @@
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use schemars::{gen::SchemaSettings, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::LazyLock;
+use std::time::SystemTime;
+
+use crate::conditional::Validators;
+use crate::content::{Collection, COLLECTIONS};
+use crate::metrics;
+use crate::{
+    is_allowed_host, starting_up, BestStart, Creator, Ecosystem, Event, FeaturedMedia, Job, Label,
+    LearningPath, MediaAsset, MediaItem, Post, RustDevContent, SiteState, Tool,
+};
+
+pub(crate) const API_VERSION: &str = "1.0.0";
+const API_CACHE_CONTROL: &str = "public, max-age=120, stale-while-revalidate=60";
+const DEFAULT_PER_PAGE: usize = 20;
+const MAX_PER_PAGE: usize = 100;
+
+/// Image URLs attached to an entity.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct MediaDto {
+    /// Square logo or icon.
+    logo_url: Option<String>,
+    /// Avatar for people and channels.
+    avatar_url: Option<String>,
+    /// Wide background image for page heroes.
+    background_url: Option<String>,
+    /// Social card / Open Graph image.
+    card_url: Option<String>,
+    /// Small thumbnail used in teasers.
+    teaser_thumb_url: Option<String>,
+}
+
+impl From<&MediaAsset> for MediaDto {
+    fn from(media: &MediaAsset) -> Self {
+        Self {
+            logo_url: media.logo_url.clone(),
+            avatar_url: media.avatar_url.clone(),
+            background_url: media.background_url.clone(),
+            card_url: media.card_url.clone(),
+            teaser_thumb_url: media.teaser_thumb_url.clone(),
+        }
+    }
+}
+
+/// A titled link to a piece of third-party media.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct MediaLinkDto {
+    title: Option<String>,
+    url: String,
+}
+
+impl MediaLinkDto {
+    fn from_item(item: Option<&MediaItem>) -> Option<Self> {
+        let item = item?;
+        Some(Self {
+            title: item.title.clone(),
+            url: item.url.clone()?,
+        })
+    }
+}
+
+/// Featured video, post and article for an entity.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct FeaturedMediaDto {
+    /// YouTube video.
+    youtube: Option<MediaLinkDto>,
+    /// Post on X (formerly Twitter).
+    twitter: Option<MediaLinkDto>,
+    /// Long-form article.
+    article: Option<MediaLinkDto>,
+}
+
+impl From<&FeaturedMedia> for FeaturedMediaDto {
+    fn from(media: &FeaturedMedia) -> Self {
+        Self {
+            youtube: MediaLinkDto::from_item(media.youtube.as_ref()),
+            twitter: MediaLinkDto::from_item(media.twitter.as_ref())
+                .or_else(|| MediaLinkDto::from_item(media.x.as_ref())),
+            article: MediaLinkDto::from_item(media.article.as_ref()),
+        }
+    }
+}
+
+fn media_dto(media: Option<&MediaAsset>) -> Option<MediaDto> {
+    media.map(MediaDto::from)
+}
+
+fn featured_dto(media: Option<&FeaturedMedia>) -> Option<FeaturedMediaDto> {
+    media.map(FeaturedMediaDto::from)
+}
+
+fn sorted_links(links: &std::collections::HashMap<String, String>) -> BTreeMap<String, String> {
+    links
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+/// A blockchain or platform ecosystem where Rust is used in production.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct EcosystemDto {
+    /// Stable identifier, unique within the collection.
+    slug: String,
+    name: String,
+    /// One-sentence summary.
+    one_liner: String,
+    topics: Vec<String>,
+    /// Official links keyed by kind (`site`, `docs`, `github`, ...).
+    official_links: BTreeMap<String, String>,
+    /// Slugs of tools featured for this ecosystem.
+    featured_tools: Vec<String>,
+    media: Option<MediaDto>,
+    featured_media: Option<FeaturedMediaDto>,
+    /// Path of the HTML page on rust.dev.
+    html_url: String,
+}
+
+impl From<&Ecosystem> for EcosystemDto {
+    fn from(e: &Ecosystem) -> Self {
+        Self {
+            slug: e.slug.clone(),
+            name: e.name.clone(),
+            one_liner: e.one_liner.clone(),
+            topics: e.topics.clone(),
+            official_links: sorted_links(&e.official_links),
+            featured_tools: e.featured_tools.clone(),
+            media: media_dto(e.media.as_ref()),
+            featured_media: featured_dto(e.featured_media.as_ref()),
+            html_url: format!("/ecosystems/{}", e.slug),
+        }
+    }
+}
+
+/// A library, framework or developer tool.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct ToolDto {
+    /// Stable identifier, unique within the collection.
+    slug: String,
+    name: String,
+    /// Tool category slug, e.g. `smart-contract-framework`.
+    category: String,
+    description: String,
+    /// Taxonomy label slugs; see `/api/v1/labels`.
+    labels: Vec<String>,
+    primary_label: Option<String>,
+    /// Editorial tier, e.g. `core`.
+    tier: Option<String>,
+    tags: Vec<String>,
+    /// Links keyed by kind (`github`, `docs`, `site`, ...).
+    links: BTreeMap<String, String>,
+    media: Option<MediaDto>,
+    featured_media: Option<FeaturedMediaDto>,
+    /// Path of the HTML page on rust.dev.
+    html_url: String,
+}
+
+impl From<&Tool> for ToolDto {
+    fn from(t: &Tool) -> Self {
+        Self {
+            slug: t.slug.clone(),
+            name: t.name.clone(),
+            category: t.category.clone(),
+            description: t.description.clone(),
+            labels: t.labels.clone(),
+            primary_label: t.primary_label.clone(),
+            tier: t.tier.clone(),
+            tags: t.tags.clone(),
+            links: sorted_links(&t.links),
+            media: media_dto(t.media.as_ref()),
+            featured_media: featured_dto(t.featured_media.as_ref()),
+            html_url: format!("/tools/{}", t.slug),
+        }
+    }
+}
+
+/// A conference, meetup or hackathon.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct EventDto {
+    /// Stable identifier, unique within the collection.
+    slug: String,
+    title: String,
+    teaser: Option<String>,
+    /// One of `upcoming`, `past`, `recurring` or `tba`.
+    status: String,
+    /// Start date, `YYYY-MM-DD`.
+    starts_on: Option<String>,
+    /// End date, `YYYY-MM-DD`.
+    ends_on: Option<String>,
+    location: String,
+    schedule_note: Option<String>,
+    labels: Vec<String>,
+    primary_label: Option<String>,
+    tags: Vec<String>,
+    /// The event's own website.
+    url: String,
+    media: Option<MediaDto>,
+    featured_media: Option<FeaturedMediaDto>,
+    /// Path of the HTML page on rust.dev.
+    html_url: String,
+}
+
+impl From<&Event> for EventDto {
+    fn from(e: &Event) -> Self {
+        Self {
+            slug: e.slug.clone(),
+            title: e.title.clone(),
+            teaser: e.teaser.clone(),
+            status: e.status.clone(),
+            starts_on: e.starts_on.clone(),
+            ends_on: e.ends_on.clone(),
+            location: e.location.clone(),
+            schedule_note: e.schedule_note.clone(),
+            labels: e.labels.clone(),
+            primary_label: e.primary_label.clone(),
+            tags: e.tags.clone(),
+            url: e.url.clone(),
+            media: media_dto(e.media.as_ref()),
+            featured_media: featured_dto(e.featured_media.as_ref()),
+            html_url: format!("/events/{}", e.slug),
+        }
+    }
+}
+
+/// A learning resource referenced by a learning path.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct ResourceDto {
+    slug: Option<String>,
+    title: String,
+    url: String,
+}
+
+/// A curated sequence of milestones and resources.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct LearningPathDto {
+    /// Stable identifier, unique within the collection.
+    slug: String,
+    title: String,
+    summary: String,
+    difficulty: String,
+    /// Estimated effort in hours; 0 when unknown.
+    duration_hours: u32,
+    milestones: Vec<String>,
+    resources: Vec<ResourceDto>,
+    media: Option<MediaDto>,
+    featured_media: Option<FeaturedMediaDto>,
+    /// Path of the HTML page on rust.dev.
+    html_url: String,
+}
+
+impl LearningPathDto {
+    fn new(path: &LearningPath, rustdev: &RustDevContent) -> Self {
+        Self {
+            slug: path.slug.clone(),
+            title: path.title.clone(),
+            summary: path.summary.clone(),
+            difficulty: path.difficulty.clone(),
+            duration_hours: path.duration_hours.unwrap_or(0),
+            milestones: path.milestones.clone(),
+            resources: rustdev
+                .resources_for(&path.resources)
+                .into_iter()
+                .map(|res| ResourceDto {
+                    slug: res.slug,
+                    title: res.title,
+                    url: res.url,
+                })
+                .collect(),
+            media: media_dto(path.media.as_ref()),
+            featured_media: featured_dto(path.featured_media.as_ref()),
+            html_url: format!("/learn/{}", path.slug),
+        }
+    }
+}
+
+/// Recommended first piece of content from a creator.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct BestStartDto {
+    title: String,
+    url: String,
+}
+
+impl BestStartDto {
+    fn from_valid(best_start: Option<&BestStart>) -> Option<Self> {
+        let best_start = best_start.filter(|bs| bs.is_valid())?;
+        Some(Self {
+            title: best_start.title.clone()?,
+            url: best_start.url.clone()?,
+        })
+    }
+}
+
+/// A YouTube channel, newsletter, podcast or other Rust content creator.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct CreatorDto {
+    /// Stable identifier, unique within the collection.
+    slug: String,
+    name: String,
+    /// Creator kind, e.g. `youtube`, `newsletter`, `podcast`, `playlist`.
+    #[serde(rename = "type")]
+    kind: String,
+    labels: Vec<String>,
+    primary_label: Option<String>,
+    focus: Vec<String>,
+    tags: Vec<String>,
+    /// Links keyed by kind (`youtube`, `site`, `rss`, ...).
+    links: BTreeMap<String, String>,
+    about: Option<String>,
+    description: Option<String>,
+    /// YouTube video id of a featured video.
+    video_id: Option<String>,
+    best_start: Option<BestStartDto>,
+    thumbnail: Option<String>,
+    media: Option<MediaDto>,
+    featured_media: Option<FeaturedMediaDto>,
+    /// Path of the HTML page on rust.dev.
+    html_url: String,
+}
+
+impl From<&Creator> for CreatorDto {
+    fn from(c: &Creator) -> Self {
+        Self {
+            slug: c.slug.clone(),
+            name: c.name.clone(),
+            kind: c.r#type.clone(),
+            labels: c.labels.clone(),
+            primary_label: c.primary_label.clone(),
+            focus: c.focus.clone(),
+            tags: c.tags.clone(),
+            links: sorted_links(&c.links),
+            about: c.about.clone(),
+            description: c.description.clone(),
+            video_id: c.video_id.clone(),
+            best_start: BestStartDto::from_valid(c.best_start.as_ref()),
+            thumbnail: c.thumbnail.clone(),
+            media: media_dto(c.media.as_ref()),
+            featured_media: featured_dto(c.featured_media.as_ref()),
+            html_url: format!("/creators/{}", c.slug),
+        }
+    }
+}
+
+/// A labelled outbound link.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct LinkDto {
+    label: String,
+    url: String,
+}
+
+/// Slugs of entities a post relates to.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct RelatedDto {
+    tools: Vec<String>,
+    events: Vec<String>,
+    ecosystems: Vec<String>,
+}
+
+/// A news brief, feature or guide.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct PostDto {
+    /// Stable identifier, unique within the collection.
+    slug: String,
+    title: String,
+    /// Post kind, e.g. `news`, `feature` or `guide`.
+    kind: String,
+    deck: String,
+    /// Publication date, `YYYY-MM-DD`.
+    published_on: String,
+    author_handle: String,
+    labels: Vec<String>,
+    primary_label: Option<String>,
+    tags: Vec<String>,
+    /// Body in Markdown.
+    body_md: String,
+    links: Vec<LinkDto>,
+    sources: Vec<String>,
+    related: Option<RelatedDto>,
+    cover_image: Option<String>,
+    media: Option<MediaDto>,
+    featured_media: Option<FeaturedMediaDto>,
+    /// Path of the HTML page on rust.dev.
+    html_url: String,
+}
+
+impl From<&Post> for PostDto {
+    fn from(p: &Post) -> Self {
+        Self {
+            slug: p.slug.clone(),
+            title: p.title.clone(),
+            kind: p.kind.clone(),
+            deck: p.deck.clone(),
+            published_on: p.published_on.clone(),
+            author_handle: p.author_handle.clone(),
+            labels: p.labels.clone(),
+            primary_label: p.primary_label.clone(),
+            tags: p.tags.clone(),
+            body_md: p.body_md.clone(),
+            links: p
+                .links
+                .iter()
+                .map(|link| LinkDto {
+                    label: link.label.clone(),
+                    url: link.url.clone(),
+                })
+                .collect(),
+            sources: p.sources.clone(),
+            related: p.related.as_ref().map(|related| RelatedDto {
+                tools: related.tools.clone(),
+                events: related.events.clone(),
+                ecosystems: related.protocols.clone(),
+            }),
+            cover_image: p.cover_image.clone(),
+            media: media_dto(p.media.as_ref()),
+            featured_media: featured_dto(p.featured_media.as_ref()),
+            html_url: format!("/news/{}", p.slug),
+        }
+    }
+}
+
+/// Hiring company.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct CompanyDto {
+    name: String,
+    domain: Option<String>,
+}
+
+/// An open Rust role.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct JobDto {
+    /// Stable identifier, unique within the collection.
+    slug: String,
+    title: String,
+    company: CompanyDto,
+    labels: Vec<String>,
+    primary_label: Option<String>,
+    about: String,
+    apply_url: String,
+    /// Date the listing was last checked, `YYYY-MM-DD`.
+    last_verified: Option<String>,
+    media: Option<MediaDto>,
+}
+
+impl From<&Job> for JobDto {
+    fn from(j: &Job) -> Self {
+        Self {
+            slug: j.slug.clone(),
+            title: j.title.clone(),
+            company: CompanyDto {
+                name: j.company.name.clone(),
+                domain: j.company.domain.clone(),
+            },
+            labels: j.labels.clone(),
+            primary_label: j.primary_label.clone(),
+            about: j.about.clone(),
+            apply_url: j.apply_url.clone(),
+            last_verified: j.last_verified.clone(),
+            media: media_dto(j.media.as_ref()),
+        }
+    }
+}
+
+/// A taxonomy label used to group entities across collections.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct LabelDto {
+    /// Stable identifier, referenced by `labels` and `primary_label`.
+    slug: String,
+    /// Display name.
+    name: String,
+    description: Option<String>,
+}
+
+impl From<&Label> for LabelDto {
+    fn from(l: &Label) -> Self {
+        Self {
+            slug: l.slug.clone(),
+            name: l.name.clone(),
+            description: l.description.clone(),
+        }
+    }
+}
+
+/// Pagination details of a list response.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct PageMeta {
+    page: usize,
+    per_page: usize,
+    /// Number of items across all pages.
+    total: usize,
+    total_pages: usize,
+}
+
+/// Navigation links of a list response.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct PageLinks {
+    #[serde(rename = "self")]
+    current: String,
+    next: Option<String>,
+    prev: Option<String>,
+}
+
+/// Error body returned by every API endpoint.
+#[derive(Debug, Serialize, JsonSchema)]
+pub(crate) struct ApiError {
+    error: String,
+    /// HTTP status code.
+    code: u16,
+}
+
+/// The DTO schema a collection's items are documented as.
+fn schema_name(collection: Collection) -> &'static str {
+    match collection {
+        Collection::Ecosystems => "EcosystemDto",
+        Collection::Tools => "ToolDto",
+        Collection::Events => "EventDto",
+        Collection::LearningPaths => "LearningPathDto",
+        Collection::Creators => "CreatorDto",
+        Collection::Posts => "PostDto",
+        Collection::Jobs => "JobDto",
+        Collection::Labels => "LabelDto",
+    }
+}
+
+fn items(collection: Collection, rustdev: &RustDevContent) -> Vec<Value> {
+    fn to_values<T: Serialize>(items: impl Iterator<Item = T>) -> Vec<Value> {
+        items
+            .filter_map(|item| serde_json::to_value(item).ok())
+            .collect()
+    }
+
+    match collection {
+        Collection::Ecosystems => to_values(rustdev.ecosystems.iter().map(EcosystemDto::from)),
+        Collection::Tools => to_values(rustdev.tools.iter().map(ToolDto::from)),
+        Collection::Events => to_values(rustdev.events.iter().map(EventDto::from)),
+        Collection::LearningPaths => to_values(
+            rustdev
+                .learning_paths
+                .iter()
+                .map(|path| LearningPathDto::new(path, rustdev)),
+        ),
+        Collection::Creators => to_values(rustdev.creators.iter().map(CreatorDto::from)),
+        Collection::Posts => to_values(rustdev.posts.iter().map(PostDto::from)),
+        Collection::Jobs => to_values(rustdev.jobs.iter().map(JobDto::from)),
+        Collection::Labels => to_values(rustdev.labels.iter().map(LabelDto::from)),
+    }
+}
+
+fn find_item(collection: Collection, rustdev: &RustDevContent, slug: &str) -> Option<Value> {
+    let value = match collection {
+        Collection::Ecosystems => {
+            serde_json::to_value(EcosystemDto::from(rustdev.ecosystem_by_slug(slug)?))
+        }
+        Collection::Tools => serde_json::to_value(ToolDto::from(rustdev.tool_by_slug(slug)?)),
+        Collection::Events => serde_json::to_value(EventDto::from(rustdev.event_by_slug(slug)?)),
+        Collection::LearningPaths => serde_json::to_value(LearningPathDto::new(
+            rustdev.learning_path_by_slug(slug)?,
+            rustdev,
+        )),
+        Collection::Creators => {
+            serde_json::to_value(CreatorDto::from(rustdev.creator_by_slug(slug)?))
+        }
+        Collection::Posts => serde_json::to_value(PostDto::from(rustdev.post_by_slug(slug)?)),
+        Collection::Jobs => serde_json::to_value(JobDto::from(
+            rustdev.jobs.iter().find(|job| job.slug == slug)?,
+        )),
+        Collection::Labels => serde_json::to_value(LabelDto::from(
+            rustdev.labels.iter().find(|label| label.slug == slug)?,
+        )),
+    };
+    value.ok()
+}
+
+/// Top-level property names of each collection's DTO, taken from its schema
+/// so sparse fieldsets and sorting validate against the documented shape.
+/// Generated once; the DTOs do not change at runtime.
+static FIELDS: LazyLock<HashMap<Collection, Vec<String>>> = LazyLock::new(|| {
+    fn props<T: JsonSchema>() -> Vec<String> {
+        schemars::schema_for!(T)
+            .schema
+            .object
+            .map(|obj| obj.properties.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    COLLECTIONS
+        .into_iter()
+        .map(|collection| {
+            let fields = match collection {
+                Collection::Ecosystems => props::<EcosystemDto>(),
+                Collection::Tools => props::<ToolDto>(),
+                Collection::Events => props::<EventDto>(),
+                Collection::LearningPaths => props::<LearningPathDto>(),
+                Collection::Creators => props::<CreatorDto>(),
+                Collection::Posts => props::<PostDto>(),
+                Collection::Jobs => props::<JobDto>(),
+                Collection::Labels => props::<LabelDto>(),
+            };
+            (collection, fields)
+        })
+        .collect()
+});
+
+fn fields(collection: Collection) -> &'static [String] {
+    FIELDS
+        .get(&collection)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ListParams {
+    page: Option<usize>,
+    per_page: Option<usize>,
+    sort: Option<String>,
+    fields: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ItemParams {
+    fields: Option<String>,
+}
+
+pub(crate) fn api_error(
+    status: actix_web::http::StatusCode,
+    message: impl Into<String>,
+) -> HttpResponse {
+    HttpResponse::build(status).json(ApiError {
+        error: message.into(),
+        code: status.as_u16(),
+    })
+}
+
+pub(crate) fn not_found(message: impl Into<String>) -> HttpResponse {
+    api_error(actix_web::http::StatusCode::NOT_FOUND, message)
+}
+
+fn bad_request(message: impl Into<String>) -> HttpResponse {
+    api_error(actix_web::http::StatusCode::BAD_REQUEST, message)
+}
+
+pub(crate) fn ok_json(
+    req: &HttpRequest,
+    body: &Value,
+    last_modified: Option<SystemTime>,
+) -> HttpResponse {
+    let body = body.to_string();
+    let validators = Validators::for_body(body.as_bytes(), last_modified);
+    if validators.is_fresh(req) {
+        return validators.not_modified(API_CACHE_CONTROL, None);
+    }
+    json_body(&validators, body)
+}
+
+fn json_body(validators: &Validators, body: String) -> HttpResponse {
+    let mut builder = HttpResponse::Ok();
+    validators.apply(&mut builder);
+    builder
+        .append_header((header::CONTENT_TYPE, "application/json"))
+        .append_header((header::CACHE_CONTROL, API_CACHE_CONTROL))
+        .body(body)
+}
+
+/// The query's pairs in a fixed order, so equivalent queries get the same
+/// body and validators.
+fn query_pairs(query: &str) -> Vec<&str> {
+    let mut pairs: Vec<&str> = query.split('&').filter(|pair| !pair.is_empty()).collect();
+    pairs.sort_unstable();
+    pairs
+}
+
+/// Validators for a collection or item response, known before its body is
+/// built: the body follows from the content, this build of the server and
+/// the request's path and query. A revalidation is answered without
+/// serializing or hashing it.
+fn api_validators(req: &HttpRequest, rustdev: &RustDevContent) -> Validators {
+    let key = format!(
+        "{}\n{}\n{}\n{}",
+        env!("CARGO_PKG_VERSION"),
+        rustdev.content_version,
+        req.path(),
+        query_pairs(req.query_string()).join("&"),
+    );
+    Validators::for_key(&key, rustdev.last_modified)
+}
+
+/// Parses `fields=a,b` against the DTO's fields. `slug` is always kept.
+fn parse_fields(raw: Option<&str>, known: &[String]) -> Result<Option<Vec<String>>, String> {
+    let Some(raw) = raw.map(str::trim).filter(|raw| !raw.is_empty()) else {
+        return Ok(None);
+    };
+    let mut fields = vec!["slug".to_string()];
+    for field in raw.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        if !known.iter().any(|k| k == field) {
+            return Err(format!("Unknown field `{field}`"));
+        }
+        if !fields.iter().any(|f| f == field) {
+            fields.push(field.to_string());
+        }
+    }
+    Ok(Some(fields))
+}
+
+fn select_fields(value: Value, fields: Option<&[String]>) -> Value {
+    match (value, fields) {
+        (Value::Object(map), Some(fields)) => Value::Object(
+            map.into_iter()
+                .filter(|(key, _)| fields.iter().any(|f| f == key))
+                .collect::<Map<String, Value>>(),
+        ),
+        (value, _) => value,
+    }
+}
+
+/// Orders JSON scalars: numbers numerically, strings case-insensitively,
+/// with nulls last regardless of direction.
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x
+            .as_f64()
+            .partial_cmp(&y.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(x), Value::String(y)) => x.to_lowercase().cmp(&y.to_lowercase()),
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        (Value::Null, Value::Null) => Ordering::Equal,
+        (Value::Null, _) => Ordering::Greater,
+        (_, Value::Null) => Ordering::Less,
+        _ => Ordering::Equal,
+    }
+}
+
+/// Parses a comma-separated list of fields to sort by; a `-` prefix sorts
+/// descending.
+fn parse_sort(sort: &str, known: &[String]) -> Result<Vec<(String, bool)>, String> {
+    let mut keys = Vec::new();
+    for key in sort.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+        let (field, descending) = match key.strip_prefix('-') {
+            Some(field) => (field, true),
+            None => (key, false),
+        };
+        if !known.iter().any(|k| k == field) {
+            return Err(format!("Cannot sort by unknown field `{field}`"));
+        }
+        keys.push((field.to_string(), descending));
+    }
+    Ok(keys)
+}
+
+fn sort_items(items: &mut [Value], keys: &[(String, bool)]) {
+    items.sort_by(|a, b| {
+        for (field, descending) in keys {
+            let (x, y) = (&a[field.as_str()], &b[field.as_str()]);
+            let ordering = match (x.is_null(), y.is_null(), descending) {
+                (false, false, true) => compare_values(y, x),
+                _ => compare_values(x, y),
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    });
+}
+
+fn page_url(req: &HttpRequest, page: usize, per_page: usize) -> String {
+    let mut query: Vec<String> = query_pairs(req.query_string())
+        .into_iter()
+        .filter(|pair| !pair.starts_with("page=") && !pair.starts_with("per_page="))
+        .map(str::to_string)
+        .collect();
+    query.push(format!("page={page}"));
+    query.push(format!("per_page={per_page}"));
+    format!("{}?{}", req.path(), query.join("&"))
+}
+
+pub(crate) async fn list(
+    collection: web::Path<String>,
+    state: web::Data<SiteState>,
+    req: HttpRequest,
+) -> HttpResponse {
+    if !is_allowed_host(&req) {
+        return not_found("Not found");
+    }
+    let Some(site) = state.snapshot() else {
+        return starting_up();
+    };
+    let rustdev = &site.rustdev;
+    let Some(collection) = Collection::from_path(collection.as_str()) else {
+        return not_found(format!("Unknown collection `{collection}`"));
+    };
+    let params = match web::Query::<ListParams>::from_query(req.query_string()) {
+        Ok(params) => params.into_inner(),
+        Err(err) => return bad_request(err.to_string()),
+    };
+
+    let known = fields(collection);
+    let fields = match parse_fields(params.fields.as_deref(), known) {
+        Ok(fields) => fields,
+        Err(err) => return bad_request(err),
+    };
+
+    let sort = match params.sort.as_deref().map(|sort| parse_sort(sort, known)) {
+        Some(Ok(keys)) => keys,
+        Some(Err(err)) => return bad_request(err),
+        None => Vec::new(),
+    };
+    let per_page = params.per_page.unwrap_or(DEFAULT_PER_PAGE);
+    if per_page == 0 || per_page > MAX_PER_PAGE {
+        return bad_request(format!("`per_page` must be between 1 and {MAX_PER_PAGE}"));
+    }
+    let page = params.page.unwrap_or(1);
+    if page == 0 {
+        return bad_request("`page` starts at 1");
+    }
+
+    let validators = api_validators(&req, rustdev);
+    if validators.is_fresh(&req) {
+        return validators.not_modified(API_CACHE_CONTROL, None);
+    }
+    let mut items = items(collection, rustdev);
+    sort_items(&mut items, &sort);
+
+    let total = items.len();
+    let total_pages = total.div_ceil(per_page).max(1);
+    let data: Vec<Value> = items
+        .into_iter()
+        .skip((page - 1) * per_page)
+        .take(per_page)
+        .map(|item| select_fields(item, fields.as_deref()))
+        .collect();
+
+    let body = json!({
+        "data": data,
+        "meta": PageMeta { page, per_page, total, total_pages },
+        "links": PageLinks {
+            current: page_url(&req, page, per_page),
+            next: (page < total_pages).then(|| page_url(&req, page + 1, per_page)),
+            prev: (page > 1).then(|| page_url(&req, (page - 1).min(total_pages), per_page)),
+        },
+    });
+    json_body(&validators, body.to_string())
+}
+
+pub(crate) async fn item(
+    path: web::Path<(String, String)>,
+    state: web::Data<SiteState>,
+    req: HttpRequest,
+) -> HttpResponse {
+    if !is_allowed_host(&req) {
+        return not_found("Not found");
+    }
+    let Some(site) = state.snapshot() else {
+        return starting_up();
+    };
+    let rustdev = &site.rustdev;
+    let (collection, slug) = path.into_inner();
+    let Some(collection) = Collection::from_path(&collection) else {
+        return not_found(format!("Unknown collection `{collection}`"));
+    };
+    let params = match web::Query::<ItemParams>::from_query(req.query_string()) {
+        Ok(params) => params.into_inner(),
+        Err(err) => return bad_request(err.to_string()),
+    };
+    let fields = match parse_fields(params.fields.as_deref(), fields(collection)) {
+        Ok(fields) => fields,
+        Err(err) => return bad_request(err),
+    };
+
+    match tracing::info_span!("slug_lookup", collection = collection.path(), slug = %slug)
+        .in_scope(|| find_item(collection, rustdev, &slug))
+    {
+        Some(item) => {
+            let validators = api_validators(&req, rustdev);
+            if validators.is_fresh(&req) {
+                return validators.not_modified(API_CACHE_CONTROL, None);
+            }
+            let body = json!({ "data": select_fields(item, fields.as_deref()) });
+            json_body(&validators, body.to_string())
+        }
+        None => {
+            metrics::not_found(Some(collection.path()));
+            not_found(format!("No {} with slug `{slug}`", collection.path()))
+        }
+    }
+}
+
+pub(crate) async fn openapi(req: HttpRequest) -> HttpResponse {
+    if !is_allowed_host(&req) {
+        return not_found("Not found");
+    }
+    ok_json(&req, &openapi_document(), None)
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{name}") })
+}
+
+fn error_response_doc(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": schema_ref("ApiError") } },
+    })
+}
+
+/// Builds the OpenAPI 3.0 document from the DTO schemas.
+pub(crate) fn openapi_document() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+    gen.subschema_for::<EcosystemDto>();
+    gen.subschema_for::<ToolDto>();
+    gen.subschema_for::<EventDto>();
+    gen.subschema_for::<LearningPathDto>();
+    gen.subschema_for::<CreatorDto>();
+    gen.subschema_for::<PostDto>();
+    gen.subschema_for::<JobDto>();
+    gen.subschema_for::<LabelDto>();
+    gen.subschema_for::<PageMeta>();
+    gen.subschema_for::<PageLinks>();
+    gen.subschema_for::<ApiError>();
+    let schemas = serde_json::to_value(gen.definitions()).unwrap_or_else(|_| json!({}));
+
+    let fields_param = json!({
+        "name": "fields",
+        "in": "query",
+        "description": "Comma-separated list of fields to return; `slug` is always included.",
+        "schema": { "type": "string" },
+    });
+
+    let mut paths = Map::new();
+    for collection in COLLECTIONS {
+        let name = collection.path();
+        let schema = schema_name(collection);
+        paths.insert(
+            format!("/{name}"),
+            json!({
+                "get": {
+                    "operationId": format!("list_{}", name.replace('-', "_")),
+                    "summary": format!("List {name}"),
+                    "parameters": [
+                        {
+                            "name": "page",
+                            "in": "query",
+                            "schema": { "type": "integer", "minimum": 1, "default": 1 },
+                        },
+                        {
+                            "name": "per_page",
+                            "in": "query",
+                            "schema": {
+                                "type": "integer",
+                                "minimum": 1,
+                                "maximum": MAX_PER_PAGE,
+                                "default": DEFAULT_PER_PAGE,
+                            },
+                        },
+                        {
+                            "name": "sort",
+                            "in": "query",
+                            "description": "Comma-separated fields; prefix with `-` for descending order.",
+                            "schema": { "type": "string" },
+                        },
+                        fields_param,
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": format!("A page of {name}"),
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "required": ["data", "meta", "links"],
+                                        "properties": {
+                                            "data": { "type": "array", "items": schema_ref(schema) },
+                                            "meta": schema_ref("PageMeta"),
+                                            "links": schema_ref("PageLinks"),
+                                        },
+                                    },
+                                },
+                            },
+                        },
+                        "400": error_response_doc("Invalid pagination, sort or fields parameter"),
+                    },
+                },
+            }),
+        );
+        paths.insert(
+            format!("/{name}/{{slug}}"),
+            json!({
+                "get": {
+                    "operationId": format!("get_{}", name.replace('-', "_")),
+                    "summary": format!("Fetch one of {name} by slug"),
+                    "parameters": [
+                        {
+                            "name": "slug",
+                            "in": "path",
+                            "required": true,
+                            "schema": { "type": "string" },
+                        },
+                        fields_param,
+                    ],
+                    "responses": {
+                        "200": {
+                            "description": "The requested item",
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "required": ["data"],
+                                        "properties": { "data": schema_ref(schema) },
+                                    },
+                                },
+                            },
+                        },
+                        "400": error_response_doc("Invalid fields parameter"),
+                        "404": error_response_doc("No item with this slug"),
+                    },
+                },
+            }),
+        );
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "rust.dev API",
+            "version": API_VERSION,
+            "description": "Read-only access to the rust.dev catalogue.",
+        },
+        "servers": [{ "url": "/api/v1" }],
+        "paths": paths,
+        "components": { "schemas": schemas },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use actix_web::{
+        test::{call_service, init_service, read_body, read_body_json, TestRequest},
+        App,
+    };
+
+    fn tool(slug: &str, name: &str, tier: Option<&str>) -> Value {
+        json!({"slug": slug, "name": name, "category": "web", "tier": tier})
+    }
+
+    fn seed(tools: Vec<Value>) -> Value {
+        json!({"version": "rustdev-hub-seed-v7.0", "tools": tools})
+    }
+
+    macro_rules! app {
+        ($state:expr) => {
+            init_service(
+                App::new()
+                    .app_data($state.clone())
+                    .route("/api/v1/openapi.json", web::get().to(openapi))
+                    .route("/api/v1/{collection}", web::get().to(list))
+                    .route("/api/v1/{collection}/{slug}", web::get().to(item)),
+            )
+            .await
+        };
+    }
+
+    fn get(uri: &str) -> TestRequest {
+        TestRequest::get()
+            .uri(uri)
+            .insert_header((header::HOST, "localhost"))
+    }
+
+    #[test]
+    fn fields_keep_slug_and_refuse_unknown_names() {
+        let known = fields(Collection::Tools);
+        assert_eq!(parse_fields(Some(" "), known), Ok(None));
+        assert_eq!(
+            parse_fields(Some("name,name, tier"), known),
+            Ok(Some(vec!["slug".into(), "name".into(), "tier".into()]))
+        );
+        assert_eq!(
+            parse_fields(Some("name,secret"), known),
+            Err("Unknown field `secret`".to_string())
+        );
+        assert_eq!(
+            select_fields(tool("a", "A", None), Some(&["slug".into()])),
+            json!({"slug": "a"})
+        );
+    }
+
+    #[test]
+    fn sorts_by_several_keys_with_nulls_last() {
+        let known = fields(Collection::Tools);
+        let mut items = vec![
+            tool("b", "beta", None),
+            tool("a", "Alpha", Some("1")),
+            tool("c", "gamma", Some("1")),
+            tool("d", "Delta", Some("2")),
+        ];
+        let slugs = |items: &[Value]| -> Vec<String> {
+            items
+                .iter()
+                .map(|i| i["slug"].as_str().unwrap().to_string())
+                .collect()
+        };
+        sort_items(&mut items, &parse_sort("-tier,name", known).unwrap());
+        assert_eq!(slugs(&items), ["d", "a", "c", "b"]);
+        sort_items(&mut items, &parse_sort("tier, -name", known).unwrap());
+        assert_eq!(slugs(&items), ["c", "a", "d", "b"]);
+        assert_eq!(
+            parse_sort("name,-secret", known),
+            Err("Cannot sort by unknown field `secret`".to_string())
+        );
+    }
+
+    #[actix_web::test]
+    async fn pages_are_bounded_and_linked() {
+        let dir = tempfile::tempdir().unwrap();
+        let tools = (1..=5).map(|n| tool(&format!("t{n}"), &format!("Tool {n}"), None));
+        let state =
+            testing::site_state(&dir.path().join("seed.json"), &seed(tools.collect())).await;
+        let app = app!(state);
+
+        let res = call_service(&app, get("/api/v1/tools?per_page=2&page=2").to_request()).await;
+        assert_eq!(res.status(), 200);
+        let body: Value = read_body_json(res).await;
+        assert_eq!(body["data"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            body["meta"],
+            json!({"page": 2, "per_page": 2, "total": 5, "total_pages": 3})
+        );
+        assert_eq!(body["links"]["next"], "/api/v1/tools?page=3&per_page=2");
+        assert_eq!(body["links"]["prev"], "/api/v1/tools?page=1&per_page=2");
+
+        let res = call_service(&app, get("/api/v1/tools?per_page=2&page=9").to_request()).await;
+        let body: Value = read_body_json(res).await;
+        assert_eq!(body["data"], json!([]));
+        assert_eq!(body["links"]["next"], Value::Null);
+        assert_eq!(body["links"]["prev"], "/api/v1/tools?page=3&per_page=2");
+
+        for query in [
+            "per_page=0",
+            "per_page=101",
+            "page=0",
+            "page=-1",
+            "sort=secret",
+        ] {
+            let res = call_service(&app, get(&format!("/api/v1/tools?{query}")).to_request()).await;
+            assert_eq!(res.status(), 400, "{query}");
+        }
+        let res = call_service(&app, get("/api/v1/tools?per_page=100").to_request()).await;
+        assert_eq!(res.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn items_honour_fields_and_unknown_slugs_are_404() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = testing::site_state(
+            &dir.path().join("seed.json"),
+            &seed(vec![tool("anchor", "Anchor", Some("core"))]),
+        )
+        .await;
+        let app = app!(state);
+
+        let res = call_service(&app, get("/api/v1/tools/anchor?fields=name").to_request()).await;
+        let body: Value = read_body_json(res).await;
+        assert_eq!(body, json!({"data": {"slug": "anchor", "name": "Anchor"}}));
+
+        let res = call_service(&app, get("/api/v1/tools/nope").to_request()).await;
+        assert_eq!(res.status(), 404);
+        let res = call_service(&app, get("/api/v1/gadgets").to_request()).await;
+        assert_eq!(res.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn etags_follow_content_and_normalized_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("seed.json");
+        let state = testing::site_state(&path, &seed(vec![tool("anchor", "Anchor", None)])).await;
+        let app = app!(state);
+        let etag = |res: &actix_web::dev::ServiceResponse| {
+            res.headers().get(header::ETAG).unwrap().clone()
+        };
+
+        let uri = "/api/v1/tools?sort=name&fields=name";
+        let first = call_service(&app, get(uri).to_request()).await;
+        let reordered = call_service(
+            &app,
+            get("/api/v1/tools?fields=name&sort=name").to_request(),
+        )
+        .await;
+        let other = call_service(&app, get("/api/v1/tools?fields=tier").to_request()).await;
+        assert_eq!(etag(&first), etag(&reordered));
+        assert_ne!(etag(&first), etag(&other));
+        let body = read_body(first).await;
+        assert_eq!(body, read_body(reordered).await);
+
+        let tag = {
+            let res = call_service(&app, get(uri).to_request()).await;
+            etag(&res)
+        };
+        let res = call_service(
+            &app,
+            get(uri)
+                .insert_header((header::IF_NONE_MATCH, tag.clone()))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), 304);
+        let res = call_service(
+            &app,
+            get("/api/v1/tools/anchor")
+                .insert_header((header::IF_NONE_MATCH, tag.clone()))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), 200);
+
+        std::fs::write(
+            &path,
+            seed(vec![tool("anchor", "Anchor 2", None)]).to_string(),
+        )
+        .unwrap();
+        crate::reload_site(&state).await.unwrap();
+        let res = call_service(
+            &app,
+            get(uri)
+                .insert_header((header::IF_NONE_MATCH, tag))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn openapi_describes_every_collection() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = testing::site_state(&dir.path().join("seed.json"), &seed(vec![])).await;
+        let app = app!(state);
+        let res = call_service(&app, get("/api/v1/openapi.json").to_request()).await;
+        assert_eq!(res.status(), 200);
+        let document: Value = read_body_json(res).await;
+
+        assert_eq!(document["openapi"], "3.0.3");
+        for collection in COLLECTIONS {
+            let name = collection.path();
+            let schema = schema_name(collection);
+            assert!(
+                document["paths"][format!("/{name}")]["get"].is_object(),
+                "{name}"
+            );
+            assert!(
+                document["paths"][format!("/{name}/{{slug}}")]["get"].is_object(),
+                "{name}"
+            );
+            assert!(
+                document["components"]["schemas"][schema].is_object(),
+                "{schema}"
+            );
+        }
+        // Every reference resolves to a component schema.
+        let text = document.to_string();
+        for reference in text.split("\"#/components/schemas/").skip(1) {
+            let name = &reference[..reference.find('"').unwrap()];
+            assert!(
+                document["components"]["schemas"][name].is_object(),
+                "{name}"
+            );
+        }
+    }
+}
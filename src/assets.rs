@@ -0,0 +1,326 @@
+use actix_files::NamedFile;
+use actix_web::{
+    http::header::{self, HeaderValue},
+    HttpRequest, HttpResponse,
+};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::conditional::content_hash;
+
+/// URL prefix the asset directory is mounted under.
+pub(crate) const ASSETS_PREFIX: &str = "/assets/";
+/// Fingerprinted URLs change whenever the file does, so they never go stale.
+const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+/// Plain asset URLs can change in place and are revalidated.
+const ASSET_CACHE_CONTROL: &str = "public, max-age=3600";
+const FINGERPRINT_LEN: usize = 12;
+
+/// Files under the asset directory, keyed by their path relative to it, with
+/// a content-hashed alias for each (`labels/crypto.jpg` ->
+/// `labels/crypto.3f9a0c1d2e4b.jpg`).
+#[derive(Clone, Debug, Default)]
+pub(crate) struct AssetManifest {
+    dir: PathBuf,
+    fingerprinted: HashMap<String, String>,
+    originals: HashMap<String, String>,
+}
+
+impl AssetManifest {
+    /// Hashes every regular file under `dir`. A missing directory yields an
+    /// empty manifest.
+    pub(crate) fn build(dir: &Path) -> io::Result<Self> {
+        let mut manifest = Self {
+            dir: dir.to_path_buf(),
+            ..Self::default()
+        };
+        for (relative, path) in files(dir)? {
+            let hash = content_hash(&fs::read(&path)?, FINGERPRINT_LEN);
+            let hashed = fingerprint(&relative, &hash);
+            manifest.originals.insert(hashed.clone(), relative.clone());
+            manifest.fingerprinted.insert(relative, hashed);
+        }
+        Ok(manifest)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.fingerprinted.len()
+    }
+
+    /// The fingerprinted URL for a site-relative `/assets/...` path, or
+    /// `None` when the path is not a known asset.
+    pub(crate) fn url(&self, path: &str) -> Option<String> {
+        let relative = path.strip_prefix(ASSETS_PREFIX)?;
+        self.fingerprinted
+            .get(relative)
+            .map(|hashed| format!("{ASSETS_PREFIX}{hashed}"))
+    }
+
+    /// Resolves a request path below `/assets/` to a file on disk. Only
+    /// files found by `build` are served, which rules out traversal.
+    fn resolve(&self, relative: &str) -> Option<(PathBuf, bool)> {
+        if let Some(original) = self.originals.get(relative) {
+            return Some((self.dir.join(original), true));
+        }
+        self.fingerprinted
+            .contains_key(relative)
+            .then(|| (self.dir.join(relative), false))
+    }
+
+    /// Serves `relative` with MIME type, range and conditional request
+    /// handling from `NamedFile`. `None` means there is no such asset.
+    pub(crate) async fn serve(&self, relative: &str, req: &HttpRequest) -> Option<HttpResponse> {
+        let (path, immutable) = self.resolve(relative)?;
+        let file = match NamedFile::open_async(&path).await {
+            Ok(file) => file,
+            Err(err) => {
+                tracing::warn!(path = %path.display(), %err, "asset read failed");
+                return None;
+            }
+        };
+
+        let mut res = file
+            .disable_content_disposition()
+            .prefer_utf8(true)
+            .into_response(req);
+        res.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(if immutable {
+                IMMUTABLE_CACHE_CONTROL
+            } else {
+                ASSET_CACHE_CONTROL
+            }),
+        );
+        Some(res)
+    }
+}
+
+/// Regular files under `dir`, with their `/`-separated paths relative to
+/// it. Symlinks are skipped so nothing outside the directory is reached.
+fn files(dir: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            if file_type.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            if let Some(relative) = path
+                .strip_prefix(dir)
+                .ok()
+                .and_then(|p| p.to_str())
+                .map(|p| p.replace('\\', "/"))
+            {
+                files.push((relative, path));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Changes whenever a file under `dir` is added, removed or rewritten,
+/// judged by names, sizes and modification times, so watching the
+/// directory does not mean reading every file.
+pub(crate) fn dir_version(dir: &Path) -> io::Result<String> {
+    let mut listing = Vec::new();
+    for (relative, path) in files(dir)? {
+        let meta = fs::metadata(&path)?;
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        listing.push(format!(
+            "{relative}\0{}\0{}",
+            meta.len(),
+            modified.as_nanos()
+        ));
+    }
+    listing.sort();
+    Ok(content_hash(listing.join("\n").as_bytes(), 16))
+}
+
+/// Inserts `hash` before the extension of the file name.
+fn fingerprint(relative: &str, hash: &str) -> String {
+    let (dir, name) = match relative.rsplit_once('/') {
+        Some((dir, name)) => (Some(dir), name),
+        None => (None, relative),
+    };
+    let name = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{stem}.{hash}.{ext}"),
+        _ => format!("{name}.{hash}"),
+    };
+    match dir {
+        Some(dir) => format!("{dir}/{name}"),
+        None => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{content_versions, reload_site, testing, DATA_PATH, DEFAULT_ASSETS_DIR};
+    use actix_web::{
+        http::StatusCode,
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App,
+    };
+    use serde_json::{json, Value};
+
+    const CSS: &str = "body { color: #c9d1d9; }\n";
+
+    fn seed() -> Value {
+        json!({
+            "version": "rustdev-hub-seed-v7.0",
+            "tools": [{"slug": "anchor", "name": "Anchor"}],
+        })
+    }
+
+    fn get(uri: &str) -> TestRequest {
+        TestRequest::get()
+            .uri(uri)
+            .insert_header((header::HOST, "localhost"))
+    }
+
+    #[actix_web::test]
+    async fn only_files_in_the_manifest_are_served() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets = dir.path().join("assets");
+        fs::create_dir_all(assets.join("css")).unwrap();
+        fs::write(assets.join("css/site.css"), CSS).unwrap();
+        fs::write(dir.path().join("secret.txt"), "secret").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir.path().join("secret.txt"), assets.join("link.txt")).unwrap();
+        let state =
+            testing::site_state_with_assets(&dir.path().join("seed.json"), &seed(), &assets).await;
+        let app = init_service(
+            App::new()
+                .app_data(state)
+                .service(web::resource("/assets/{path:.*}").route(web::get().to(crate::asset))),
+        )
+        .await;
+
+        let res = call_service(&app, get("/assets/css/site.css").to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        for uri in [
+            "/assets/../secret.txt",
+            "/assets/css/../../secret.txt",
+            "/assets/%2e%2e/secret.txt",
+            "/assets/%2E%2E%2Fsecret.txt",
+            "/assets/css/..%2f..%2fsecret.txt",
+            "/assets/../Cargo.toml",
+            "/assets/%2e%2e/%2e%2e/Cargo.toml",
+            "/assets//etc/passwd",
+            "/assets/link.txt",
+        ] {
+            let res = call_service(&app, get(uri).to_request()).await;
+            assert_eq!(res.status(), StatusCode::NOT_FOUND, "{uri}");
+            let body = read_body(res).await;
+            assert!(!body.starts_with(b"secret"), "{uri}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn fingerprinted_urls_are_immutable_and_ranges_are_honoured() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets = dir.path().join("assets");
+        fs::create_dir_all(&assets).unwrap();
+        fs::write(assets.join("site.css"), CSS).unwrap();
+        let state =
+            testing::site_state_with_assets(&dir.path().join("seed.json"), &seed(), &assets).await;
+        let app = init_service(
+            App::new()
+                .app_data(state.clone())
+                .service(web::resource("/assets/{path:.*}").route(web::get().to(crate::asset))),
+        )
+        .await;
+        let manifest = AssetManifest::build(&assets).unwrap();
+        let url = manifest.url("/assets/site.css").unwrap();
+        assert!(
+            url.starts_with("/assets/site.") && url.ends_with(".css"),
+            "{url}"
+        );
+        assert_eq!(url.len(), "/assets/site..css".len() + FINGERPRINT_LEN);
+        assert_eq!(manifest.url("/assets/missing.css"), None);
+
+        let res = call_service(&app, get(&url).to_request()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CACHE_CONTROL).unwrap(),
+            IMMUTABLE_CACHE_CONTROL
+        );
+        assert_eq!(read_body(res).await, CSS.as_bytes());
+        let res = call_service(&app, get("/assets/site.css").to_request()).await;
+        assert_eq!(
+            res.headers().get(header::CACHE_CONTROL).unwrap(),
+            ASSET_CACHE_CONTROL
+        );
+        let stale = format!("/assets/site.{}.css", "0".repeat(FINGERPRINT_LEN));
+        let res = call_service(&app, get(&stale).to_request()).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let res = call_service(
+            &app,
+            get(&url)
+                .insert_header((header::RANGE, "bytes=0-3"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            res.headers()
+                .get(header::CONTENT_RANGE)
+                .unwrap()
+                .to_str()
+                .unwrap(),
+            format!("bytes 0-3/{}", CSS.len())
+        );
+        assert_eq!(read_body(res).await, &CSS.as_bytes()[..4]);
+    }
+
+    #[actix_web::test]
+    async fn asset_changes_are_picked_up_on_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets = dir.path().join("assets");
+        fs::create_dir_all(&assets).unwrap();
+        let state =
+            testing::site_state_with_assets(&dir.path().join("seed.json"), &seed(), &assets).await;
+        assert_eq!(content_versions(&state.config).await, state.loaded_from());
+
+        fs::write(assets.join("site.css"), CSS).unwrap();
+        assert_ne!(content_versions(&state.config).await, state.loaded_from());
+        reload_site(&state).await.unwrap();
+        let site = state.snapshot().unwrap();
+        assert!(site.assets.url("/assets/site.css").is_some());
+        assert_eq!(content_versions(&state.config).await, state.loaded_from());
+    }
+
+    #[test]
+    fn the_seed_label_backgrounds_ship_with_the_repository() {
+        let manifest = AssetManifest::build(Path::new(DEFAULT_ASSETS_DIR)).unwrap();
+        let seed: Value = serde_json::from_slice(&fs::read(DATA_PATH).unwrap()).unwrap();
+        let backgrounds = seed
+            .pointer("/site/assets/label_backgrounds")
+            .and_then(Value::as_object)
+            .unwrap();
+        assert!(!backgrounds.is_empty());
+        for url in backgrounds.values() {
+            let url = url.as_str().unwrap();
+            assert!(manifest.url(url).is_some(), "{url} is missing");
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    env,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::content::Collection;
+
+/// Where edits are recorded unless `AUDIT_LOG` says otherwise. It is kept
+/// out of `static/` and out of git, since entries name editors.
+const DEFAULT_AUDIT_LOG: &str = "data/content-audit.jsonl";
+
+/// One field that differs between two versions of an entity, addressed by
+/// JSON pointer. The empty pointer stands for the whole entity, as when it
+/// is created or deleted.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Change {
+    pub(crate) path: String,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub(crate) before: Value,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub(crate) after: Value,
+}
+
+/// Fields are compared one by one, recursing into objects; arrays and
+/// scalars are compared whole. A field set to `null` reads as absent.
+pub(crate) fn diff(before: &Value, after: &Value) -> Vec<Change> {
+    fn walk(path: &str, before: &Value, after: &Value, changes: &mut Vec<Change>) {
+        match (before, after) {
+            (Value::Object(old), Value::Object(new)) => {
+                for (key, value) in old {
+                    let path = format!("{path}/{}", escape(key));
+                    walk(&path, value, new.get(key).unwrap_or(&Value::Null), changes);
+                }
+                for (key, value) in new.iter().filter(|(key, _)| !old.contains_key(*key)) {
+                    walk(
+                        &format!("{path}/{}", escape(key)),
+                        &Value::Null,
+                        value,
+                        changes,
+                    );
+                }
+            }
+            _ if before == after => {}
+            _ => changes.push(Change {
+                path: path.to_string(),
+                before: before.clone(),
+                after: after.clone(),
+            }),
+        }
+    }
+
+    let mut changes = Vec::new();
+    walk("", before, after, &mut changes);
+    changes
+}
+
+/// RFC 6901 escaping for a key in a JSON pointer.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// A recorded content edit: who changed which entity, when, and both
+/// versions, so the change can be reviewed and undone.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Entry {
+    pub(crate) id: String,
+    pub(crate) at: String,
+    pub(crate) actor: String,
+    pub(crate) action: String,
+    pub(crate) collection: String,
+    pub(crate) slug: String,
+    /// The entry this edit undid, for reverts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) reverts: Option<String>,
+    pub(crate) diff: Vec<Change>,
+    /// `null` when the edit created the entity.
+    pub(crate) before: Value,
+    /// `null` when the edit deleted the entity.
+    pub(crate) after: Value,
+}
+
+impl Entry {
+    pub(crate) fn new(
+        actor: &str,
+        action: &str,
+        collection: Collection,
+        slug: &str,
+        reverts: Option<String>,
+        before: Value,
+        after: Value,
+    ) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            at: OffsetDateTime::now_utc()
+                .format(&Rfc3339)
+                .unwrap_or_default(),
+            actor: actor.to_string(),
+            action: action.to_string(),
+            collection: collection.path().to_string(),
+            slug: slug.to_string(),
+            reverts,
+            diff: diff(&before, &after),
+            before,
+            after,
+        }
+    }
+}
+
+/// The append-only JSON Lines file edits are recorded in. Lines are only
+/// ever added; nothing in the server rewrites or truncates it.
+pub(crate) struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    pub(crate) fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// `AUDIT_LOG` overrides the file's location.
+    pub(crate) fn from_env() -> Self {
+        Self::new(env::var("AUDIT_LOG").unwrap_or_else(|_| DEFAULT_AUDIT_LOG.into()))
+    }
+
+    /// Appends `entry` and syncs it to disk before returning. The file and
+    /// its directory are created on the first edit.
+    pub(crate) async fn append(&self, entry: &Entry) -> io::Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir)?;
+            }
+            let mut file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)?;
+            file.write_all(&line)?;
+            file.sync_data()
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+
+    /// The recorded edits of one entity, newest first. Lines that do not
+    /// parse are skipped with a warning rather than hiding the rest.
+    pub(crate) async fn history(
+        &self,
+        collection: Collection,
+        slug: &str,
+    ) -> io::Result<Vec<Entry>> {
+        let path = self.path.clone();
+        let (collection, slug) = (collection.path(), slug.to_string());
+        tokio::task::spawn_blocking(move || {
+            let file = match std::fs::File::open(&path) {
+                Ok(file) => file,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+                Err(err) => return Err(err),
+            };
+            let mut entries = Vec::new();
+            for (number, line) in io::BufReader::new(file).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str::<Entry>(&line) {
+                    Ok(entry) if entry.collection == collection && entry.slug == slug => {
+                        entries.push(entry)
+                    }
+                    Ok(_) => {}
+                    Err(err) => tracing::warn!(
+                        path = %path.display(),
+                        line = number + 1,
+                        %err,
+                        "unreadable audit log entry"
+                    ),
+                }
+            }
+            entries.reverse();
+            Ok(entries)
+        })
+        .await
+        .map_err(io::Error::other)?
+    }
+
+    pub(crate) async fn entry(
+        &self,
+        collection: Collection,
+        slug: &str,
+        id: &str,
+    ) -> io::Result<Option<Entry>> {
+        Ok(self
+            .history(collection, slug)
+            .await?
+            .into_iter()
+            .find(|entry| entry.id == id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{admin::Edit, testing};
+    use actix_web::http::StatusCode;
+    use serde_json::json;
+
+    #[actix_web::test]
+    async fn the_first_append_creates_the_log_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = AuditLog {
+            path: dir.path().join("data/content-audit.jsonl"),
+        };
+        let entry = Entry::new(
+            "editor",
+            "update",
+            Collection::Tools,
+            "anchor",
+            None,
+            json!({"name": "Anchor"}),
+            json!({"name": "Anchor X"}),
+        );
+        log.append(&entry).await.unwrap();
+
+        let history = log.history(Collection::Tools, "anchor").await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].diff[0].path, "/name");
+    }
+
+    #[test]
+    fn diffs_address_changed_fields_by_pointer() {
+        let before = json!({
+            "name": "Anchor",
+            "links": {"docs": "https://a.dev", "a/b~c": "1"},
+            "tags": ["a", "b"],
+            "gone": 1,
+            "unset": null,
+        });
+        let after = json!({
+            "name": "Anchor X",
+            "links": {"docs": "https://a.dev", "a/b~c": "2"},
+            "tags": ["a", "b", "c"],
+            "added": true,
+        });
+        let mut changes: Vec<_> = diff(&before, &after)
+            .into_iter()
+            .map(|change| (change.path, change.before, change.after))
+            .collect();
+        changes.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            changes,
+            [
+                ("/added".to_string(), Value::Null, json!(true)),
+                ("/gone".to_string(), json!(1), Value::Null),
+                ("/links/a~1b~0c".to_string(), json!("1"), json!("2")),
+                ("/name".to_string(), json!("Anchor"), json!("Anchor X")),
+                (
+                    "/tags".to_string(),
+                    json!(["a", "b"]),
+                    json!(["a", "b", "c"])
+                ),
+            ]
+        );
+
+        assert!(diff(&before, &before).is_empty());
+        let created = diff(&Value::Null, &after);
+        assert_eq!(created.len(), 1);
+        assert_eq!(created[0].path, "");
+        assert_eq!(created[0].after, after);
+    }
+
+    #[actix_web::test]
+    async fn entries_are_only_ever_appended() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let log = AuditLog::new(&path);
+        let entry = |slug: &str, name: &str| {
+            Entry::new(
+                "ed",
+                "update",
+                Collection::Tools,
+                slug,
+                None,
+                json!({"slug": slug}),
+                json!({"slug": slug, "name": name}),
+            )
+        };
+
+        let first = entry("anchor", "One");
+        log.append(&first).await.unwrap();
+        log.append(&entry("borsh", "Other")).await.unwrap();
+        let mut text = std::fs::read_to_string(&path).unwrap();
+        // A damaged line hides nothing after it.
+        text.push_str("{not json\n");
+        std::fs::write(&path, &text).unwrap();
+        let second = entry("anchor", "Two");
+        log.append(&second).await.unwrap();
+
+        let after = std::fs::read_to_string(&path).unwrap();
+        assert!(after.starts_with(&text));
+        assert_eq!(after.lines().count(), 4);
+        let history = log.history(Collection::Tools, "anchor").await.unwrap();
+        let ids: Vec<_> = history.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, [second.id.as_str(), first.id.as_str()]);
+        assert_eq!(history[0].after["name"], "Two");
+        assert!(log
+            .entry(Collection::Tools, "anchor", &first.id)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(log
+            .entry(Collection::Events, "anchor", &first.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[actix_web::test]
+    async fn reverts_put_back_the_recorded_version_through_validation() {
+        let dir = tempfile::tempdir().unwrap();
+        let seed_path = dir.path().join("seed.json");
+        let audit_path = dir.path().join("audit.jsonl");
+        let seed = json!({
+            "version": "rustdev-hub-seed-v7.0",
+            "tools": [{"slug": "anchor", "name": "Anchor"}],
+        });
+        let state = testing::site_state(&seed_path, &seed).await;
+        let editor = testing::editor(&state, &audit_path);
+        let stored =
+            || -> Value { serde_json::from_slice(&std::fs::read(&seed_path).unwrap()).unwrap() };
+        editor
+            .apply(
+                &state,
+                "ed",
+                Collection::Tools,
+                Edit::Update("anchor".into(), json!({"name": "Anchor X"})),
+            )
+            .await
+            .unwrap();
+        let update = editor
+            .history(Collection::Tools, "anchor")
+            .await
+            .unwrap()
+            .remove(0);
+        editor
+            .apply(
+                &state,
+                "ed",
+                Collection::Tools,
+                Edit::Delete("anchor".into()),
+            )
+            .await
+            .unwrap();
+        let delete = editor
+            .history(Collection::Tools, "anchor")
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!(stored()["tools"], json!([]));
+
+        // Undoing the delete brings back the updated entity.
+        let restored = editor
+            .revert(&state, "ed", Collection::Tools, "anchor", &delete.id)
+            .await
+            .unwrap();
+        assert_eq!(restored["name"], "Anchor X");
+        assert_eq!(stored()["tools"][0], restored);
+        let revert = editor
+            .history(Collection::Tools, "anchor")
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!(revert.action, "revert");
+        assert_eq!(revert.reverts.as_deref(), Some(delete.id.as_str()));
+
+        editor
+            .revert(&state, "ed", Collection::Tools, "anchor", &update.id)
+            .await
+            .unwrap();
+        assert_eq!(stored(), seed);
+        assert_eq!(state.snapshot().unwrap().rustdev.tools[0].name, "Anchor");
+
+        // A recorded version that no longer validates is not put back.
+        let mut broken = Entry::new(
+            "ed",
+            "update",
+            Collection::Tools,
+            "anchor",
+            None,
+            json!({"slug": "anchor", "name": 7}),
+            json!({"slug": "anchor", "name": "Anchor"}),
+        );
+        broken.id = "broken".into();
+        AuditLog::new(&audit_path).append(&broken).await.unwrap();
+        let err = editor
+            .revert(&state, "ed", Collection::Tools, "anchor", "broken")
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(stored(), seed);
+    }
+}
@@ -0,0 +1,599 @@
+use actix_web::{
+    body::BoxBody,
+    cookie::{time::Duration as CookieAge, Cookie, SameSite},
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderValue},
+        StatusCode,
+    },
+    web, Error, HttpMessage, HttpRequest, HttpResponse,
+};
+use argon2::{
+    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    env,
+    future::Future,
+    io::{self, BufRead},
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use crate::{admin::digests_match, admin_ui, is_allowed_host, SiteState};
+
+pub(crate) const SESSION_COOKIE: &str = "rustdev_admin";
+const LOGIN_COOKIE: &str = "rustdev_login";
+const LOGIN_PATH: &str = "/admin/login";
+const SESSION_TTL: Duration = Duration::from_secs(12 * 60 * 60);
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+
+/// A PHC-format Argon2id hash of `password`, as stored in the accounts file.
+pub(crate) fn hash_password(password: &str) -> io::Result<String> {
+    let salt = SaltString::encode_b64(uuid::Uuid::new_v4().as_bytes())
+        .map_err(|err| invalid(err.to_string()))?;
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| invalid(err.to_string()))
+}
+
+/// `rustdev hash-password`: reads a password from stdin and prints the line
+/// to add to the accounts file.
+pub(crate) fn hash_password_command() -> io::Result<()> {
+    let mut password = String::new();
+    io::stdin().lock().read_line(&mut password)?;
+    let password = password.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        return Err(invalid("no password on stdin"));
+    }
+    println!("{}", hash_password(password)?);
+    Ok(())
+}
+
+/// 256 bits from the OS generator, hex-encoded.
+fn random_token() -> String {
+    format!(
+        "{}{}",
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+fn secrets_match(expected: &str, presented: &str) -> bool {
+    digests_match(&Sha256::digest(expected.as_bytes()).into(), presented)
+}
+
+/// A signed-in editor.
+#[derive(Clone, Debug)]
+pub(crate) struct Session {
+    pub(crate) user: String,
+    /// Every form posted during the session must carry this.
+    pub(crate) csrf: String,
+    expires: Instant,
+}
+
+impl Session {
+    pub(crate) fn csrf_ok(&self, presented: &str) -> bool {
+        secrets_match(&self.csrf, presented)
+    }
+}
+
+/// Editor accounts and their sessions. Sessions live in memory, so a
+/// restart signs everyone out.
+pub(crate) struct Accounts {
+    /// Name to PHC-format password hash.
+    users: HashMap<String, String>,
+    /// Checked for unknown names, so they take as long as wrong passwords.
+    decoy: String,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl Accounts {
+    /// `ADMIN_USERS_FILE` lists one `name:hash` per line, hashes made with
+    /// `rustdev hash-password`. `None` when unset, which leaves `/admin` off.
+    pub(crate) fn from_env() -> io::Result<Option<Self>> {
+        let Ok(path) = env::var("ADMIN_USERS_FILE") else {
+            return Ok(None);
+        };
+        let text = std::fs::read_to_string(&path)?;
+        let mut users = HashMap::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, hash) = line
+                .split_once(':')
+                .ok_or_else(|| invalid(format!("{path}:{}: expected name:hash", number + 1)))?;
+            PasswordHash::new(hash.trim())
+                .map_err(|err| invalid(format!("{path}:{}: {err}", number + 1)))?;
+            users.insert(name.trim().to_string(), hash.trim().to_string());
+        }
+        if users.is_empty() {
+            return Err(invalid(format!("{path} lists no accounts")));
+        }
+        Self::new(users).map(Some)
+    }
+
+    /// `users` maps names to PHC-format password hashes.
+    pub(crate) fn new(users: HashMap<String, String>) -> io::Result<Self> {
+        Ok(Self {
+            users,
+            decoy: hash_password(&random_token())?,
+            sessions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Deliberately slow; call off the async workers.
+    fn verify(&self, user: &str, password: &str) -> bool {
+        let (known, hash) = match self.users.get(user) {
+            Some(hash) => (true, hash.as_str()),
+            None => (false, self.decoy.as_str()),
+        };
+        let matches = PasswordHash::new(hash).is_ok_and(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        });
+        known && matches
+    }
+
+    fn sessions(&self) -> std::sync::MutexGuard<'_, HashMap<String, Session>> {
+        self.sessions.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Starts a session and returns its ID.
+    pub(crate) fn start(&self, user: &str) -> String {
+        let now = Instant::now();
+        let id = random_token();
+        let mut sessions = self.sessions();
+        sessions.retain(|_, session| session.expires > now);
+        sessions.insert(
+            id.clone(),
+            Session {
+                user: user.to_string(),
+                csrf: random_token(),
+                expires: now + SESSION_TTL,
+            },
+        );
+        id
+    }
+
+    pub(crate) fn session(&self, id: &str) -> Option<Session> {
+        self.sessions()
+            .get(id)
+            .filter(|session| session.expires > Instant::now())
+            .cloned()
+    }
+
+    fn end(&self, id: &str) {
+        self.sessions().remove(id);
+    }
+}
+
+/// The session `require_session` found for the request.
+pub(crate) fn session(req: &HttpRequest) -> Option<Session> {
+    req.extensions().get::<Session>().cloned()
+}
+
+/// Only paths inside `/admin` are followed after signing in.
+fn safe_next(next: &str) -> &str {
+    if next.starts_with("/admin") && !next.starts_with("//") && !next.contains('\\') {
+        next
+    } else {
+        "/admin"
+    }
+}
+
+pub(crate) fn see_other(location: &str) -> HttpResponse {
+    HttpResponse::SeeOther()
+        .append_header((header::LOCATION, location))
+        .finish()
+}
+
+fn cookie(
+    req: &HttpRequest,
+    name: &'static str,
+    value: String,
+    path: &'static str,
+) -> Cookie<'static> {
+    Cookie::build(name, value)
+        .path(path)
+        .http_only(true)
+        .secure(req.connection_info().scheme() == "https")
+        .same_site(SameSite::Strict)
+        .finish()
+}
+
+fn expired(req: &HttpRequest, name: &'static str, path: &'static str) -> Cookie<'static> {
+    let mut cookie = cookie(req, name, String::new(), path);
+    cookie.set_max_age(CookieAge::ZERO);
+    cookie
+}
+
+/// Middleware guarding `/admin`: requests must come for one of the site's
+/// hosts and, apart from the sign-in page, belong to a live session, which
+/// is made available to handlers through `session`. Responses are never
+/// cached.
+pub(crate) fn require_session<S>(
+    mut req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error>,
+{
+    let rejection = if !is_allowed_host(req.parts_mut().0) {
+        Some(HttpResponse::NotFound().finish())
+    } else if req.path() == LOGIN_PATH {
+        None
+    } else {
+        let session = req
+            .app_data::<web::Data<Accounts>>()
+            .zip(req.cookie(SESSION_COOKIE))
+            .and_then(|(accounts, id)| accounts.session(id.value()));
+        match session {
+            Some(session) => {
+                req.extensions_mut().insert(session);
+                None
+            }
+            None => {
+                let next = req
+                    .uri()
+                    .path_and_query()
+                    .map(|pq| pq.as_str())
+                    .unwrap_or("/admin");
+                let query = url::form_urlencoded::Serializer::new(String::new())
+                    .append_pair("next", next)
+                    .finish();
+                Some(see_other(&format!("{LOGIN_PATH}?{query}")))
+            }
+        }
+    };
+    let outcome = match rejection {
+        None => Ok(srv.call(req)),
+        Some(res) => Err(req.into_response(res)),
+    };
+    async move {
+        let mut res = match outcome {
+            Ok(fut) => fut.await?,
+            Err(res) => res,
+        };
+        res.headers_mut()
+            .insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+        Ok(res)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct LoginQuery {
+    next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct LoginForm {
+    user: String,
+    password: String,
+    /// Missing is treated like wrong, rather than as a malformed form.
+    #[serde(default)]
+    csrf: String,
+    next: Option<String>,
+}
+
+/// The sign-in page. Its form is tied to a cookie set here, so another site
+/// cannot sign a browser in.
+fn login_page(
+    req: &HttpRequest,
+    state: &SiteState,
+    status: StatusCode,
+    user: &str,
+    next: &str,
+    error: Option<&str>,
+) -> HttpResponse {
+    let token = random_token();
+    let mut res = admin_ui::render(
+        state,
+        status,
+        "admin/login",
+        &json!({
+            "title": "Sign in",
+            "login_csrf": token,
+            "name": user,
+            "next": safe_next(next),
+            "error": error,
+        }),
+    );
+    let _ = res.add_cookie(&cookie(req, LOGIN_COOKIE, token, LOGIN_PATH));
+    res
+}
+
+pub(crate) async fn login_form(
+    req: HttpRequest,
+    query: web::Query<LoginQuery>,
+    state: web::Data<SiteState>,
+) -> HttpResponse {
+    login_page(
+        &req,
+        &state,
+        StatusCode::OK,
+        "",
+        query.next.as_deref().unwrap_or("/admin"),
+        None,
+    )
+}
+
+pub(crate) async fn login(
+    req: HttpRequest,
+    form: web::Form<LoginForm>,
+    state: web::Data<SiteState>,
+    accounts: web::Data<Accounts>,
+) -> HttpResponse {
+    let form = form.into_inner();
+    let next = form.next.as_deref().unwrap_or("/admin");
+    let form_ok = req
+        .cookie(LOGIN_COOKIE)
+        .is_some_and(|cookie| secrets_match(cookie.value(), &form.csrf));
+    if !form_ok {
+        return login_page(
+            &req,
+            &state,
+            StatusCode::FORBIDDEN,
+            &form.user,
+            next,
+            Some("The sign-in form expired. Please try again."),
+        );
+    }
+
+    let (user, password) = (form.user.trim().to_string(), form.password);
+    let checked = accounts.clone();
+    let verified = tokio::task::spawn_blocking(move || {
+        let verified = checked.verify(&user, &password);
+        (user, verified)
+    })
+    .await;
+    let (user, verified) = match verified {
+        Ok(result) => result,
+        Err(err) => {
+            tracing::error!(%err, "password check failed");
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    if !verified {
+        tracing::warn!(user = %user, "admin sign-in failed");
+        return login_page(
+            &req,
+            &state,
+            StatusCode::UNAUTHORIZED,
+            &user,
+            next,
+            Some("Unknown name or wrong password."),
+        );
+    }
+
+    tracing::info!(user = %user, "admin signed in");
+    let id = accounts.start(&user);
+    let mut res = see_other(safe_next(next));
+    let _ = res.add_cookie(&cookie(&req, SESSION_COOKIE, id, "/admin"));
+    let _ = res.add_cookie(&expired(&req, LOGIN_COOKIE, LOGIN_PATH));
+    res
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct CsrfForm {
+    #[serde(default)]
+    csrf: String,
+}
+
+pub(crate) async fn logout(
+    req: HttpRequest,
+    form: web::Form<CsrfForm>,
+    accounts: web::Data<Accounts>,
+) -> HttpResponse {
+    let Some(session) = session(&req) else {
+        return see_other(LOGIN_PATH);
+    };
+    if !session.csrf_ok(&form.csrf) {
+        return HttpResponse::Forbidden().finish();
+    }
+    if let Some(id) = req.cookie(SESSION_COOKIE) {
+        accounts.end(id.value());
+    }
+    let mut res = see_other(LOGIN_PATH);
+    let _ = res.add_cookie(&expired(&req, SESSION_COOKIE, "/admin"));
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use actix_web::{
+        test::{call_service, init_service, TestRequest},
+        App,
+    };
+    use serde_json::Value;
+
+    fn accounts() -> web::Data<Accounts> {
+        let users = HashMap::from([("ed".to_string(), hash_password("hunter22").unwrap())]);
+        web::Data::new(Accounts::new(users).unwrap())
+    }
+
+    fn seed() -> Value {
+        json!({
+            "version": "rustdev-hub-seed-v7.0",
+            "tools": [{"slug": "anchor", "name": "Anchor"}],
+        })
+    }
+
+    fn location(res: &ServiceResponse) -> &str {
+        res.headers()
+            .get(header::LOCATION)
+            .unwrap()
+            .to_str()
+            .unwrap()
+    }
+
+    fn set_cookie(res: &ServiceResponse, name: &str) -> String {
+        res.headers()
+            .get_all(header::SET_COOKIE)
+            .filter_map(|value| value.to_str().ok())
+            .find(|value| value.starts_with(&format!("{name}=")))
+            .unwrap_or_else(|| panic!("no {name} cookie"))
+            .to_string()
+    }
+
+    #[actix_web::test]
+    async fn signing_in_needs_the_form_token_and_sets_strict_cookies() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = testing::site_state(&dir.path().join("seed.json"), &seed()).await;
+        let accounts = accounts();
+        let app = init_service(
+            App::new()
+                .app_data(state.clone())
+                .app_data(testing::editor(&state, &dir.path().join("audit.jsonl")))
+                .service(testing::admin_scope(accounts.clone())),
+        )
+        .await;
+        let post = |body: &str| {
+            TestRequest::post()
+                .uri("/admin/login")
+                .insert_header((header::HOST, "localhost"))
+                .insert_header((header::CONTENT_TYPE, "application/x-www-form-urlencoded"))
+                .set_payload(body.to_string())
+        };
+
+        let res = call_service(&app, post("user=ed&password=hunter22").to_request()).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let res = call_service(
+            &app,
+            post("user=ed&password=hunter22&csrf=guess")
+                .cookie(Cookie::new(LOGIN_COOKIE, "token"))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        for (proto, secure) in [("http", false), ("https", true)] {
+            let form = call_service(
+                &app,
+                TestRequest::get()
+                    .uri("/admin/login")
+                    .insert_header((header::HOST, "localhost"))
+                    .insert_header(("x-forwarded-proto", proto))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(form.status(), StatusCode::OK);
+            let login = set_cookie(&form, LOGIN_COOKIE);
+            let token = login[LOGIN_COOKIE.len() + 1..].split(';').next().unwrap();
+
+            let res = call_service(
+                &app,
+                post(&format!(
+                    "user=ed&password=hunter22&csrf={token}&next=%2Fadmin%2Ftools"
+                ))
+                .insert_header(("x-forwarded-proto", proto))
+                .cookie(Cookie::new(LOGIN_COOKIE, token.to_string()))
+                .to_request(),
+            )
+            .await;
+            assert_eq!(res.status(), StatusCode::SEE_OTHER);
+            assert_eq!(location(&res), "/admin/tools");
+            for cookie in [login, set_cookie(&res, SESSION_COOKIE)] {
+                assert!(cookie.contains("HttpOnly"), "{cookie}");
+                assert!(cookie.contains("SameSite=Strict"), "{cookie}");
+                assert_eq!(cookie.contains("Secure"), secure, "{cookie}");
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn pages_need_a_live_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = testing::site_state(&dir.path().join("seed.json"), &seed()).await;
+        let accounts = accounts();
+        let app = init_service(
+            App::new()
+                .app_data(state.clone())
+                .app_data(testing::editor(&state, &dir.path().join("audit.jsonl")))
+                .service(testing::admin_scope(accounts.clone())),
+        )
+        .await;
+        let get = |cookie: Option<Cookie<'static>>| {
+            let mut req = TestRequest::get()
+                .uri("/admin/tools?page=2")
+                .insert_header((header::HOST, "localhost"));
+            if let Some(cookie) = cookie {
+                req = req.cookie(cookie);
+            }
+            req.to_request()
+        };
+
+        let (live, _) = testing::sign_in(&accounts, "ed");
+        let res = call_service(&app, get(Some(live))).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-store"
+        );
+
+        let (expired, _) = testing::sign_in(&accounts, "ed");
+        accounts
+            .sessions()
+            .get_mut(expired.value())
+            .unwrap()
+            .expires = Instant::now();
+        for cookie in [
+            None,
+            Some(Cookie::new(SESSION_COOKIE, "unknown")),
+            Some(expired),
+        ] {
+            let res = call_service(&app, get(cookie)).await;
+            assert_eq!(res.status(), StatusCode::SEE_OTHER);
+            assert_eq!(
+                location(&res),
+                "/admin/login?next=%2Fadmin%2Ftools%3Fpage%3D2"
+            );
+        }
+    }
+
+    #[actix_web::test]
+    async fn signing_out_needs_the_session_csrf_token() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = testing::site_state(&dir.path().join("seed.json"), &seed()).await;
+        let accounts = accounts();
+        let app = init_service(
+            App::new()
+                .app_data(state.clone())
+                .app_data(testing::editor(&state, &dir.path().join("audit.jsonl")))
+                .service(testing::admin_scope(accounts.clone())),
+        )
+        .await;
+        let (cookie, csrf) = testing::sign_in(&accounts, "ed");
+        let logout = |body: String| {
+            TestRequest::post()
+                .uri("/admin/logout")
+                .insert_header((header::HOST, "localhost"))
+                .insert_header((header::CONTENT_TYPE, "application/x-www-form-urlencoded"))
+                .cookie(cookie.clone())
+                .set_payload(body)
+                .to_request()
+        };
+
+        for body in [String::new(), "csrf=guess".to_string()] {
+            let res = call_service(&app, logout(body)).await;
+            assert_eq!(res.status(), StatusCode::FORBIDDEN);
+            assert!(accounts.session(cookie.value()).is_some());
+        }
+        let res = call_service(&app, logout(format!("csrf={csrf}"))).await;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert!(accounts.session(cookie.value()).is_none());
+    }
+}
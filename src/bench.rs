@@ -0,0 +1,68 @@
+//! Entry points for `benches/pages.rs`, which compares rendering a page on
+//! every request, as the server did before `PageCache`, with serving the
+//! pre-rendered body.
+
+use actix_web::{http::header::HeaderMap, web::Bytes};
+use std::{io, path::PathBuf, sync::Arc};
+
+use crate::{
+    cache::PageSource, formats::Format, images::AllowedHosts, page_sources,
+    render_template_or_json, security::CspConfig, store, PromoContent, RustDevContent, Site,
+    SiteConfig, DEFAULT_ASSETS_DIR, DEFAULT_SITE_URL, PROMO_PATH,
+};
+
+/// The bundled content, built into a `Site` the way the server builds it.
+pub struct Pages {
+    site: Site,
+    sources: Vec<PageSource>,
+    site_url: String,
+}
+
+impl Pages {
+    /// Reads the JSON seed and promo file and pre-renders every page. Run
+    /// from the repository root, where the templates are.
+    pub fn load() -> io::Result<Self> {
+        let (seed, modified) = store::open("json")?.seed()?;
+        let mut rustdev = RustDevContent::from_seed(seed);
+        rustdev.last_modified = modified;
+        let promo: PromoContent = serde_json::from_slice(&std::fs::read(PROMO_PATH)?)?;
+        let config = SiteConfig {
+            seed: Arc::from(store::open("json")?),
+            site_url: DEFAULT_SITE_URL.to_string(),
+            assets_dir: PathBuf::from(DEFAULT_ASSETS_DIR),
+            image_hosts: AllowedHosts::new(crate::images::DEFAULT_ALLOWED_HOSTS),
+            csp: CspConfig::default(),
+        };
+        let today = time::OffsetDateTime::now_utc().date();
+        let site = Site::build(rustdev, promo.clone(), &config, today)?;
+        let sources = page_sources(&site.hb, &site.rustdev, &promo);
+        Ok(Self {
+            site,
+            sources,
+            site_url: config.site_url,
+        })
+    }
+
+    /// Builds the page's context and renders its HTML, as each request did
+    /// before pages were cached.
+    pub fn render(&self, path: &str) -> Option<String> {
+        let source = self.sources.iter().find(|source| source.path == path)?;
+        let context = source.context.clone();
+        render_template_or_json(
+            &self.site.hb,
+            source.template,
+            &context,
+            Format::Html,
+            &self.site_url,
+            path,
+        )
+        .ok()
+    }
+
+    /// The cached HTML body the page handler sends for `path`.
+    pub fn serve(&self, path: &str, headers: &HeaderMap) -> Option<Bytes> {
+        let representation = self.site.pages.get(path)?.get(Format::Html)?;
+        let (_, body) = representation.select(headers);
+        Some(body.bytes.clone())
+    }
+}
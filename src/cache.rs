@@ -0,0 +1,267 @@
+use actix_web::{http::header::HeaderMap, web::Bytes};
+use handlebars::Handlebars;
+use serde_json::Value;
+use std::{collections::HashMap, time::SystemTime};
+use time::Date;
+
+use crate::{
+    compression::{self, Encoding, Level},
+    conditional::Validators,
+    formats::{self, Format},
+    helpers, metrics, render_template_or_json,
+    security::PagePolicy,
+};
+
+/// A page the site serves: its request path, the template it renders with
+/// and the context fed to every representation.
+pub(crate) struct PageSource {
+    pub(crate) path: String,
+    pub(crate) template: &'static str,
+    pub(crate) context: Value,
+    pub(crate) last_modified: Option<SystemTime>,
+}
+
+impl PageSource {
+    pub(crate) fn new(
+        path: impl Into<String>,
+        template: &'static str,
+        context: Value,
+        last_modified: Option<SystemTime>,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            template,
+            context,
+            last_modified,
+        }
+    }
+}
+
+/// A response body with the validators that identify it.
+pub(crate) struct Body {
+    pub(crate) bytes: Bytes,
+    pub(crate) validators: Validators,
+}
+
+/// One rendered representation of a page, shared by every request for it,
+/// alongside its precompressed variants.
+pub(crate) struct Representation {
+    identity: Body,
+    encoded: Vec<(Encoding, Body)>,
+}
+
+impl Representation {
+    /// Compresses `body` in every supported coding up front, keeping only the
+    /// variants that come out smaller. `rendered_on` is the day the body
+    /// was rendered relative to, if it reads the date.
+    fn new(body: String, last_modified: Option<SystemTime>, rendered_on: Option<Date>) -> Self {
+        let validators = match rendered_on {
+            Some(today) => Validators::for_body_on(body.as_bytes(), last_modified, today),
+            None => Validators::for_body(body.as_bytes(), last_modified),
+        };
+        let mut encoded = Vec::new();
+        if body.len() >= compression::MIN_COMPRESS_SIZE {
+            for encoding in compression::ENCODINGS {
+                match encoding.compress(body.as_bytes(), Level::Best) {
+                    Ok(bytes) if bytes.len() < body.len() => encoded.push((
+                        encoding,
+                        Body {
+                            bytes: Bytes::from(bytes),
+                            validators: validators.encoded(encoding),
+                        },
+                    )),
+                    Ok(_) => {}
+                    Err(err) => {
+                        tracing::warn!(encoding = encoding.token(), %err, "compression failed")
+                    }
+                }
+            }
+        }
+        Self {
+            identity: Body {
+                bytes: Bytes::from(body),
+                validators,
+            },
+            encoded,
+        }
+    }
+
+    /// The variant to send for the request's `Accept-Encoding`, and its
+    /// coding (`None` for identity).
+    pub(crate) fn select(&self, headers: &HeaderMap) -> (Option<Encoding>, &Body) {
+        let available: Vec<Encoding> = self.encoded.iter().map(|(e, _)| *e).collect();
+        compression::negotiate(headers, &available)
+            .and_then(|chosen| self.encoded.iter().find(|(e, _)| *e == chosen))
+            .map(|(encoding, body)| (Some(*encoding), body))
+            .unwrap_or((None, &self.identity))
+    }
+}
+
+/// Every representation of a page that rendered successfully.
+pub(crate) struct CachedPage {
+    template: &'static str,
+    policy: PagePolicy,
+    representations: HashMap<Format, Representation>,
+}
+
+impl CachedPage {
+    /// The template the page was rendered from.
+    pub(crate) fn template(&self) -> &'static str {
+        self.template
+    }
+
+    /// What the Content Security Policy has to allow for the HTML
+    /// representation.
+    pub(crate) fn policy(&self) -> &PagePolicy {
+        &self.policy
+    }
+
+    /// `None` when the representation failed to render at build time.
+    pub(crate) fn get(&self, format: Format) -> Option<&Representation> {
+        self.representations.get(&format)
+    }
+}
+
+/// Pages pre-rendered once per content version and day, keyed by request
+/// path.
+#[derive(Default)]
+pub(crate) struct PageCache {
+    pages: HashMap<String, CachedPage>,
+}
+
+impl PageCache {
+    /// Renders every page in every page format and precompresses the
+    /// results. `today` is the day `hb`'s helpers count relative times
+    /// from. Render failures are logged
+    /// and leave that representation out, so it is answered with a 500.
+    pub(crate) fn build(
+        hb: &Handlebars<'_>,
+        sources: Vec<PageSource>,
+        site_url: &str,
+        today: Date,
+    ) -> Self {
+        let mut pages = HashMap::with_capacity(sources.len());
+        for source in sources {
+            let mut representations = HashMap::with_capacity(formats::PAGE_FORMATS.len());
+            let mut policy = PagePolicy::default();
+            for format in formats::PAGE_FORMATS {
+                let (rendered, reads_today) = helpers::reads_today(|| {
+                    render_template_or_json(
+                        hb,
+                        source.template,
+                        &source.context,
+                        format,
+                        site_url,
+                        &source.path,
+                    )
+                });
+                match rendered {
+                    Ok(body) => {
+                        if format == Format::Html {
+                            policy = PagePolicy::for_html(&body);
+                        }
+                        let rendered_on = reads_today.then_some(today);
+                        representations.insert(
+                            format,
+                            Representation::new(body, source.last_modified, rendered_on),
+                        );
+                    }
+                    Err(err) => {
+                        tracing::error!(
+                            template = source.template,
+                            path = %source.path,
+                            format = format.name(),
+                            %err,
+                            "template render failed"
+                        );
+                        metrics::render_error(source.template);
+                    }
+                }
+            }
+            pages.insert(
+                source.path,
+                CachedPage {
+                    template: source.template,
+                    policy,
+                    representations,
+                },
+            );
+        }
+        Self { pages }
+    }
+
+    pub(crate) fn get(&self, path: &str) -> Option<&CachedPage> {
+        self.pages.get(path)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.pages.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{assets::AssetManifest, images::AllowedHosts};
+    use actix_web::{http::header::HeaderValue, HttpResponse};
+    use serde_json::json;
+    use time::{Date, Month};
+
+    fn build_on(today: Date) -> PageCache {
+        let mut hb = Handlebars::new();
+        hb.register_template_string("dated", "Verified {{relative_time verified}}")
+            .unwrap();
+        hb.register_template_string("plain", "{{name}}").unwrap();
+        helpers::register(
+            &mut hb,
+            &[],
+            "https://rust.dev",
+            &AssetManifest::default(),
+            &AllowedHosts::new(crate::images::DEFAULT_ALLOWED_HOSTS),
+            today,
+        );
+        let context = json!({"name": "Jobs", "verified": "2026-06-14"});
+        let modified = Some(SystemTime::UNIX_EPOCH);
+        let sources = vec![
+            PageSource::new("/dated", "dated", context.clone(), modified),
+            PageSource::new("/plain", "plain", context, modified),
+        ];
+        PageCache::build(&hb, sources, "https://rust.dev", today)
+    }
+
+    /// The HTML body and its `ETag` and `Last-Modified` headers.
+    fn html(cache: &PageCache, path: &str) -> (Bytes, HeaderValue, HeaderValue) {
+        let (_, body) = cache
+            .get(path)
+            .and_then(|page| page.get(Format::Html))
+            .unwrap()
+            .select(&HeaderMap::new());
+        let mut response = HttpResponse::Ok();
+        body.validators.apply(&mut response);
+        let response = response.finish();
+        let header = |name| response.headers().get(name).unwrap().clone();
+        (
+            body.bytes.clone(),
+            header(actix_web::http::header::ETAG),
+            header(actix_web::http::header::LAST_MODIFIED),
+        )
+    }
+
+    #[test]
+    fn pages_showing_relative_dates_change_with_the_day() {
+        let day = |d| Date::from_calendar_date(2026, Month::June, d).unwrap();
+        let (monday, tuesday) = (build_on(day(15)), build_on(day(16)));
+
+        let (body, etag, modified) = html(&monday, "/dated");
+        let (next_body, next_etag, next_modified) = html(&tuesday, "/dated");
+        assert_eq!(body, "Verified yesterday");
+        assert_eq!(next_body, "Verified 2 days ago");
+        assert_ne!(etag, next_etag);
+        assert_eq!(modified, "Mon, 15 Jun 2026 00:00:00 GMT");
+        assert_eq!(next_modified, "Tue, 16 Jun 2026 00:00:00 GMT");
+
+        let (_, etag, modified) = html(&monday, "/plain");
+        let (_, next_etag, next_modified) = html(&tuesday, "/plain");
+        assert_eq!((etag, modified), (next_etag, next_modified));
+    }
+}
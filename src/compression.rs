@@ -0,0 +1,414 @@
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderMap, HeaderValue},
+        StatusCode,
+    },
+    web::Bytes,
+    Error,
+};
+use flate2::{write::GzEncoder, Compression};
+use std::{future::Future, io::Write};
+
+/// Bodies smaller than this are not worth the encoding overhead.
+pub(crate) const MIN_COMPRESS_SIZE: usize = 1024;
+
+/// Content codings the site produces, in server preference order.
+pub(crate) const ENCODINGS: [Encoding; 2] = [Encoding::Brotli, Encoding::Gzip];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+/// How hard to work: `Best` for bodies compressed once and reused, `Fast`
+/// for bodies compressed per response.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Level {
+    Best,
+    Fast,
+}
+
+impl Encoding {
+    /// Token used in `Accept-Encoding` and `Content-Encoding`.
+    pub(crate) fn token(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    pub(crate) fn compress(self, input: &[u8], level: Level) -> std::io::Result<Vec<u8>> {
+        match self {
+            Encoding::Brotli => {
+                let quality = match level {
+                    Level::Best => 9,
+                    Level::Fast => 4,
+                };
+                let mut writer = brotli::CompressorWriter::new(
+                    Vec::with_capacity(input.len() / 4),
+                    4096,
+                    quality,
+                    22,
+                );
+                writer.write_all(input)?;
+                Ok(writer.into_inner())
+            }
+            Encoding::Gzip => {
+                let compression = match level {
+                    Level::Best => Compression::best(),
+                    Level::Fast => Compression::fast(),
+                };
+                let mut encoder = GzEncoder::new(Vec::with_capacity(input.len() / 4), compression);
+                encoder.write_all(input)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Picks the coding to send out of `available` from `Accept-Encoding`.
+/// Missing or unparseable headers, and codings with `q=0`, mean identity.
+pub(crate) fn negotiate(headers: &HeaderMap, available: &[Encoding]) -> Option<Encoding> {
+    let accept = headers
+        .get(header::ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())?;
+
+    let mut wildcard = None;
+    let mut explicit: Vec<(&str, f32)> = Vec::new();
+    for item in accept.split(',') {
+        let mut parts = item.split(';');
+        let token = parts.next().unwrap_or("").trim();
+        if token.is_empty() {
+            continue;
+        }
+        let q = parts
+            .filter_map(|param| {
+                let (name, value) = param.split_once('=')?;
+                name.trim().eq_ignore_ascii_case("q").then_some(value)
+            })
+            .find_map(|q| q.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        if token == "*" {
+            wildcard = Some(q);
+        } else {
+            explicit.push((token, q));
+        }
+    }
+
+    let mut best: Option<(Encoding, f32)> = None;
+    for encoding in available {
+        let q = explicit
+            .iter()
+            .find(|(token, _)| token.eq_ignore_ascii_case(encoding.token()))
+            .map(|(_, q)| *q)
+            .or(wildcard)
+            .unwrap_or(0.0);
+        if q > 0.0 && best.map(|(_, best_q)| q > best_q).unwrap_or(true) {
+            best = Some((*encoding, q));
+        }
+    }
+    best.map(|(encoding, _)| encoding)
+}
+
+/// Media types that are worth compressing. Images, video, audio, fonts and
+/// archives are already compressed and are sent as-is.
+pub(crate) fn is_compressible(content_type: &str) -> bool {
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    media_type.starts_with("text/")
+        || media_type == "image/svg+xml"
+        || media_type == "application/json"
+        || media_type == "application/javascript"
+        || media_type == "application/xml"
+        || media_type == "application/wasm"
+        || media_type.ends_with("+json")
+        || media_type.ends_with("+xml")
+}
+
+/// The ETag of an encoded variant: the identity tag with the coding appended,
+/// so variants never share a strong validator.
+pub(crate) fn encoded_etag(etag: &str, encoding: Encoding) -> String {
+    match etag.strip_suffix('"') {
+        Some(open) => format!("{open}-{}\"", encoding.token()),
+        None => etag.to_string(),
+    }
+}
+
+/// Reverses `encoded_etag`, so a validator for any variant can be compared
+/// against the identity tag.
+pub(crate) fn identity_etag(etag: &str) -> String {
+    for encoding in ENCODINGS {
+        if let Some(open) = etag.strip_suffix(&format!("-{}\"", encoding.token())) {
+            return format!("{open}\"");
+        }
+    }
+    etag.to_string()
+}
+
+/// Adds `Accept-Encoding` to `Vary` unless it is already listed.
+pub(crate) fn vary_on_encoding(headers: &mut HeaderMap) {
+    let listed = headers.get_all(header::VARY).any(|value| {
+        value
+            .to_str()
+            .map(|v| {
+                v.split(',')
+                    .any(|name| name.trim().eq_ignore_ascii_case("accept-encoding"))
+            })
+            .unwrap_or(false)
+    });
+    if !listed {
+        headers.append(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+    }
+}
+
+/// Middleware for responses built per request (API JSON, error pages).
+/// Pre-rendered pages arrive with `Content-Encoding` already set and pass
+/// through untouched, as do small bodies and incompressible media types.
+pub(crate) fn compress_responses<S>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error>,
+{
+    let encoding = negotiate(req.headers(), &ENCODINGS);
+    let fut = srv.call(req);
+    async move {
+        let res = fut.await?;
+        Ok(res.map_body(|head, body| {
+            let eligible = !matches!(
+                head.status,
+                StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED
+            ) && !head.headers().contains_key(header::CONTENT_ENCODING)
+                && head
+                    .headers()
+                    .get(header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .map(is_compressible)
+                    .unwrap_or(false);
+            if !eligible {
+                return body;
+            }
+            vary_on_encoding(head.headers_mut());
+            let Some(encoding) = encoding else {
+                return body;
+            };
+
+            let bytes = match body.try_into_bytes() {
+                Ok(bytes) => bytes,
+                Err(body) => return body,
+            };
+            if bytes.len() < MIN_COMPRESS_SIZE {
+                return BoxBody::new(bytes);
+            }
+            let compressed = match encoding.compress(&bytes, Level::Fast) {
+                Ok(compressed) if compressed.len() < bytes.len() => compressed,
+                Ok(_) => return BoxBody::new(bytes),
+                Err(err) => {
+                    tracing::warn!(encoding = encoding.token(), %err, "compression failed");
+                    return BoxBody::new(bytes);
+                }
+            };
+
+            let headers = head.headers_mut();
+            headers.insert(
+                header::CONTENT_ENCODING,
+                HeaderValue::from_static(encoding.token()),
+            );
+            if let Some(etag) = headers
+                .get(header::ETAG)
+                .and_then(|v| v.to_str().ok())
+                .map(|etag| encoded_etag(etag, encoding))
+            {
+                if let Ok(value) = HeaderValue::from_str(&etag) {
+                    headers.insert(header::ETAG, value);
+                }
+            }
+            vary_on_encoding(headers);
+            BoxBody::new(Bytes::from(compressed))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use actix_web::{
+        test::{call_service, init_service, read_body, TestRequest},
+        web, App,
+    };
+    use serde_json::json;
+
+    fn accepting(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT_ENCODING,
+            HeaderValue::from_str(value).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn negotiation_follows_q_values_then_server_preference() {
+        let pick = |value: &str| negotiate(&accepting(value), &ENCODINGS);
+        assert_eq!(negotiate(&HeaderMap::new(), &ENCODINGS), None);
+        assert_eq!(pick(""), None);
+        assert_eq!(pick("gzip, br"), Some(Encoding::Brotli));
+        assert_eq!(pick("gzip"), Some(Encoding::Gzip));
+        assert_eq!(pick("GZIP"), Some(Encoding::Gzip));
+        assert_eq!(pick("br;q=0.5, gzip;q=0.8"), Some(Encoding::Gzip));
+        assert_eq!(pick("br;q=0, gzip"), Some(Encoding::Gzip));
+        assert_eq!(pick("br;Q=0, gzip"), Some(Encoding::Gzip));
+        assert_eq!(pick("br ; q=0 , gzip;q=0"), None);
+        assert_eq!(pick("deflate"), None);
+        // `*` stands for every coding not named.
+        assert_eq!(pick("*"), Some(Encoding::Brotli));
+        assert_eq!(pick("br;q=0, *"), Some(Encoding::Gzip));
+        assert_eq!(pick("*;q=0"), None);
+        assert_eq!(pick("gzip;q=0.2, *;q=0.1"), Some(Encoding::Gzip));
+        // Refusing identity does not stop a coding being picked, and
+        // refusing everything still gets identity.
+        assert_eq!(pick("identity;q=0, gzip"), Some(Encoding::Gzip));
+        assert_eq!(pick("identity;q=0, *;q=0"), None);
+        assert_eq!(
+            negotiate(&accepting("br, gzip"), &[Encoding::Gzip]),
+            Some(Encoding::Gzip)
+        );
+    }
+
+    #[test]
+    fn variant_etags_round_trip_to_the_identity_tag() {
+        for encoding in ENCODINGS {
+            for etag in ["\"abc123\"", "W/\"abc123\""] {
+                let encoded = encoded_etag(etag, encoding);
+                assert_ne!(encoded, etag);
+                assert!(encoded.ends_with(&format!("-{}\"", encoding.token())));
+                assert_eq!(identity_etag(&encoded), etag);
+            }
+        }
+        assert_eq!(encoded_etag("\"abc\"", Encoding::Brotli), "\"abc-br\"");
+        assert_eq!(identity_etag("\"abc\""), "\"abc\"");
+        assert_eq!(identity_etag("\"abc-deflate\""), "\"abc-deflate\"");
+        // Not a quoted tag: left alone.
+        assert_eq!(encoded_etag("abc", Encoding::Gzip), "abc");
+    }
+
+    #[test]
+    fn only_text_like_media_types_are_compressed() {
+        for compressible in [
+            "text/html; charset=utf-8",
+            "TEXT/CSS",
+            "application/json",
+            "application/problem+json",
+            "application/atom+xml; charset=utf-8",
+            "application/javascript",
+            "application/wasm",
+            "image/svg+xml",
+        ] {
+            assert!(is_compressible(compressible), "{compressible}");
+        }
+        for incompressible in [
+            "image/png",
+            "image/jpeg",
+            "image/webp",
+            "video/mp4",
+            "audio/ogg",
+            "font/woff2",
+            "application/zip",
+            "application/gzip",
+            "application/octet-stream",
+            "",
+        ] {
+            assert!(!is_compressible(incompressible), "{incompressible}");
+        }
+    }
+
+    #[actix_web::test]
+    async fn variant_etags_get_304s() {
+        let dir = tempfile::tempdir().unwrap();
+        let tools: Vec<_> = (0..40)
+            .map(|n| json!({"slug": format!("tool-{n}"), "name": format!("Tool number {n}")}))
+            .collect();
+        let state = testing::site_state(
+            &dir.path().join("seed.json"),
+            &json!({"version": "rustdev-hub-seed-v7.0", "tools": tools}),
+        )
+        .await;
+        let app = init_service(
+            App::new()
+                .app_data(state)
+                .wrap_fn(compress_responses)
+                .service(web::resource("/tools").route(web::get().to(crate::page)))
+                .service(
+                    web::resource("/api/v1/{collection}").route(web::get().to(crate::api::list)),
+                ),
+        )
+        .await;
+        let get = |uri: &str, encoding: &str| {
+            TestRequest::get()
+                .uri(uri)
+                .insert_header((header::HOST, "localhost"))
+                .insert_header((header::ACCEPT_ENCODING, encoding))
+        };
+
+        for uri in ["/tools", "/api/v1/tools"] {
+            let mut tags = Vec::new();
+            for encoding in ENCODINGS {
+                let res = call_service(&app, get(uri, encoding.token()).to_request()).await;
+                assert_eq!(res.status(), StatusCode::OK);
+                assert_eq!(
+                    res.headers().get(header::CONTENT_ENCODING).unwrap(),
+                    encoding.token(),
+                    "{uri}"
+                );
+                let etag = res.headers().get(header::ETAG).unwrap().to_str().unwrap();
+                assert!(
+                    etag.ends_with(&format!("-{}\"", encoding.token())),
+                    "{etag}"
+                );
+                tags.push(etag.to_string());
+                let vary = res.headers().get(header::VARY).unwrap().to_str().unwrap();
+                assert!(
+                    vary.to_ascii_lowercase().contains("accept-encoding"),
+                    "{vary}"
+                );
+            }
+
+            // A cache may revalidate with any variant's tag, whichever
+            // coding it now asks for.
+            for tag in &tags {
+                for encoding in ["br", "gzip", "identity"] {
+                    let res = call_service(
+                        &app,
+                        get(uri, encoding)
+                            .insert_header((header::IF_NONE_MATCH, tag.as_str()))
+                            .to_request(),
+                    )
+                    .await;
+                    assert_eq!(
+                        res.status(),
+                        StatusCode::NOT_MODIFIED,
+                        "{uri} {tag} {encoding}"
+                    );
+                    assert!(read_body(res).await.is_empty());
+                }
+            }
+            let res = call_service(
+                &app,
+                get(uri, "gzip")
+                    .insert_header((header::IF_NONE_MATCH, "\"other-gzip\""))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+    }
+}
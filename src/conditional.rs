@@ -0,0 +1,119 @@
+use actix_web::{
+    http::header::{self, HttpDate},
+    HttpRequest, HttpResponse, HttpResponseBuilder,
+};
+use sha2::{Digest, Sha256};
+
+use crate::compression::{self, Encoding};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use time::Date;
+
+/// Cache validators for a response body: a strong ETag derived from the
+/// bytes and an optional `Last-Modified` time.
+pub(crate) struct Validators {
+    etag: String,
+    last_modified: Option<SystemTime>,
+}
+
+/// Hex SHA-256 of `bytes`, truncated to `len` characters.
+pub(crate) fn content_hash(bytes: &[u8], len: usize) -> String {
+    let digest = Sha256::digest(bytes);
+    let mut hex = String::with_capacity(64);
+    for byte in digest {
+        hex.push_str(&format!("{byte:02x}"));
+    }
+    hex.truncate(len);
+    hex
+}
+
+/// Drops sub-second precision, which HTTP dates cannot carry.
+fn whole_seconds(time: SystemTime) -> SystemTime {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+impl Validators {
+    pub(crate) fn for_body(body: &[u8], last_modified: Option<SystemTime>) -> Self {
+        Self {
+            etag: format!("\"{}\"", content_hash(body, 32)),
+            last_modified: last_modified.map(whole_seconds),
+        }
+    }
+
+    /// Validators for a body that is fully determined by `key`, so they are
+    /// known before it is built.
+    pub(crate) fn for_key(key: &str, last_modified: Option<SystemTime>) -> Self {
+        Self::for_body(key.as_bytes(), last_modified)
+    }
+
+    /// Validators for a body that was rendered relative to `today`. The day
+    /// is part of the ETag and `Last-Modified` is no earlier than its start,
+    /// so a copy from the day before never revalidates as current.
+    pub(crate) fn for_body_on(body: &[u8], last_modified: Option<SystemTime>, today: Date) -> Self {
+        let mut dated = body.to_vec();
+        dated.extend_from_slice(today.to_string().as_bytes());
+        let start_of_day = SystemTime::from(today.midnight().assume_utc());
+        Self::for_body(
+            &dated,
+            Some(last_modified.map_or(start_of_day, |modified| modified.max(start_of_day))),
+        )
+    }
+
+    /// Validators for the `encoding` variant of the same body.
+    pub(crate) fn encoded(&self, encoding: Encoding) -> Self {
+        Self {
+            etag: compression::encoded_etag(&self.etag, encoding),
+            last_modified: self.last_modified,
+        }
+    }
+
+    /// Whether the client's cached copy is current. `If-None-Match` takes
+    /// precedence; `If-Modified-Since` is only consulted without it. Tags of
+    /// encoded variants match their identity body.
+    pub(crate) fn is_fresh(&self, req: &HttpRequest) -> bool {
+        let headers = req.headers();
+        if let Some(if_none_match) = headers
+            .get(header::IF_NONE_MATCH)
+            .and_then(|v| v.to_str().ok())
+        {
+            let current = compression::identity_etag(&self.etag);
+            return if_none_match.split(',').map(str::trim).any(|tag| {
+                tag == "*"
+                    || compression::identity_etag(tag.strip_prefix("W/").unwrap_or(tag)) == current
+            });
+        }
+
+        match (
+            self.last_modified,
+            headers
+                .get(header::IF_MODIFIED_SINCE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<HttpDate>().ok()),
+        ) {
+            (Some(modified), Some(since)) => modified <= SystemTime::from(since),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn apply(&self, builder: &mut HttpResponseBuilder) {
+        builder.append_header((header::ETAG, self.etag.as_str()));
+        if let Some(modified) = self.last_modified {
+            builder.append_header((header::LAST_MODIFIED, HttpDate::from(modified)));
+        }
+    }
+
+    /// A bodiless 304 carrying the same validators and caching headers the
+    /// full response would have.
+    pub(crate) fn not_modified(&self, cache_control: &str, vary: Option<&str>) -> HttpResponse {
+        let mut builder = HttpResponse::NotModified();
+        self.apply(&mut builder);
+        builder.append_header((header::CACHE_CONTROL, cache_control));
+        if let Some(vary) = vary {
+            builder.append_header((header::VARY, vary));
+        }
+        builder.finish()
+    }
+}
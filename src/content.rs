@@ -0,0 +1,163 @@
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::io;
+
+use crate::{store::invalid_data, Creator, Ecosystem, Event, Job, Label, LearningPath, Post, Tool};
+
+/// The seed's entity collections, named as in `/api/v1` and `/admin`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Collection {
+    Ecosystems,
+    Tools,
+    Events,
+    LearningPaths,
+    Creators,
+    Posts,
+    Jobs,
+    Labels,
+}
+
+pub(crate) const COLLECTIONS: [Collection; 8] = [
+    Collection::Ecosystems,
+    Collection::Tools,
+    Collection::Events,
+    Collection::LearningPaths,
+    Collection::Creators,
+    Collection::Posts,
+    Collection::Jobs,
+    Collection::Labels,
+];
+
+impl Collection {
+    pub(crate) fn from_path(path: &str) -> Option<Self> {
+        COLLECTIONS.into_iter().find(|c| c.path() == path)
+    }
+
+    pub(crate) fn path(self) -> &'static str {
+        match self {
+            Collection::Ecosystems => "ecosystems",
+            Collection::Tools => "tools",
+            Collection::Events => "events",
+            Collection::LearningPaths => "learning-paths",
+            Collection::Creators => "creators",
+            Collection::Posts => "posts",
+            Collection::Jobs => "jobs",
+            Collection::Labels => "labels",
+        }
+    }
+
+    /// The collection's name as editors see it.
+    pub(crate) fn title(self) -> &'static str {
+        match self {
+            Collection::Ecosystems => "Ecosystems",
+            Collection::Tools => "Tools",
+            Collection::Events => "Events",
+            Collection::LearningPaths => "Learning paths",
+            Collection::Creators => "Creators",
+            Collection::Posts => "News posts",
+            Collection::Jobs => "Jobs",
+            Collection::Labels => "Labels",
+        }
+    }
+
+    /// Labels are the only entities that render no page.
+    pub(crate) fn has_pages(self) -> bool {
+        self != Collection::Labels
+    }
+
+    /// The public page an entity shows on. Jobs only appear in the listing.
+    pub(crate) fn page_path(self, slug: &str) -> Option<String> {
+        let section = match self {
+            Collection::Ecosystems => "ecosystems",
+            Collection::Tools => "tools",
+            Collection::Events => "events",
+            Collection::LearningPaths => "learn",
+            Collection::Creators => "creators",
+            Collection::Posts => "news",
+            Collection::Jobs => return Some("/jobs".to_string()),
+            Collection::Labels => return None,
+        };
+        Some(format!("/{section}/{slug}"))
+    }
+
+    /// Where the collection lives in the seed. The first one present in the
+    /// file is edited, so the legacy names the seed still uses are kept.
+    pub(crate) fn pointers(self) -> &'static [&'static str] {
+        match self {
+            Collection::Ecosystems => &["/ecosystems", "/protocols"],
+            Collection::Tools => &["/tools"],
+            Collection::Events => &["/events"],
+            Collection::LearningPaths => &["/learning_paths"],
+            Collection::Creators => &["/creators"],
+            Collection::Posts => &["/posts", "/news"],
+            Collection::Jobs => &["/jobs"],
+            Collection::Labels => &["/taxonomy/labels"],
+        }
+    }
+
+    /// Checks that `entity` reads as the struct `RustDevSeed` holds for this
+    /// collection.
+    pub(crate) fn check(self, entity: &Value) -> Result<(), String> {
+        fn parse<T: DeserializeOwned>(entity: &Value) -> Result<(), String> {
+            T::deserialize(entity)
+                .map(drop)
+                .map_err(|err| err.to_string())
+        }
+
+        match self {
+            Collection::Ecosystems => parse::<Ecosystem>(entity),
+            Collection::Tools => parse::<Tool>(entity),
+            Collection::Events => parse::<Event>(entity),
+            Collection::LearningPaths => parse::<LearningPath>(entity),
+            Collection::Creators => parse::<Creator>(entity),
+            Collection::Posts => parse::<Post>(entity),
+            Collection::Jobs => parse::<Job>(entity),
+            Collection::Labels => parse::<Label>(entity),
+        }
+    }
+
+    pub(crate) fn items(self, seed: &Value) -> &[Value] {
+        self.pointers()
+            .iter()
+            .find_map(|pointer| seed.pointer(pointer))
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Whether `seed` has the collection at all, even empty.
+    pub(crate) fn is_in(self, seed: &Value) -> bool {
+        self.pointers()
+            .iter()
+            .any(|pointer| seed.pointer(pointer).is_some())
+    }
+
+    /// The collection's array in `seed`, created if the seed has none yet.
+    pub(crate) fn items_mut(self, seed: &mut Value) -> io::Result<&mut Vec<Value>> {
+        let pointers = self.pointers();
+        let pointer = pointers
+            .iter()
+            .find(|pointer| seed.pointer(pointer).is_some())
+            .unwrap_or(&pointers[0]);
+        let mut node = seed;
+        let mut keys = pointer.split('/').skip(1).peekable();
+        while let Some(key) = keys.next() {
+            let empty = if keys.peek().is_some() {
+                json!({})
+            } else {
+                json!([])
+            };
+            node = node
+                .as_object_mut()
+                .ok_or_else(|| invalid_data(format!("`{pointer}` is not in an object")))?
+                .entry(key)
+                .or_insert(empty);
+        }
+        node.as_array_mut()
+            .ok_or_else(|| invalid_data(format!("`{pointer}` is not an array")))
+    }
+}
+
+pub(crate) fn slug_of(entity: &Value) -> Option<&str> {
+    entity.get("slug").and_then(Value::as_str)
+}
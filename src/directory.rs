@@ -0,0 +1,626 @@
+use serde_json::{json, Map, Value};
+use std::{
+    collections::{BTreeMap, HashSet},
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    content::{slug_of, Collection},
+    store::{
+        self, invalid_data, mtime_revision, replace_file, to_seed_json, ContentStore, Mutation,
+        Snapshot, StoreVersion, DEFAULT_CONTENT_DIR,
+    },
+    DATA_PATH,
+};
+
+/// The file the rest of the content hangs off: the seed's top level, with
+/// everything kept elsewhere replaced by a marker.
+const INDEX_FILE: &str = "index.json";
+/// Marks a value kept in a file of its own: `{"$include": "site.json"}`.
+const INCLUDE: &str = "$include";
+/// Marks an array kept as one file per entity, named by slug, in a
+/// directory: `{"$collection": "tools", "order": ["anchor", …]}`. Entities
+/// go in `order`; files it does not list follow, sorted by slug.
+const COLLECTION: &str = "$collection";
+const ORDER: &str = "order";
+/// Opens and closes TOML front matter in Markdown files.
+const FRONT_MATTER: &str = "+++";
+/// Includes nest at most this deep, which also stops include cycles.
+const MAX_INCLUDE_DEPTH: usize = 4;
+
+/// How an entity or included file is written, chosen by its extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FileFormat {
+    Json,
+    Toml,
+    /// TOML front matter for the fields, with the body as the collection's
+    /// body field.
+    Markdown,
+}
+
+impl FileFormat {
+    const ALL: [FileFormat; 3] = [FileFormat::Json, FileFormat::Toml, FileFormat::Markdown];
+
+    fn extension(self) -> &'static str {
+        match self {
+            FileFormat::Json => "json",
+            FileFormat::Toml => "toml",
+            FileFormat::Markdown => "md",
+        }
+    }
+
+    fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        Self::ALL
+            .into_iter()
+            .find(|format| format.extension() == extension)
+    }
+}
+
+/// Which field a Markdown body holds. Entities keep their prose in
+/// `about`; posts may add a longer `body_md`, which then takes the body.
+#[derive(Clone, Copy, Debug)]
+struct BodyField {
+    body_md: bool,
+}
+
+impl BodyField {
+    /// The fields entities of the collection in `dir` can have.
+    fn of(dir: &str) -> Self {
+        Self {
+            body_md: Collection::Posts
+                .pointers()
+                .contains(&format!("/{dir}").as_str()),
+        }
+    }
+
+    /// The field written as the body of `fields`.
+    fn written(self, fields: &Map<String, Value>) -> &'static str {
+        if self.body_md && fields.contains_key("body_md") {
+            "body_md"
+        } else {
+            "about"
+        }
+    }
+
+    /// The field a body read under `matter` fills: `body_md` when the front
+    /// matter has `about` already, else `about`.
+    fn read(self, matter: &Map<String, Value>) -> &'static str {
+        if self.body_md && matter.contains_key("about") {
+            "body_md"
+        } else {
+            "about"
+        }
+    }
+}
+
+/// Whether `name` can be used as a file name as it is.
+fn file_safe(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\'])
+}
+
+fn toml_to_json(value: toml::Value) -> io::Result<Value> {
+    Ok(match value {
+        toml::Value::String(text) => Value::String(text),
+        toml::Value::Integer(number) => number.into(),
+        toml::Value::Float(number) => serde_json::Number::from_f64(number)
+            .map(Value::Number)
+            .ok_or_else(|| invalid_data(format!("{number} is not a JSON number")))?,
+        toml::Value::Boolean(flag) => flag.into(),
+        toml::Value::Datetime(datetime) => Value::String(datetime.to_string()),
+        toml::Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(toml_to_json)
+                .collect::<io::Result<_>>()?,
+        ),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| Ok((key, toml_to_json(value)?)))
+                .collect::<io::Result<_>>()?,
+        ),
+    })
+}
+
+fn parse_toml(text: &str) -> io::Result<Value> {
+    toml_to_json(toml::Value::Table(
+        toml::from_str(text).map_err(invalid_data)?,
+    ))
+}
+
+/// Splits a Markdown file into its front matter and body.
+fn front_matter(text: &str) -> Option<(&str, &str)> {
+    let mut lines = text.split_inclusive('\n');
+    let opening = lines.next()?;
+    if opening.trim_end() != FRONT_MATTER {
+        return None;
+    }
+    let mut offset = opening.len();
+    for line in lines {
+        if line.trim_end() == FRONT_MATTER {
+            return Some((&text[opening.len()..offset], &text[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// The fields in `text`. A Markdown body goes in its `BodyField`, without
+/// the blank line after the front matter and the file's final newline.
+fn parse(format: FileFormat, text: &str, field: BodyField) -> io::Result<Map<String, Value>> {
+    let value = match format {
+        FileFormat::Json => serde_json::from_str(text).map_err(invalid_data)?,
+        FileFormat::Toml => parse_toml(text)?,
+        FileFormat::Markdown => {
+            let (matter, body) = front_matter(text).ok_or_else(|| {
+                invalid_data(format!("no front matter between `{FRONT_MATTER}` lines"))
+            })?;
+            let mut value = parse_toml(matter)?;
+            let body = body.strip_prefix('\n').unwrap_or(body);
+            let body = body.strip_suffix('\n').unwrap_or(body);
+            if let Some(fields) = value.as_object_mut() {
+                let body_field = field.read(fields);
+                if fields.contains_key(body_field) {
+                    return Err(invalid_data(format!(
+                        "`{body_field}` is set in the front matter and by the body"
+                    )));
+                }
+                fields.insert(body_field.to_string(), body.into());
+            }
+            value
+        }
+    };
+    match value {
+        Value::Object(fields) => Ok(fields),
+        _ => Err(invalid_data("not an object")),
+    }
+}
+
+/// `fields` written in `format`, or `None` when the format cannot hold
+/// them exactly, such as TOML with a `null` or Markdown without a body.
+fn encode(format: FileFormat, fields: &Map<String, Value>, body: BodyField) -> Option<String> {
+    let text = match format {
+        FileFormat::Json => {
+            String::from_utf8(to_seed_json(&Value::Object(fields.clone())).ok()?).ok()?
+        }
+        FileFormat::Toml => toml::to_string_pretty(fields).ok()?,
+        FileFormat::Markdown => {
+            let mut matter = fields.clone();
+            let text = matter.shift_remove(body.written(fields))?;
+            let matter = toml::to_string_pretty(&matter).ok()?;
+            format!(
+                "{FRONT_MATTER}\n{matter}{FRONT_MATTER}\n\n{}\n",
+                text.as_str()?
+            )
+        }
+    };
+    (parse(format, &text, body).ok()? == *fields).then_some(text)
+}
+
+/// The file for `entity`, in `preferred` format if it holds the entity
+/// exactly, else Markdown, else JSON. The slug is left to the file name.
+fn encode_entity(
+    entity: &Value,
+    body: BodyField,
+    preferred: Option<FileFormat>,
+) -> io::Result<(FileFormat, String)> {
+    let mut fields = entity
+        .as_object()
+        .cloned()
+        .ok_or_else(|| invalid_data("an entity must be an object"))?;
+    fields.shift_remove("slug");
+    preferred
+        .into_iter()
+        .chain([FileFormat::Markdown, FileFormat::Json])
+        .find_map(|format| Some((format, encode(format, &fields, body)?)))
+        .ok_or_else(|| invalid_data("the entity cannot be written as JSON"))
+}
+
+/// Whether `value` can be split into one file per entity: a non-empty
+/// array of objects with distinct slugs usable as file names.
+fn splittable(value: &Value) -> bool {
+    let Some(items) = value.as_array().filter(|items| !items.is_empty()) else {
+        return false;
+    };
+    let mut seen = HashSet::new();
+    items
+        .iter()
+        .all(|item| slug_of(item).is_some_and(|slug| file_safe(slug) && seen.insert(slug)))
+}
+
+/// Latest modification time of `path` and everything under it, so that
+/// editing, adding or removing any file moves it forward.
+fn latest_change(path: &Path) -> io::Result<Option<SystemTime>> {
+    let meta = fs::metadata(path)?;
+    let mut latest = meta.modified().ok();
+    if meta.is_dir() {
+        for entry in fs::read_dir(path)? {
+            latest = latest.max(latest_change(&entry?.path())?);
+        }
+    }
+    Ok(latest)
+}
+
+/// The seed kept as a directory of small files, so concurrent edits to
+/// different entities never touch the same file. `index.json` holds the
+/// top level; large sections are included from files of their own and
+/// entity arrays are directories with a file per entity:
+///
+/// ```text
+/// content/index.json          {"site": {"$include": "site.json"}, "tools": {"$collection": "tools", …}, …}
+/// content/site.json
+/// content/tools/anchor.md     TOML front matter, body as `about`
+/// content/news/…              body as `body_md` if the post has one, else `about`
+/// content/taxonomy/labels/…
+/// ```
+///
+/// Entity files are `.json`, `.toml` or `.md` and named by slug.
+pub(crate) struct DirectoryStore {
+    root: PathBuf,
+}
+
+impl DirectoryStore {
+    pub(crate) fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// A JSON or TOML file under the root, as found, markers included.
+    fn read_file(&self, name: &str) -> io::Result<Value> {
+        let path = self.root.join(name);
+        let text = fs::read_to_string(&path)?;
+        match FileFormat::of(&path) {
+            Some(FileFormat::Json) => serde_json::from_str(&text).map_err(invalid_data),
+            Some(FileFormat::Toml) => parse_toml(&text),
+            _ => Err(invalid_data("only .json and .toml files can be included")),
+        }
+        .map_err(|err| invalid_data(format!("{}: {err}", path.display())))
+    }
+
+    fn index(&self) -> io::Result<Value> {
+        self.read_file(INDEX_FILE).map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} holds no content", self.describe()),
+            ),
+            _ => err,
+        })
+    }
+
+    /// `value` with every marker in it replaced by the content it stands for.
+    fn resolve(&self, value: Value, depth: usize) -> io::Result<Value> {
+        let Value::Object(fields) = value else {
+            return Ok(value);
+        };
+        if let Some(name) = fields.get(INCLUDE).and_then(Value::as_str) {
+            if depth == MAX_INCLUDE_DEPTH {
+                return Err(invalid_data(format!(
+                    "{name}: includes nest deeper than {MAX_INCLUDE_DEPTH}"
+                )));
+            }
+            return self.resolve(self.read_file(name)?, depth + 1);
+        }
+        if let Some(dir) = fields.get(COLLECTION).and_then(Value::as_str) {
+            let order = fields
+                .get(ORDER)
+                .and_then(Value::as_array)
+                .map(|slugs| slugs.iter().filter_map(Value::as_str).collect())
+                .unwrap_or_default();
+            return self.read_collection(dir, order).map(Value::Array);
+        }
+        fields
+            .into_iter()
+            .map(|(key, value)| Ok((key, self.resolve(value, depth)?)))
+            .collect::<io::Result<_>>()
+            .map(Value::Object)
+    }
+
+    fn read_collection(&self, dir: &str, order: Vec<&str>) -> io::Result<Vec<Value>> {
+        let mut files = BTreeMap::new();
+        for entry in fs::read_dir(self.root.join(dir))? {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if name.starts_with('.') || path.is_dir() {
+                continue;
+            }
+            let (Some(slug), Some(format)) = (path.file_stem(), FileFormat::of(&path)) else {
+                return Err(invalid_data(format!(
+                    "{}: entity files must be .json, .toml or .md",
+                    path.display()
+                )));
+            };
+            let slug = slug.to_string_lossy().into_owned();
+            if let Some((other, _)) = files.insert(slug.clone(), (path.clone(), format)) {
+                return Err(invalid_data(format!(
+                    "{} and {} are both `{slug}`",
+                    other.display(),
+                    path.display()
+                )));
+            }
+        }
+
+        let mut entities = Vec::with_capacity(files.len());
+        for slug in order {
+            if let Some((path, format)) = files.remove(slug) {
+                entities.push(read_entity(&path, format, slug, BodyField::of(dir))?);
+            }
+        }
+        for (slug, (path, format)) in files {
+            entities.push(read_entity(&path, format, &slug, BodyField::of(dir))?);
+        }
+        Ok(entities)
+    }
+
+    /// The directory `collection` is kept in, found by following its seed
+    /// pointers through the index and included files.
+    fn collection_dir(&self, collection: Collection) -> io::Result<String> {
+        for pointer in collection.pointers() {
+            let mut value = self.index()?;
+            for key in pointer.split('/').skip(1) {
+                if let Some(name) = value.get(INCLUDE).and_then(Value::as_str) {
+                    value = self.read_file(name)?;
+                }
+                value = value.get(key).cloned().unwrap_or_default();
+            }
+            if let Some(dir) = value.get(COLLECTION).and_then(Value::as_str) {
+                return Ok(dir.to_string());
+            }
+        }
+        Err(invalid_data(format!(
+            "{} keeps no {} directory; add the collection with `rustdev split`",
+            self.describe(),
+            collection.path()
+        )))
+    }
+
+    /// Writes the entities of `items` to `dir` under `root` and returns the
+    /// marker standing for them.
+    fn write_collection(root: &Path, dir: &str, items: &[Value]) -> io::Result<Value> {
+        fs::create_dir_all(root.join(dir))?;
+        let mut order = Vec::with_capacity(items.len());
+        for item in items {
+            let slug = slug_of(item).unwrap_or_default();
+            let (format, text) = encode_entity(item, BodyField::of(dir), None)?;
+            fs::write(
+                root.join(dir)
+                    .join(format!("{slug}.{}", format.extension())),
+                text,
+            )?;
+            order.push(slug);
+        }
+        Ok(json!({ COLLECTION: dir, ORDER: order }))
+    }
+
+    fn revision(&self) -> io::Result<Option<String>> {
+        Ok(mtime_revision(latest_change(&self.root)?))
+    }
+}
+
+fn read_entity(path: &Path, format: FileFormat, slug: &str, body: BodyField) -> io::Result<Value> {
+    let text = fs::read_to_string(path)?;
+    let fields = parse(format, &text, body)
+        .map_err(|err| invalid_data(format!("{}: {err}", path.display())))?;
+    match fields.get("slug") {
+        Some(stored) if stored != slug => Err(invalid_data(format!(
+            "{}: `slug` is {stored} but the file is named `{slug}`",
+            path.display()
+        ))),
+        Some(_) => Ok(Value::Object(fields)),
+        None => {
+            let mut entity = Map::new();
+            entity.insert("slug".into(), slug.into());
+            entity.extend(fields);
+            Ok(Value::Object(entity))
+        }
+    }
+}
+
+impl ContentStore for DirectoryStore {
+    fn describe(&self) -> String {
+        format!("dir:{}", self.root.display())
+    }
+
+    fn version(&self) -> StoreVersion {
+        StoreVersion::Modified(latest_change(&self.root).ok().flatten())
+    }
+
+    fn load(&self) -> io::Result<Snapshot> {
+        let modified = latest_change(&self.root)?;
+        let seed = self.resolve(self.index()?, 0)?;
+        Ok(Snapshot {
+            seed,
+            modified,
+            revision: mtime_revision(modified),
+        })
+    }
+
+    /// Writes only the entity's own file, keeping its format when that
+    /// still holds the entity exactly.
+    fn apply(&self, mutation: &Mutation) -> io::Result<Option<String>> {
+        if mutation.base.is_some() && mutation.base != self.revision()?.as_deref() {
+            return Err(io::Error::other(format!(
+                "{} changed on disk since it was read; try again",
+                self.describe()
+            )));
+        }
+        if !file_safe(mutation.slug) {
+            return Err(invalid_data(format!(
+                "`{}` cannot be a file name",
+                mutation.slug
+            )));
+        }
+        let dir = self.collection_dir(mutation.collection)?;
+        let path = |format: FileFormat| {
+            self.root
+                .join(&dir)
+                .join(format!("{}.{}", mutation.slug, format.extension()))
+        };
+        let existing: Vec<_> = FileFormat::ALL
+            .into_iter()
+            .filter(|format| path(*format).exists())
+            .collect();
+
+        let written = match mutation.entity {
+            Some(entity) => {
+                let (format, text) =
+                    encode_entity(entity, BodyField::of(&dir), existing.first().copied())?;
+                replace_file(&path(format), text.as_bytes())?;
+                Some(format)
+            }
+            None => None,
+        };
+        for format in existing
+            .into_iter()
+            .filter(|format| Some(*format) != written)
+        {
+            fs::remove_file(path(format))?;
+        }
+        self.revision()
+    }
+
+    /// Writes the new tree next to the old one and swaps it in, so a
+    /// failed write leaves the old content in place.
+    fn replace(&self, seed: &Value, _author: &str, _message: &str) -> io::Result<()> {
+        let fields = seed
+            .as_object()
+            .ok_or_else(|| invalid_data("the seed must be an object"))?;
+        let name = self
+            .root
+            .file_name()
+            .ok_or_else(|| invalid_data(format!("{} has no name", self.root.display())))?
+            .to_string_lossy();
+        let staging = self.root.with_file_name(format!(".{name}.new"));
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+        fs::create_dir_all(&staging)?;
+
+        let mut index = Map::new();
+        for (key, value) in fields {
+            let kept = match value {
+                _ if !file_safe(key) => value.clone(),
+                Value::Array(items) if splittable(value) => {
+                    Self::write_collection(&staging, key, items)?
+                }
+                Value::Object(section) => {
+                    let mut section = section.clone();
+                    for (field, value) in section.iter_mut() {
+                        if file_safe(field) && splittable(value) {
+                            let items = value.as_array().cloned().unwrap_or_default();
+                            *value = Self::write_collection(
+                                &staging,
+                                &format!("{key}/{field}"),
+                                &items,
+                            )?;
+                        }
+                    }
+                    fs::write(
+                        staging.join(format!("{key}.json")),
+                        to_seed_json(&Value::Object(section))?,
+                    )?;
+                    json!({ INCLUDE: format!("{key}.json") })
+                }
+                Value::Array(_) => {
+                    fs::write(staging.join(format!("{key}.json")), to_seed_json(value)?)?;
+                    json!({ INCLUDE: format!("{key}.json") })
+                }
+                _ => value.clone(),
+            };
+            index.insert(key.clone(), kept);
+        }
+        fs::write(
+            staging.join(INDEX_FILE),
+            to_seed_json(&Value::Object(index))?,
+        )?;
+
+        let old = self.root.with_file_name(format!(".{name}.old"));
+        if self.root.exists() {
+            fs::rename(&self.root, &old)?;
+        }
+        fs::rename(&staging, &self.root)?;
+        if old.exists() {
+            fs::remove_dir_all(&old)?;
+        }
+        Ok(())
+    }
+}
+
+/// `rustdev split [--out <dir>] [--force]`: converts the seed file into a
+/// content directory, by default `CONTENT_DIR` or `content`.
+pub(crate) fn split_command(args: &[String]) -> io::Result<()> {
+    let usage = || {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: rustdev split [--out <dir>] [--force]",
+        )
+    };
+    let mut out = env::var("CONTENT_DIR").unwrap_or_else(|_| DEFAULT_CONTENT_DIR.to_string());
+    let mut force = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => out = args.next().ok_or_else(usage)?.clone(),
+            "--force" => force = true,
+            _ => return Err(usage()),
+        }
+    }
+    store::copy(
+        &store::JsonFileStore::new(DATA_PATH),
+        &DirectoryStore::new(out),
+        force,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(dir: &str, entity: Value) -> (FileFormat, String) {
+        let body = BodyField::of(dir);
+        let (format, text) = encode_entity(&entity, body, None).unwrap();
+        let mut read = parse(format, &text, body).unwrap();
+        read.insert("slug".into(), entity["slug"].clone());
+        assert_eq!(Value::Object(read), entity);
+        (format, text)
+    }
+
+    #[test]
+    fn posts_without_body_md_keep_about_as_the_body() {
+        let (format, text) = round_trip(
+            "news",
+            json!({"slug": "release", "title": "Release", "about": "What changed."}),
+        );
+        assert_eq!(format, FileFormat::Markdown);
+        assert!(text.ends_with("+++\n\nWhat changed.\n"));
+        assert!(!text.contains("about ="));
+    }
+
+    #[test]
+    fn posts_with_body_md_use_it_as_the_body() {
+        let (format, text) = round_trip(
+            "news",
+            json!({"slug": "release", "about": "Summary.", "body_md": "# Release\n\nDetails."}),
+        );
+        assert_eq!(format, FileFormat::Markdown);
+        assert!(text.contains("about = \"Summary.\""));
+        assert!(text.ends_with("+++\n\n# Release\n\nDetails.\n"));
+    }
+
+    #[test]
+    fn other_collections_use_about() {
+        let (format, text) = round_trip(
+            "tools",
+            json!({"slug": "anchor", "name": "Anchor", "about": "Solana framework."}),
+        );
+        assert_eq!(format, FileFormat::Markdown);
+        assert!(text.ends_with("\nSolana framework.\n"));
+    }
+
+    #[test]
+    fn entities_without_a_body_stay_json() {
+        let (format, _) = round_trip("tools", json!({"slug": "anchor", "name": "Anchor"}));
+        assert_eq!(format, FileFormat::Json);
+    }
+}
@@ -0,0 +1,801 @@
+use actix_web::{http::header, HttpRequest};
+use serde_json::{json, Map, Value};
+
+/// Representations a page can be served in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Format {
+    Html,
+    Json,
+    JsonLd,
+    Markdown,
+    Text,
+}
+
+/// Every page route offers all formats; HTML wins ties and bare `*/*`.
+pub(crate) const PAGE_FORMATS: [Format; 5] = [
+    Format::Html,
+    Format::Json,
+    Format::JsonLd,
+    Format::Markdown,
+    Format::Text,
+];
+
+/// Error bodies only come as HTML or JSON.
+pub(crate) const ERROR_FORMATS: [Format; 2] = [Format::Html, Format::Json];
+
+impl Format {
+    pub(crate) fn media_type(self) -> &'static str {
+        match self {
+            Format::Html => "text/html",
+            Format::Json => "application/json",
+            Format::JsonLd => "application/ld+json",
+            Format::Markdown => "text/markdown",
+            Format::Text => "text/plain",
+        }
+    }
+
+    pub(crate) fn content_type(self) -> &'static str {
+        match self {
+            Format::Html => "text/html; charset=utf-8",
+            Format::Json => "application/json",
+            Format::JsonLd => "application/ld+json",
+            Format::Markdown => "text/markdown; charset=utf-8",
+            Format::Text => "text/plain; charset=utf-8",
+        }
+    }
+
+    /// Name accepted by `?format=`.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Format::Html => "html",
+            Format::Json => "json",
+            Format::JsonLd => "jsonld",
+            Format::Markdown => "markdown",
+            Format::Text => "text",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "html" => Some(Format::Html),
+            "json" => Some(Format::Json),
+            "jsonld" | "json-ld" => Some(Format::JsonLd),
+            "md" | "markdown" => Some(Format::Markdown),
+            "txt" | "text" => Some(Format::Text),
+            _ => None,
+        }
+    }
+
+    /// Media types this format answers to, besides wildcards.
+    fn aliases(self) -> &'static [&'static str] {
+        match self {
+            Format::Html => &["text/html", "application/xhtml+xml"],
+            Format::Json => &["application/json"],
+            Format::JsonLd => &["application/ld+json"],
+            Format::Markdown => &["text/markdown", "text/x-markdown"],
+            Format::Text => &["text/plain"],
+        }
+    }
+
+    pub(crate) fn is_json(self) -> bool {
+        matches!(self, Format::Json | Format::JsonLd)
+    }
+}
+
+/// Neither `?format=` nor `Accept` matched anything in `available`.
+#[derive(Debug)]
+pub(crate) struct NotAcceptable;
+
+/// Picks the representation for `req` out of `available`, which is listed in
+/// server preference order. `?format=` overrides the `Accept` header.
+pub(crate) fn negotiate(req: &HttpRequest, available: &[Format]) -> Result<Format, NotAcceptable> {
+    if let Some(requested) = query_format(req.query_string()) {
+        return Format::from_name(requested)
+            .filter(|format| available.contains(format))
+            .ok_or(NotAcceptable);
+    }
+
+    let accept = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("");
+    negotiate_accept(accept, available)
+}
+
+fn query_format(query: &str) -> Option<&str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "format")
+        .map(|(_, value)| value)
+}
+
+struct MediaRange<'a> {
+    kind: &'a str,
+    subtype: &'a str,
+    q: f32,
+}
+
+fn parse_accept(accept: &str) -> Vec<MediaRange<'_>> {
+    accept
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let (kind, subtype) = parts.next()?.trim().split_once('/')?;
+            let q = parts
+                .filter_map(|param| param.trim().split_once('='))
+                .find(|(key, _)| key.trim().eq_ignore_ascii_case("q"))
+                .and_then(|(_, value)| value.trim().parse::<f32>().ok())
+                .unwrap_or(1.0)
+                .clamp(0.0, 1.0);
+            Some(MediaRange {
+                kind: kind.trim(),
+                subtype: subtype.trim(),
+                q,
+            })
+        })
+        .collect()
+}
+
+/// How specifically `range` names `format`: 3 for an exact type, 2 for a
+/// `type/*` or structured-suffix match, 1 for `*/*`.
+fn specificity(range: &MediaRange<'_>, format: Format) -> Option<u8> {
+    if range.kind == "*" && range.subtype == "*" {
+        return Some(1);
+    }
+    let exact = format.aliases().iter().any(|alias| {
+        alias
+            .split_once('/')
+            .map(|(kind, subtype)| {
+                kind.eq_ignore_ascii_case(range.kind) && subtype.eq_ignore_ascii_case(range.subtype)
+            })
+            .unwrap_or(false)
+    });
+    if exact {
+        return Some(3);
+    }
+    let primary = format.media_type().split('/').next().unwrap_or("");
+    if range.subtype == "*" && range.kind.eq_ignore_ascii_case(primary) {
+        return Some(2);
+    }
+    // Vendor types such as `application/vnd.api+json` are served plain JSON.
+    if format == Format::Json && range.subtype.ends_with("+json") && range.subtype != "ld+json" {
+        return Some(2);
+    }
+    None
+}
+
+pub(crate) fn negotiate_accept(
+    accept: &str,
+    available: &[Format],
+) -> Result<Format, NotAcceptable> {
+    let ranges = parse_accept(accept);
+    if ranges.is_empty() {
+        return available.first().copied().ok_or(NotAcceptable);
+    }
+
+    let mut best: Option<(Format, f32)> = None;
+    for format in available {
+        let q = ranges
+            .iter()
+            .filter_map(|range| specificity(range, *format).map(|s| (s, range.q)))
+            .fold(None, |acc: Option<(u8, f32)>, (s, q)| match acc {
+                Some((best_s, best_q)) if best_s > s || (best_s == s && best_q >= q) => acc,
+                _ => Some((s, q)),
+            })
+            .map(|(_, q)| q)
+            .unwrap_or(0.0);
+        if q > 0.0 && best.map(|(_, best_q)| q > best_q).unwrap_or(true) {
+            best = Some((*format, q));
+        }
+    }
+    best.map(|(format, _)| format).ok_or(NotAcceptable)
+}
+
+/// URL prefix and page heading for each template.
+fn page_info(template: &str) -> (Option<&'static str>, &'static str) {
+    match template {
+        "ecosystems-list" | "ecosystem-single" => (Some("ecosystems"), "Ecosystems"),
+        "tools-list" | "tool-single" => (Some("tools"), "Tools"),
+        "events-list" | "event-single" => (Some("events"), "Events"),
+        "learn-list" | "learning-single" => (Some("learn"), "Learn Rust"),
+        "creators-list" | "creator-single" => (Some("creators"), "Creators"),
+        "news-list" | "post-single" => (Some("news"), "News"),
+        "jobs-list" => (Some("jobs"), "Jobs"),
+        _ => (None, "rust.dev"),
+    }
+}
+
+fn is_list(template: &str) -> bool {
+    template.ends_with("-list") || template == "index-rust"
+}
+
+fn str_field<'a>(value: &'a Value, keys: &[&str]) -> Option<&'a str> {
+    keys.iter()
+        .filter_map(|key| value.get(*key).and_then(Value::as_str))
+        .map(str::trim)
+        .find(|s| !s.is_empty())
+}
+
+const TITLE_KEYS: [&str; 2] = ["name", "title"];
+const SUMMARY_KEYS: [&str; 7] = [
+    "one_liner",
+    "description",
+    "summary",
+    "deck",
+    "teaser",
+    "about",
+    "schedule_note",
+];
+const FACT_KEYS: [(&str, &str); 10] = [
+    ("category", "Category"),
+    ("type", "Type"),
+    ("kind", "Kind"),
+    ("status", "Status"),
+    ("starts_on", "Starts"),
+    ("ends_on", "Ends"),
+    ("location", "Location"),
+    ("published_on", "Published"),
+    ("difficulty", "Difficulty"),
+    ("duration_hours", "Duration (hours)"),
+];
+
+struct Item {
+    name: String,
+    href: Option<String>,
+    summary: Option<String>,
+}
+
+struct Section {
+    title: Option<String>,
+    items: Vec<Item>,
+}
+
+/// Format-neutral outline of a page, rendered as Markdown or plain text.
+struct Document {
+    title: String,
+    summary: Option<String>,
+    facts: Vec<(&'static str, String)>,
+    body: Option<String>,
+    tags: Vec<String>,
+    links: Vec<(String, String)>,
+    sections: Vec<Section>,
+}
+
+fn item_from(value: &Value, collection: Option<&str>) -> Option<Item> {
+    let name = str_field(value, &TITLE_KEYS)?.to_string();
+    let slug = value.get("slug").and_then(Value::as_str);
+    // Carousel entries carry their collection in `type`.
+    let prefix = value
+        .get("type")
+        .and_then(Value::as_str)
+        .filter(|t| matches!(*t, "news" | "events"))
+        .or(collection);
+    let href = str_field(value, &["href", "apply_url"])
+        .map(str::to_string)
+        .or_else(|| match (prefix, slug) {
+            (Some(prefix), Some(slug)) if prefix != "jobs" => Some(format!("/{prefix}/{slug}")),
+            _ => None,
+        })
+        .or_else(|| str_field(value, &["url"]).map(str::to_string));
+    Some(Item {
+        name,
+        href,
+        summary: str_field(value, &SUMMARY_KEYS).map(str::to_string),
+    })
+}
+
+fn items_from(values: &[Value], collection: Option<&str>) -> Vec<Item> {
+    values
+        .iter()
+        .filter_map(|value| item_from(value, collection))
+        .collect()
+}
+
+pub(crate) fn heading_for(key: &str) -> String {
+    let mut words = key.split('_').map(str::to_string).collect::<Vec<_>>();
+    if let Some(first) = words.first_mut() {
+        let mut chars = first.chars();
+        if let Some(c) = chars.next() {
+            *first = format!("{}{}", c.to_uppercase(), chars.as_str());
+        }
+    }
+    words.join(" ")
+}
+
+fn is_group(value: &Value) -> bool {
+    value.get("title").is_some()
+        && value
+            .as_object()
+            .map(|obj| {
+                obj.values().filter_map(Value::as_array).any(|entries| {
+                    entries
+                        .iter()
+                        .any(|entry| str_field(entry, &TITLE_KEYS).is_some())
+                })
+            })
+            .unwrap_or(false)
+}
+
+fn list_sections(context: &Value, collection: Option<&str>) -> Vec<Section> {
+    let Some(map) = context.as_object() else {
+        return Vec::new();
+    };
+
+    let mut sections = Vec::new();
+    for (key, value) in map {
+        if key == "labels" {
+            continue;
+        }
+        let Some(values) = value.as_array() else {
+            continue;
+        };
+        // Grouped listings: `[{title, tools|creators|paths: [...]}]`.
+        let grouped = !values.is_empty() && values.iter().all(is_group);
+        if grouped {
+            for group in values {
+                let items = group
+                    .as_object()
+                    .into_iter()
+                    .flat_map(Map::values)
+                    .filter_map(Value::as_array)
+                    .flat_map(|entries| items_from(entries, collection))
+                    .collect();
+                sections.push(Section {
+                    title: str_field(group, &["title"]).map(str::to_string),
+                    items,
+                });
+            }
+        } else {
+            let items = items_from(values, collection);
+            if !items.is_empty() {
+                sections.push(Section {
+                    title: Some(heading_for(key)),
+                    items,
+                });
+            }
+        }
+    }
+    sections
+}
+
+fn links_from(context: &Value) -> Vec<(String, String)> {
+    let mut links = Vec::new();
+    for key in ["links", "official_links"] {
+        match context.get(key) {
+            Some(Value::Object(map)) => {
+                for (label, url) in map {
+                    if let Some(url) = url.as_str() {
+                        links.push((label.clone(), url.to_string()));
+                    }
+                }
+            }
+            Some(Value::Array(entries)) => {
+                for entry in entries {
+                    if let (Some(label), Some(url)) = (
+                        entry.get("label").and_then(Value::as_str),
+                        entry.get("url").and_then(Value::as_str),
+                    ) {
+                        links.push((label.to_string(), url.to_string()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    for key in ["url", "href"] {
+        if let Some(url) = str_field(context, &[key]) {
+            links.push((key.to_string(), url.to_string()));
+        }
+    }
+    links
+}
+
+fn document(template: &str, context: &Value) -> Document {
+    let (collection, heading) = page_info(template);
+    if is_list(template) {
+        return Document {
+            title: heading.to_string(),
+            summary: None,
+            facts: Vec::new(),
+            body: None,
+            tags: Vec::new(),
+            links: Vec::new(),
+            sections: list_sections(context, collection),
+        };
+    }
+
+    let summary = str_field(context, &SUMMARY_KEYS).map(str::to_string);
+    let body = str_field(context, &["body_md"])
+        .filter(|body| Some(*body) != summary.as_deref())
+        .map(str::to_string);
+    let facts = FACT_KEYS
+        .iter()
+        .filter_map(|(key, label)| {
+            let value = match context.get(*key)? {
+                Value::String(s) if !s.trim().is_empty() => s.clone(),
+                Value::Number(n) => n.to_string(),
+                _ => return None,
+            };
+            Some((*label, value))
+        })
+        .collect();
+    let tags = ["tags", "topics", "focus"]
+        .iter()
+        .filter_map(|key| context.get(*key).and_then(Value::as_array))
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect();
+    let mut sections = Vec::new();
+    for (key, title) in [
+        ("featured_tools", "Featured tools"),
+        ("resources_data", "Resources"),
+    ] {
+        if let Some(values) = context.get(key).and_then(Value::as_array) {
+            let items = items_from(
+                values,
+                if key == "featured_tools" {
+                    Some("tools")
+                } else {
+                    None
+                },
+            );
+            if !items.is_empty() {
+                sections.push(Section {
+                    title: Some(title.to_string()),
+                    items,
+                });
+            }
+        }
+    }
+    if let Some(milestones) = context.get("milestones").and_then(Value::as_array) {
+        let items: Vec<Item> = milestones
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|name| Item {
+                name: name.to_string(),
+                href: None,
+                summary: None,
+            })
+            .collect();
+        if !items.is_empty() {
+            sections.push(Section {
+                title: Some("Milestones".to_string()),
+                items,
+            });
+        }
+    }
+
+    Document {
+        title: str_field(context, &TITLE_KEYS)
+            .unwrap_or(heading)
+            .to_string(),
+        summary,
+        facts,
+        body,
+        tags,
+        links: links_from(context),
+        sections,
+    }
+}
+
+/// Markdown rendering of a page context.
+pub(crate) fn markdown(template: &str, context: &Value) -> String {
+    let doc = document(template, context);
+    let mut out = format!("# {}\n", doc.title);
+
+    if let Some(summary) = &doc.summary {
+        out.push_str(&format!("\n{summary}\n"));
+    }
+    if !doc.facts.is_empty() {
+        out.push('\n');
+        for (label, value) in &doc.facts {
+            out.push_str(&format!("- **{label}:** {value}\n"));
+        }
+    }
+    if let Some(body) = &doc.body {
+        out.push_str(&format!("\n{}\n", body.trim_end()));
+    }
+    if !doc.tags.is_empty() {
+        out.push_str(&format!("\n**Tags:** {}\n", doc.tags.join(", ")));
+    }
+    for section in &doc.sections {
+        if let Some(title) = &section.title {
+            out.push_str(&format!("\n## {title}\n"));
+        }
+        out.push('\n');
+        for item in &section.items {
+            let name = match &item.href {
+                Some(href) => format!("[{}]({href})", item.name),
+                None => item.name.clone(),
+            };
+            match &item.summary {
+                Some(summary) => out.push_str(&format!("- {name} — {summary}\n")),
+                None => out.push_str(&format!("- {name}\n")),
+            }
+        }
+    }
+    if !doc.links.is_empty() {
+        out.push_str("\n## Links\n\n");
+        for (label, url) in &doc.links {
+            out.push_str(&format!("- [{label}]({url})\n"));
+        }
+    }
+    out
+}
+
+/// Plain-text rendering of a page context.
+pub(crate) fn plain_text(template: &str, context: &Value) -> String {
+    let doc = document(template, context);
+    let mut out = format!("{}\n{}\n", doc.title, "=".repeat(doc.title.chars().count()));
+
+    if let Some(summary) = &doc.summary {
+        out.push_str(&format!("\n{summary}\n"));
+    }
+    if !doc.facts.is_empty() {
+        out.push('\n');
+        for (label, value) in &doc.facts {
+            out.push_str(&format!("{label}: {value}\n"));
+        }
+    }
+    if let Some(body) = &doc.body {
+        out.push_str(&format!("\n{}\n", body.trim_end()));
+    }
+    if !doc.tags.is_empty() {
+        out.push_str(&format!("\nTags: {}\n", doc.tags.join(", ")));
+    }
+    for section in &doc.sections {
+        if let Some(title) = &section.title {
+            out.push_str(&format!(
+                "\n{title}\n{}\n",
+                "-".repeat(title.chars().count())
+            ));
+        } else {
+            out.push('\n');
+        }
+        for item in &section.items {
+            out.push_str(&format!("* {}", item.name));
+            if let Some(href) = &item.href {
+                out.push_str(&format!(" <{href}>"));
+            }
+            out.push('\n');
+            if let Some(summary) = &item.summary {
+                out.push_str(&format!("  {summary}\n"));
+            }
+        }
+    }
+    if !doc.links.is_empty() {
+        out.push_str("\nLinks\n-----\n");
+        for (label, url) in &doc.links {
+            out.push_str(&format!("{label}: {url}\n"));
+        }
+    }
+    out
+}
+
+fn absolute(base: &str, href: &str) -> String {
+    if href.starts_with("http://") || href.starts_with("https://") {
+        href.to_string()
+    } else {
+        format!("{base}{href}")
+    }
+}
+
+/// schema.org JSON-LD for a page context. `base` is the request origin and
+/// `path` the page path, used to build absolute URLs.
+pub(crate) fn json_ld(template: &str, context: &Value, base: &str, path: &str) -> Value {
+    let doc = document(template, context);
+    let url = absolute(base, path);
+
+    if template == "index-rust" {
+        return json!({
+            "@context": "https://schema.org",
+            "@type": "WebSite",
+            "name": "rust.dev",
+            "url": url,
+        });
+    }
+
+    if is_list(template) {
+        let elements: Vec<Value> = doc
+            .sections
+            .iter()
+            .flat_map(|section| section.items.iter())
+            .enumerate()
+            .map(|(idx, item)| {
+                let mut element = json!({
+                    "@type": "ListItem",
+                    "position": idx + 1,
+                    "name": item.name,
+                });
+                if let Some(href) = &item.href {
+                    element["url"] = Value::String(absolute(base, href));
+                }
+                element
+            })
+            .collect();
+        return json!({
+            "@context": "https://schema.org",
+            "@type": "ItemList",
+            "name": doc.title,
+            "url": url,
+            "numberOfItems": elements.len(),
+            "itemListElement": elements,
+        });
+    }
+
+    let schema_type = match template {
+        "tool-single" => "SoftwareApplication",
+        "event-single" => "Event",
+        "learning-single" => "Course",
+        "creator-single" => "Person",
+        "post-single" => "NewsArticle",
+        _ => "Thing",
+    };
+    let mut ld = json!({
+        "@context": "https://schema.org",
+        "@type": schema_type,
+        "@id": url,
+        "url": url,
+        "name": doc.title,
+    });
+    if let Some(summary) = &doc.summary {
+        ld["description"] = Value::String(summary.clone());
+    }
+    if !doc.tags.is_empty() {
+        ld["keywords"] = Value::String(doc.tags.join(", "));
+    }
+    let same_as: Vec<&str> = doc
+        .links
+        .iter()
+        .map(|(_, url)| url.as_str())
+        .filter(|url| url.starts_with("http"))
+        .collect();
+    if !same_as.is_empty() {
+        ld["sameAs"] = json!(same_as);
+    }
+
+    match template {
+        "tool-single" => {
+            if let Some(category) = str_field(context, &["category"]) {
+                ld["applicationCategory"] = Value::String(category.to_string());
+            }
+        }
+        "event-single" => {
+            if let Some(starts) = str_field(context, &["starts_on"]) {
+                ld["startDate"] = Value::String(starts.to_string());
+            }
+            if let Some(ends) = str_field(context, &["ends_on"]) {
+                ld["endDate"] = Value::String(ends.to_string());
+            }
+            if let Some(location) = str_field(context, &["location"]) {
+                ld["location"] = json!({ "@type": "Place", "name": location });
+            }
+        }
+        "learning-single" => {
+            if let Some(difficulty) = str_field(context, &["difficulty"]) {
+                ld["educationalLevel"] = Value::String(difficulty.to_string());
+            }
+            if let Some(hours) = context.get("duration_hours").and_then(Value::as_u64) {
+                if hours > 0 {
+                    ld["timeRequired"] = Value::String(format!("PT{hours}H"));
+                }
+            }
+        }
+        "post-single" => {
+            ld["headline"] = Value::String(doc.title.clone());
+            if let Some(published) = str_field(context, &["published_on"]) {
+                ld["datePublished"] = Value::String(published.to_string());
+            }
+            if let Some(body) = &doc.body {
+                ld["articleBody"] = Value::String(body.clone());
+            }
+            if let Some(author) = str_field(context, &["author_handle"]) {
+                ld["author"] = json!({ "@type": "Person", "name": author });
+            }
+        }
+        _ => {}
+    }
+    ld
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn negotiated(uri: &str, accept: Option<&str>, available: &[Format]) -> Option<Format> {
+        let mut req = TestRequest::get().uri(uri);
+        if let Some(accept) = accept {
+            req = req.insert_header((header::ACCEPT, accept));
+        }
+        negotiate(&req.to_http_request(), available).ok()
+    }
+
+    fn page(accept: &str) -> Option<Format> {
+        negotiated("/tools", Some(accept), &PAGE_FORMATS)
+    }
+
+    #[test]
+    fn a_zero_q_value_refuses_a_type() {
+        assert_eq!(page("text/html;q=0"), None);
+        assert_eq!(page("text/html;q=0, */*"), Some(Format::Json));
+        assert_eq!(page("*/*, application/json;q=0"), Some(Format::Html));
+    }
+
+    #[test]
+    fn a_named_type_beats_a_low_wildcard() {
+        assert_eq!(page("*/*;q=0.1, application/json"), Some(Format::Json));
+        assert_eq!(page("application/json;q=0.5, text/*"), Some(Format::Html));
+        assert_eq!(
+            page("text/plain;q=0.9, text/markdown"),
+            Some(Format::Markdown)
+        );
+    }
+
+    #[test]
+    fn wildcards_and_aliases_follow_server_preference() {
+        assert_eq!(page("*/*"), Some(Format::Html));
+        assert_eq!(page("text/*"), Some(Format::Html));
+        assert_eq!(page("TEXT/X-MARKDOWN"), Some(Format::Markdown));
+        assert_eq!(page("application/vnd.api+json"), Some(Format::Json));
+        assert_eq!(page("application/ld+json"), Some(Format::JsonLd));
+        assert_eq!(page("image/png"), None);
+    }
+
+    #[test]
+    fn empty_or_unparseable_accept_gets_the_first_format() {
+        assert_eq!(
+            negotiated("/tools", None, &PAGE_FORMATS),
+            Some(Format::Html)
+        );
+        assert_eq!(page(""), Some(Format::Html));
+        assert_eq!(page("nonsense"), Some(Format::Html));
+        assert_eq!(page("text/html;q=high"), Some(Format::Html));
+        assert_eq!(
+            negotiated("/", Some("nonsense"), &[Format::Json]),
+            Some(Format::Json)
+        );
+    }
+
+    #[test]
+    fn the_format_query_overrides_accept() {
+        let accept = Some("text/html");
+        assert_eq!(
+            negotiated("/tools?format=md", accept, &PAGE_FORMATS),
+            Some(Format::Markdown)
+        );
+        assert_eq!(
+            negotiated("/tools?q=x&format=JSON-LD", accept, &PAGE_FORMATS),
+            Some(Format::JsonLd)
+        );
+        assert_eq!(negotiated("/tools?format=xml", accept, &PAGE_FORMATS), None);
+        assert_eq!(negotiated("/tools?format=", accept, &PAGE_FORMATS), None);
+        assert_eq!(
+            negotiated("/nope?format=text", accept, &ERROR_FORMATS),
+            None
+        );
+    }
+
+    #[actix_web::test]
+    async fn not_acceptable_lists_the_available_formats() {
+        let res = crate::not_acceptable(&ERROR_FORMATS);
+        assert_eq!(res.status(), 406);
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Accept");
+        let body = actix_web::body::to_bytes(res.into_body()).await.unwrap();
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body,
+            json!({
+                "error": "Not acceptable",
+                "code": 406,
+                "available": [
+                    {"format": "html", "media_type": "text/html"},
+                    {"format": "json", "media_type": "application/json"},
+                ],
+            })
+        );
+    }
+}
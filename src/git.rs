@@ -0,0 +1,464 @@
+use git2::{
+    build::{CheckoutBuilder, TreeUpdateBuilder},
+    BranchType, Commit, FileMode, Oid, Repository, Signature,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::store::{invalid_data, to_seed_json, ContentStore, Mutation, Snapshot, StoreVersion};
+
+/// Commits made for editors list the server as committer unless the
+/// repository configures `user.name` and `user.email`.
+const COMMITTER_NAME: &str = "rustdev";
+const COMMITTER_EMAIL: &str = "rustdev@localhost";
+/// Editors have no email address, so their commits get one under this
+/// reserved domain.
+const AUTHOR_DOMAIN: &str = "editors.invalid";
+/// Commits shown on `/admin/history`.
+pub(crate) const HISTORY_LIMIT: usize = 100;
+
+fn git_error(err: git2::Error) -> io::Error {
+    io::Error::other(err.message().to_string())
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+
+/// What the served content follows.
+#[derive(Clone, Debug)]
+enum Pin {
+    /// The tip of a local branch. Edits are committed onto it, and commits
+    /// made to it by anyone else are picked up by the content watcher.
+    Branch(String),
+    /// One fixed commit. Nothing can change it, so editing is off.
+    Commit(Oid),
+}
+
+/// A seed file kept in a local git repository and read from a branch or
+/// commit rather than from the working tree, so only committed content is
+/// served.
+#[derive(Clone, Debug)]
+pub(crate) struct GitContent {
+    repo: PathBuf,
+    /// The seed's path inside the repository.
+    file: PathBuf,
+    pin: Pin,
+}
+
+impl GitContent {
+    /// `CONTENT_GIT_REPO` is a local repository and `CONTENT_GIT_FILE` the
+    /// seed's path in it. `CONTENT_GIT_REF` pins the content to a branch,
+    /// or to any other revision git understands, which makes it read-only;
+    /// it defaults to the branch checked out. `None` when
+    /// `CONTENT_GIT_REPO` is unset, which serves the seed file directly.
+    pub(crate) fn from_env(default_file: &str) -> io::Result<Option<Self>> {
+        let Ok(repo_dir) = env::var("CONTENT_GIT_REPO") else {
+            return Ok(None);
+        };
+        let file = PathBuf::from(
+            env::var("CONTENT_GIT_FILE").unwrap_or_else(|_| default_file.to_string()),
+        );
+        if file.is_absolute() {
+            return Err(invalid(
+                "CONTENT_GIT_FILE must be relative to the repository",
+            ));
+        }
+        Self::open_at(repo_dir.into(), file, env::var("CONTENT_GIT_REF").ok()).map(Some)
+    }
+
+    /// The seed at `file` in the repository at `repo_dir`, pinned to
+    /// `reference` or to the branch checked out.
+    fn open_at(repo_dir: PathBuf, file: PathBuf, reference: Option<String>) -> io::Result<Self> {
+        let repo = Repository::open(&repo_dir).map_err(git_error)?;
+        let pin = match reference {
+            Some(name) if repo.find_branch(&name, BranchType::Local).is_ok() => Pin::Branch(name),
+            Some(revision) => Pin::Commit(
+                repo.revparse_single(&revision)
+                    .and_then(|object| object.peel_to_commit())
+                    .map_err(|err| {
+                        invalid(format!("CONTENT_GIT_REF {revision:?}: {}", err.message()))
+                    })?
+                    .id(),
+            ),
+            None => {
+                let head = repo.head().map_err(git_error)?;
+                match head.shorthand().filter(|_| head.is_branch()) {
+                    Some(branch) => Pin::Branch(branch.to_string()),
+                    None => Pin::Commit(head.peel_to_commit().map_err(git_error)?.id()),
+                }
+            }
+        };
+        Ok(Self {
+            repo: repo_dir,
+            file,
+            pin,
+        })
+    }
+
+    fn open(&self) -> io::Result<Repository> {
+        Repository::open(&self.repo).map_err(git_error)
+    }
+
+    fn branch_ref(branch: &str) -> String {
+        format!("refs/heads/{branch}")
+    }
+
+    fn pinned_commit<'r>(&self, repo: &'r Repository) -> io::Result<Commit<'r>> {
+        match &self.pin {
+            Pin::Branch(branch) => repo
+                .find_reference(&Self::branch_ref(branch))
+                .and_then(|reference| reference.peel_to_commit()),
+            Pin::Commit(id) => repo.find_commit(*id),
+        }
+        .map_err(git_error)
+    }
+
+    /// The commit currently served.
+    fn head(&self) -> io::Result<Oid> {
+        Ok(self.pinned_commit(&self.open()?)?.id())
+    }
+
+    /// The seed as of the pinned commit, with that commit's time.
+    fn read(&self) -> io::Result<(Vec<u8>, Oid, SystemTime)> {
+        let repo = self.open()?;
+        let commit = self.pinned_commit(&repo)?;
+        let blob = commit
+            .tree()
+            .and_then(|tree| tree.get_path(&self.file))
+            .and_then(|entry| entry.to_object(&repo))
+            .and_then(|object| object.peel_to_blob())
+            .map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "{} at {}: {}",
+                        self.file.display(),
+                        commit.id(),
+                        err.message()
+                    ),
+                )
+            })?;
+        Ok((blob.content().to_vec(), commit.id(), commit_time(&commit)))
+    }
+
+    /// Commits `bytes` as the seed on the pinned branch with `author` as
+    /// the commit's author. Fails without committing if the branch moved
+    /// since `parent` was read. When the branch is checked out, the working
+    /// tree's copy of the seed is brought up to date too.
+    fn commit(&self, parent: Oid, bytes: &[u8], author: &str, message: &str) -> io::Result<Oid> {
+        let Pin::Branch(branch) = &self.pin else {
+            return Err(invalid(format!("content is pinned to {}", self.describe())));
+        };
+        let refname = Self::branch_ref(branch);
+        let repo = self.open()?;
+        let parent = repo.find_commit(parent).map_err(git_error)?;
+
+        let blob = repo.blob(bytes).map_err(git_error)?;
+        let tree = TreeUpdateBuilder::new()
+            .upsert(&self.file, blob, FileMode::Blob)
+            .create_updated(&repo, &parent.tree().map_err(git_error)?)
+            .and_then(|id| repo.find_tree(id))
+            .map_err(git_error)?;
+        let author =
+            Signature::now(author, &format!("{author}@{AUTHOR_DOMAIN}")).map_err(git_error)?;
+        let committer = repo
+            .signature()
+            .or_else(|_| Signature::now(COMMITTER_NAME, COMMITTER_EMAIL))
+            .map_err(git_error)?;
+        // Creating the commit moves the branch only if it still points at
+        // `parent`, so a concurrent push is never overwritten.
+        let id = repo
+            .commit(
+                Some(&refname),
+                &author,
+                &committer,
+                message,
+                &tree,
+                &[&parent],
+            )
+            .map_err(git_error)?;
+
+        let checked_out = repo
+            .head()
+            .ok()
+            .is_some_and(|head| head.name() == Some(refname.as_str()));
+        if checked_out && !repo.is_bare() {
+            repo.checkout_head(Some(CheckoutBuilder::new().force().path(&self.file)))
+                .map_err(git_error)?;
+        }
+        Ok(id)
+    }
+
+    /// The most recent commits of the pinned branch or commit that touch
+    /// the seed, newest first. Blocking.
+    pub(crate) fn log(&self, limit: usize) -> io::Result<Vec<LogEntry>> {
+        let repo = self.open()?;
+        let mut walk = repo.revwalk().map_err(git_error)?;
+        walk.push(self.pinned_commit(&repo)?.id())
+            .map_err(git_error)?;
+        let mut entries = Vec::new();
+        for id in walk {
+            if entries.len() == limit {
+                break;
+            }
+            let commit = repo
+                .find_commit(id.map_err(git_error)?)
+                .map_err(git_error)?;
+            if !touches(&commit, &self.file) {
+                continue;
+            }
+            let author = commit.author();
+            entries.push(LogEntry {
+                id: commit.id().to_string(),
+                short_id: commit
+                    .as_object()
+                    .short_id()
+                    .ok()
+                    .and_then(|id| id.as_str().map(str::to_string))
+                    .unwrap_or_default(),
+                summary: commit.summary().unwrap_or_default().to_string(),
+                author: author.name().unwrap_or_default().to_string(),
+                at: time::OffsetDateTime::from(commit_time(&commit))
+                    .format(&time::format_description::well_known::Rfc3339)
+                    .unwrap_or_default(),
+            });
+        }
+        Ok(entries)
+    }
+}
+
+impl ContentStore for GitContent {
+    /// Where the content is pinned.
+    fn describe(&self) -> String {
+        match &self.pin {
+            Pin::Branch(branch) => format!("branch {branch}"),
+            Pin::Commit(id) => format!("commit {id}"),
+        }
+    }
+
+    fn version(&self) -> StoreVersion {
+        StoreVersion::Commit(self.head().ok())
+    }
+
+    fn load(&self) -> io::Result<Snapshot> {
+        let (bytes, commit, time) = self.read()?;
+        Ok(Snapshot {
+            seed: serde_json::from_slice(&bytes).map_err(invalid_data)?,
+            modified: Some(time),
+            revision: Some(commit.to_string()),
+        })
+    }
+
+    fn apply(&self, mutation: &Mutation) -> io::Result<Option<String>> {
+        let parent = match mutation.base {
+            Some(base) => Oid::from_str(base).map_err(git_error)?,
+            None => self.head()?,
+        };
+        let id = self.commit(
+            parent,
+            &to_seed_json(mutation.seed)?,
+            mutation.author,
+            mutation.message,
+        )?;
+        Ok(Some(id.to_string()))
+    }
+
+    fn replace(&self, seed: &Value, author: &str, message: &str) -> io::Result<()> {
+        self.commit(self.head()?, &to_seed_json(seed)?, author, message)
+            .map(drop)
+    }
+
+    /// Editing needs a branch to commit to.
+    fn read_only(&self) -> Option<String> {
+        matches!(self.pin, Pin::Commit(_))
+            .then(|| format!("Content is pinned to {}", self.describe()))
+    }
+
+    fn git(&self) -> Option<&GitContent> {
+        Some(self)
+    }
+}
+
+/// A commit on `/admin/history`.
+#[derive(Debug, Serialize)]
+pub(crate) struct LogEntry {
+    pub(crate) id: String,
+    pub(crate) short_id: String,
+    pub(crate) summary: String,
+    pub(crate) author: String,
+    pub(crate) at: String,
+}
+
+fn commit_time(commit: &Commit) -> SystemTime {
+    let seconds = commit.time().seconds();
+    SystemTime::UNIX_EPOCH + Duration::from_secs(seconds.max(0) as u64)
+}
+
+/// Whether `file` differs between `commit` and its first parent.
+fn touches(commit: &Commit, file: &Path) -> bool {
+    let blob_id = |commit: &Commit| {
+        commit
+            .tree()
+            .ok()
+            .and_then(|tree| tree.get_path(file).ok())
+            .map(|entry| entry.id())
+    };
+    blob_id(commit) != commit.parent(0).ok().and_then(|parent| blob_id(&parent))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content::Collection;
+    use serde_json::json;
+
+    const SEED: &str = "content/seed.json";
+
+    /// A repository on branch `main` with the seed committed at `v1`,
+    /// then an unrelated commit on top.
+    fn repo(dir: &Path) -> Repository {
+        let repo =
+            Repository::init_opts(dir, git2::RepositoryInitOptions::new().initial_head("main"))
+                .unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Maintainer").unwrap();
+        config
+            .set_str("user.email", "maintainer@example.com")
+            .unwrap();
+        drop(config);
+        commit_file(
+            &repo,
+            SEED,
+            &json!({"version": "v1"}).to_string(),
+            "Add the seed",
+        );
+        commit_file(&repo, "README.md", "content\n", "Add a readme");
+        repo
+    }
+
+    fn commit_file(repo: &Repository, path: &str, contents: &str, message: &str) -> Oid {
+        let full = repo.workdir().unwrap().join(path);
+        std::fs::create_dir_all(full.parent().unwrap()).unwrap();
+        std::fs::write(&full, contents).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = repo.signature().unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        let parents: Vec<&Commit> = parent.iter().collect();
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .unwrap()
+    }
+
+    fn content(dir: &Path, reference: Option<&str>) -> GitContent {
+        GitContent::open_at(dir.into(), SEED.into(), reference.map(str::to_string)).unwrap()
+    }
+
+    fn mutation<'a>(seed: &'a Value, base: Option<&'a str>) -> Mutation<'a> {
+        Mutation {
+            collection: Collection::Tools,
+            slug: "anchor",
+            entity: None,
+            seed,
+            base,
+            author: "alice",
+            message: "Edit anchor",
+        }
+    }
+
+    #[test]
+    fn loads_the_checked_out_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo(dir.path());
+        let content = content(dir.path(), None);
+        assert_eq!(content.describe(), "branch main");
+        assert_eq!(content.read_only(), None);
+
+        let snapshot = content.load().unwrap();
+        assert_eq!(snapshot.seed, json!({"version": "v1"}));
+        let head = repo.head().unwrap().peel_to_commit().unwrap().id();
+        assert_eq!(snapshot.revision, Some(head.to_string()));
+    }
+
+    #[test]
+    fn loads_a_pinned_commit_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo(dir.path());
+        let pinned = repo.head().unwrap().peel_to_commit().unwrap().id();
+        commit_file(&repo, SEED, &json!({"version": "v2"}).to_string(), "Bump");
+
+        let content = content(dir.path(), Some(&pinned.to_string()));
+        assert_eq!(content.load().unwrap().seed, json!({"version": "v1"}));
+        assert!(content.read_only().is_some());
+        let seed = json!({"version": "v3"});
+        assert!(content.apply(&mutation(&seed, None)).is_err());
+    }
+
+    #[test]
+    fn apply_commits_as_the_editor() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo(dir.path());
+        let content = content(dir.path(), Some("main"));
+        let base = content.load().unwrap().revision.unwrap();
+
+        let seed = json!({"version": "v2"});
+        let id = content
+            .apply(&mutation(&seed, Some(&base)))
+            .unwrap()
+            .unwrap();
+
+        let commit = repo.find_commit(Oid::from_str(&id).unwrap()).unwrap();
+        assert_eq!(commit.author().name(), Some("alice"));
+        assert_eq!(commit.author().email(), Some("alice@editors.invalid"));
+        assert_eq!(commit.committer().name(), Some("Maintainer"));
+        assert_eq!(commit.summary(), Some("Edit anchor"));
+        assert_eq!(content.load().unwrap().seed, seed);
+        // The branch is checked out, so the working tree follows.
+        let on_disk = std::fs::read(dir.path().join(SEED)).unwrap();
+        assert_eq!(serde_json::from_slice::<Value>(&on_disk).unwrap(), seed);
+    }
+
+    #[test]
+    fn apply_rejects_a_stale_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo(dir.path());
+        let content = content(dir.path(), None);
+        let stale = content.load().unwrap().revision.unwrap();
+        let tip = commit_file(&repo, SEED, &json!({"version": "v2"}).to_string(), "Bump");
+
+        let seed = json!({"version": "v3"});
+        assert!(content.apply(&mutation(&seed, Some(&stale))).is_err());
+        assert_eq!(content.head().unwrap(), tip);
+        assert_eq!(content.load().unwrap().seed, json!({"version": "v2"}));
+    }
+
+    #[test]
+    fn log_lists_commits_touching_the_seed() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = repo(dir.path());
+        commit_file(&repo, SEED, &json!({"version": "v2"}).to_string(), "Bump");
+        commit_file(&repo, "README.md", "more\n", "Edit the readme");
+
+        let log = content(dir.path(), None).log(HISTORY_LIMIT).unwrap();
+        let summaries: Vec<&str> = log.iter().map(|entry| entry.summary.as_str()).collect();
+        assert_eq!(summaries, ["Bump", "Add the seed"]);
+        assert_eq!(log[0].author, "Maintainer");
+
+        let log = content(dir.path(), None).log(1).unwrap();
+        assert_eq!(log.len(), 1);
+    }
+}
@@ -0,0 +1,82 @@
+use actix_web::{
+    http::{header, StatusCode},
+    web, HttpResponse,
+};
+use serde_json::{json, Value};
+use std::time::SystemTime;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::{Site, SiteState};
+
+const PROBE_CACHE_CONTROL: &str = "no-store";
+
+fn rfc3339(time: SystemTime) -> Option<String> {
+    OffsetDateTime::from(time).format(&Rfc3339).ok()
+}
+
+/// Probe responses skip `is_allowed_host` so the orchestrator can reach them
+/// by pod IP, and are never cached.
+fn probe(status: StatusCode, body: Value) -> HttpResponse {
+    HttpResponse::build(status)
+        .append_header((header::CACHE_CONTROL, PROBE_CACHE_CONTROL))
+        .json(body)
+}
+
+/// Liveness: the process is up and answering.
+pub(crate) async fn healthz() -> HttpResponse {
+    probe(StatusCode::OK, json!({ "status": "ok" }))
+}
+
+/// Readiness: succeeds once content and templates have been loaded and
+/// the pages pre-rendered.
+pub(crate) async fn readyz(state: web::Data<SiteState>) -> HttpResponse {
+    match state.snapshot() {
+        Some(site) => probe(
+            StatusCode::OK,
+            json!({
+                "status": "ready",
+                "pages": site.pages.len(),
+                "templates": site.hb.get_templates().len(),
+            }),
+        ),
+        None => probe(
+            StatusCode::SERVICE_UNAVAILABLE,
+            json!({ "status": "loading" }),
+        ),
+    }
+}
+
+fn counts(site: &Site) -> Value {
+    let rustdev = &site.rustdev;
+    json!({
+        "ecosystems": rustdev.ecosystems.len(),
+        "tools": rustdev.tools.len(),
+        "events": rustdev.events.len(),
+        "learning_paths": rustdev.learning_paths.len(),
+        "creators": rustdev.creators.len(),
+        "posts": rustdev.posts.len(),
+        "resources": rustdev.resources.len(),
+        "jobs": rustdev.jobs.len(),
+        "labels": rustdev.labels.len(),
+        "pages": site.pages.len(),
+        "assets": site.assets.len(),
+    })
+}
+
+/// Build and content info: crate version, seed version and generation date,
+/// per-collection counts and when the content was last (re)loaded.
+pub(crate) async fn version(state: web::Data<SiteState>) -> HttpResponse {
+    let mut body = json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+    });
+    if let Some(site) = state.snapshot() {
+        body["seed"] = json!({
+            "version": site.rustdev.seed_version,
+            "generated_at": site.rustdev.generated_at,
+        });
+        body["counts"] = counts(&site);
+        body["last_reload"] = json!(rfc3339(site.loaded_at));
+    }
+    probe(StatusCode::OK, body)
+}
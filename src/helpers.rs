@@ -0,0 +1,635 @@
+use handlebars::{
+    handlebars_helper, Context, Handlebars, Helper, HelperDef, JsonValue, RenderContext,
+    RenderError, ScopedJson,
+};
+use pulldown_cmark::{html, Event, Options, Parser, Tag};
+use serde_json::Value;
+use std::{cell::Cell, collections::HashMap};
+use time::{Date, Month};
+
+use crate::{
+    assets::AssetManifest,
+    images::{self, AllowedHosts},
+    Label,
+};
+
+const MONTHS_SHORT: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+const MONTHS_LONG: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Registers every template helper on `hb`.
+///
+/// `labels` backs `label_name`; `site_url` is the origin `asset_url` prefixes
+/// to site-relative paths, and `assets` supplies their fingerprinted names.
+/// `image_hosts` decides which URLs `image_url` sends through `/img`, and
+/// `relative_time` counts from `today`.
+pub(crate) fn register(
+    hb: &mut Handlebars<'_>,
+    labels: &[Label],
+    site_url: &str,
+    assets: &AssetManifest,
+    image_hosts: &AllowedHosts,
+    today: Date,
+) {
+    handlebars_helper!(eq: |a: JsonValue, b: JsonValue| a == b);
+    hb.register_helper("eq", Box::new(eq));
+
+    hb.register_helper("format_date", Box::new(format_date_helper));
+    hb.register_helper("relative_time", Box::new(RelativeTime { today }));
+    hb.register_helper("markdown", Box::new(markdown_helper));
+    hb.register_helper("truncate", Box::new(truncate_helper));
+    hb.register_helper("join", Box::new(join_helper));
+    hb.register_helper("pluralize", Box::new(pluralize_helper));
+    hb.register_helper("json", Box::new(json_helper));
+    hb.register_helper("label_name", Box::new(LabelName::new(labels)));
+    hb.register_helper("asset_url", Box::new(AssetUrl::new(site_url, assets)));
+    hb.register_helper(
+        "image_url",
+        Box::new(ImageUrl {
+            allowed: image_hosts.clone(),
+            assets: AssetUrl::new(site_url, assets),
+        }),
+    );
+}
+
+fn value_as_str(value: &Value) -> Option<&str> {
+    value.as_str().map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// Parses the seed's date strings: `YYYY-MM-DD`, optionally followed by a
+/// time part (`2026-01-03T10:00:00Z`), which is ignored.
+pub(crate) fn parse_date(input: &str) -> Option<Date> {
+    let date_part = input.trim().get(..10)?;
+    let mut parts = date_part.split('-');
+    let year: i32 = parts.next()?.parse().ok()?;
+    let month: u8 = parts.next()?.parse().ok()?;
+    let day: u8 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()
+}
+
+/// Formats a date using one of the named styles: `short` (`Jan 3, 2026`),
+/// `long` (`January 3, 2026`), `month` (`January 2026`) or `iso`.
+pub(crate) fn format_date(date: Date, style: &str) -> String {
+    let month_idx = u8::from(date.month()) as usize - 1;
+    match style {
+        "iso" => format!(
+            "{:04}-{:02}-{:02}",
+            date.year(),
+            u8::from(date.month()),
+            date.day()
+        ),
+        "long" => format!("{} {}, {}", MONTHS_LONG[month_idx], date.day(), date.year()),
+        "month" => format!("{} {}", MONTHS_LONG[month_idx], date.year()),
+        _ => format!(
+            "{} {}, {}",
+            MONTHS_SHORT[month_idx],
+            date.day(),
+            date.year()
+        ),
+    }
+}
+
+/// Describes `date` relative to `today` in whole days, weeks, months or years.
+pub(crate) fn relative_time(date: Date, today: Date) -> String {
+    let days = (date - today).whole_days();
+    if days == 0 {
+        return "today".to_string();
+    }
+    if days == 1 {
+        return "tomorrow".to_string();
+    }
+    if days == -1 {
+        return "yesterday".to_string();
+    }
+
+    let abs = days.unsigned_abs();
+    let (amount, unit) = if abs < 14 {
+        (abs, "day")
+    } else if abs < 60 {
+        (abs / 7, "week")
+    } else if abs < 365 {
+        (abs / 30, "month")
+    } else {
+        (abs / 365, "year")
+    };
+    let phrase = pluralize(amount, unit, None);
+    if days > 0 {
+        format!("in {phrase}")
+    } else {
+        format!("{phrase} ago")
+    }
+}
+
+/// Whether a link or image destination is safe to emit: http(s), mailto,
+/// relative and fragment URLs. Browsers ignore whitespace and control
+/// characters in a scheme, so they are dropped before it is compared.
+fn is_safe_url(url: &str) -> bool {
+    let Some(colon) = url.find(':') else {
+        return true;
+    };
+    if url[..colon].contains(['/', '?', '#']) {
+        return true;
+    }
+    let scheme: String = url[..colon]
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    matches!(scheme.as_str(), "http" | "https" | "mailto")
+}
+
+/// Renders Markdown to HTML. Raw HTML in the source is escaped rather than
+/// passed through, so seed content cannot inject markup, and links and
+/// images with any other scheme than those `is_safe_url` allows lose their
+/// destination.
+pub(crate) fn markdown_to_html(input: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let parser = Parser::new_ext(input, options).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: "#".into(),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: "".into(),
+            title,
+            id,
+        }),
+        other => other,
+    });
+
+    let mut out = String::with_capacity(input.len() * 3 / 2);
+    html::push_html(&mut out, parser);
+    out
+}
+
+/// Shortens `input` to at most `max` characters, cutting at the last word
+/// boundary and appending `suffix` when anything was removed.
+pub(crate) fn truncate(input: &str, max: usize, suffix: &str) -> String {
+    let trimmed = input.trim();
+    if trimmed.chars().count() <= max {
+        return trimmed.to_string();
+    }
+
+    let keep = max.saturating_sub(suffix.chars().count());
+    let cut = trimmed
+        .char_indices()
+        .nth(keep)
+        .map(|(idx, _)| idx)
+        .unwrap_or(trimmed.len());
+    let head = &trimmed[..cut];
+    let head = match head.rfind(char::is_whitespace) {
+        Some(idx) if idx > 0 => &head[..idx],
+        _ => head,
+    };
+    format!(
+        "{}{}",
+        head.trim_end_matches(|c: char| c.is_whitespace() || c == ',' || c == '.'),
+        suffix
+    )
+}
+
+/// Returns `"{count} {word}"`, picking the plural form when `count != 1`.
+/// Without an explicit `plural`, an `s` (or `es`) suffix is used.
+pub(crate) fn pluralize(count: u64, singular: &str, plural: Option<&str>) -> String {
+    if count == 1 {
+        return format!("{count} {singular}");
+    }
+    let plural = match plural {
+        Some(p) => p.to_string(),
+        None if singular.ends_with('s')
+            || singular.ends_with('x')
+            || singular.ends_with("ch")
+            || singular.ends_with("sh") =>
+        {
+            format!("{singular}es")
+        }
+        None => format!("{singular}s"),
+    };
+    format!("{count} {plural}")
+}
+
+fn value_to_display(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn count_of(value: &Value) -> u64 {
+    match value {
+        Value::Number(n) => n.as_u64().unwrap_or(0),
+        Value::Array(items) => items.len() as u64,
+        Value::String(s) => s.trim().parse().unwrap_or(0),
+        _ => 0,
+    }
+}
+
+// `{{format_date published_on}}`, `{{format_date starts_on format="long"}}`.
+// Unparseable input is echoed back unchanged and missing input renders empty.
+handlebars_helper!(format_date_helper: |value: Json, {format: str = "short"}| {
+    match value_as_str(value) {
+        Some(raw) => parse_date(raw)
+            .map(|date| format_date(date, format))
+            .unwrap_or_else(|| raw.to_string()),
+        None => String::new(),
+    }
+});
+
+// `{{{markdown body_md}}}`; use the triple-stash, the output is HTML.
+handlebars_helper!(markdown_helper: |value: Json| {
+    value.as_str().map(markdown_to_html).unwrap_or_default()
+});
+
+// `{{truncate deck 120}}` or `{{truncate deck 120 suffix="..."}}`.
+handlebars_helper!(truncate_helper: |value: Json, max: u64, {suffix: str = "…"}| {
+    value
+        .as_str()
+        .map(|raw| truncate(raw, max as usize, suffix))
+        .unwrap_or_default()
+});
+
+// `{{join tags}}` or `{{join tags " / "}}`; the separator defaults to `", "`.
+handlebars_helper!(join_helper: |items: Json, *args| {
+    let sep = args.get(1).and_then(|v| v.as_str()).unwrap_or(", ");
+    items
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(value_to_display)
+                .collect::<Vec<_>>()
+                .join(sep)
+        })
+        .unwrap_or_default()
+});
+
+// `{{pluralize duration_hours "hour"}}`, `{{pluralize n "entry" plural="entries"}}`.
+// An array as the count uses its length.
+handlebars_helper!(pluralize_helper: |count: Json, singular: str, {plural: str = ""}| {
+    let plural = Some(plural).filter(|p| !p.is_empty());
+    pluralize(count_of(count), singular, plural)
+});
+
+// `{{{json value}}}` serializes a value for inline `<script>` blocks. `</` is
+// escaped so the payload cannot close the surrounding script element.
+handlebars_helper!(json_helper: |value: Json, {pretty: bool = false}| {
+    let serialized = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    serialized.unwrap_or_default().replace("</", "<\\/")
+});
+
+/// `{{label_name primary_label}}` resolves a taxonomy slug to its display
+/// name, falling back to the slug itself.
+pub(crate) struct LabelName {
+    names: HashMap<String, String>,
+}
+
+impl LabelName {
+    pub(crate) fn new(labels: &[Label]) -> Self {
+        Self {
+            names: labels
+                .iter()
+                .map(|label| (label.slug.clone(), label.name.clone()))
+                .collect(),
+        }
+    }
+}
+
+impl HelperDef for LabelName {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let name = h
+            .param(0)
+            .and_then(|p| value_as_str(p.value()))
+            .map(|slug| {
+                self.names
+                    .get(slug)
+                    .cloned()
+                    .unwrap_or_else(|| slug.to_string())
+            })
+            .unwrap_or_default();
+        Ok(ScopedJson::Derived(JsonValue::String(name)))
+    }
+}
+
+thread_local! {
+    /// Set when a helper renders something that depends on the current date.
+    static READ_TODAY: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Runs `render` and reports whether what it rendered depends on the date
+/// the helpers were registered with, so it goes stale when the day changes.
+pub(crate) fn reads_today<T>(render: impl FnOnce() -> T) -> (T, bool) {
+    let outer = READ_TODAY.with(|read| read.replace(false));
+    let rendered = render();
+    let read = READ_TODAY.with(|read| read.replace(outer || read.get()));
+    (rendered, read)
+}
+
+/// `{{relative_time last_verified}}` renders e.g. `3 weeks ago` or
+/// `in 2 days`, counting from the day the templates were built for.
+pub(crate) struct RelativeTime {
+    today: Date,
+}
+
+impl HelperDef for RelativeTime {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let text = match h.param(0).and_then(|p| value_as_str(p.value())) {
+            Some(raw) => match parse_date(raw) {
+                Some(date) => {
+                    READ_TODAY.with(|read| read.set(true));
+                    relative_time(date, self.today)
+                }
+                None => raw.to_string(),
+            },
+            None => String::new(),
+        };
+        Ok(ScopedJson::Derived(JsonValue::String(text)))
+    }
+}
+
+/// `{{asset_url media.background_url}}` turns site-relative paths into
+/// absolute URLs on the configured origin. Files in the asset directory get
+/// their fingerprinted name. Absolute URLs pass through.
+pub(crate) struct AssetUrl {
+    base: String,
+    assets: AssetManifest,
+}
+
+impl AssetUrl {
+    pub(crate) fn new(site_url: &str, assets: &AssetManifest) -> Self {
+        Self {
+            base: site_url.trim_end_matches('/').to_string(),
+            assets: assets.clone(),
+        }
+    }
+
+    pub(crate) fn resolve(&self, path: &str) -> String {
+        let path = path.trim();
+        if path.starts_with("http://") || path.starts_with("https://") || path.starts_with("//") {
+            return path.to_string();
+        }
+        if let Some(fingerprinted) = self.assets.url(path) {
+            return format!("{}{}", self.base, fingerprinted);
+        }
+        if path.starts_with('/') {
+            format!("{}{}", self.base, path)
+        } else {
+            format!("{}/{}", self.base, path)
+        }
+    }
+}
+
+impl HelperDef for AssetUrl {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let url = h
+            .param(0)
+            .and_then(|p| value_as_str(p.value()))
+            .map(|path| self.resolve(path))
+            .unwrap_or_default();
+        Ok(ScopedJson::Derived(JsonValue::String(url)))
+    }
+}
+
+/// `{{image_url media.logo_url w=64}}` routes images on allowed third-party
+/// origins through the `/img` proxy at the given width. Anything else
+/// resolves like `asset_url`.
+pub(crate) struct ImageUrl {
+    allowed: AllowedHosts,
+    assets: AssetUrl,
+}
+
+impl HelperDef for ImageUrl {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'reg, 'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'reg, 'rc>, RenderError> {
+        let width = h.hash_get("w").and_then(|w| w.value().as_u64());
+        let url = h
+            .param(0)
+            .and_then(|p| value_as_str(p.value()))
+            .map(|src| {
+                images::proxy_url(&self.allowed, src, width)
+                    .unwrap_or_else(|| self.assets.resolve(src))
+            })
+            .unwrap_or_default();
+        Ok(ScopedJson::Derived(JsonValue::String(url)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: Month, day: u8) -> Date {
+        Date::from_calendar_date(year, month, day).unwrap()
+    }
+
+    fn render(template: &str, data: Value) -> String {
+        let mut hb = Handlebars::new();
+        register(
+            &mut hb,
+            &[],
+            "https://rust.dev",
+            &AssetManifest::default(),
+            &AllowedHosts::new(images::DEFAULT_ALLOWED_HOSTS),
+            date(2026, Month::June, 15),
+        );
+        hb.render_template(template, &data).unwrap()
+    }
+
+    #[test]
+    fn parses_dates_with_and_without_a_time() {
+        assert_eq!(
+            parse_date("2026-01-03"),
+            Some(date(2026, Month::January, 3))
+        );
+        assert_eq!(
+            parse_date(" 2026-01-03T10:00:00Z "),
+            Some(date(2026, Month::January, 3))
+        );
+        assert_eq!(parse_date("2026-02-30"), None);
+        assert_eq!(parse_date("2026-1-3"), None);
+        assert_eq!(parse_date("soon"), None);
+    }
+
+    #[test]
+    fn formats_dates_in_each_style() {
+        let day = date(2026, Month::January, 3);
+        assert_eq!(format_date(day, "short"), "Jan 3, 2026");
+        assert_eq!(format_date(day, "long"), "January 3, 2026");
+        assert_eq!(format_date(day, "month"), "January 2026");
+        assert_eq!(format_date(day, "iso"), "2026-01-03");
+        assert_eq!(format_date(day, "unknown"), "Jan 3, 2026");
+    }
+
+    #[test]
+    fn format_date_helper_echoes_what_it_cannot_parse() {
+        let data = serde_json::json!({ "on": "2026-03-14", "tba": "TBA" });
+        assert_eq!(
+            render(r#"{{format_date on format="long"}}"#, data.clone()),
+            "March 14, 2026"
+        );
+        assert_eq!(render("{{format_date tba}}", data.clone()), "TBA");
+        assert_eq!(render("{{format_date missing}}", data), "");
+    }
+
+    #[test]
+    fn describes_relative_time() {
+        let today = date(2026, Month::June, 15);
+        let cases = [
+            (date(2026, Month::June, 15), "today"),
+            (date(2026, Month::June, 16), "tomorrow"),
+            (date(2026, Month::June, 14), "yesterday"),
+            (date(2026, Month::June, 20), "in 5 days"),
+            (date(2026, Month::June, 1), "2 weeks ago"),
+            (date(2026, Month::September, 15), "in 3 months"),
+            (date(2025, Month::June, 15), "1 year ago"),
+        ];
+        for (day, expected) in cases {
+            assert_eq!(relative_time(day, today), expected, "{day}");
+        }
+    }
+
+    #[test]
+    fn relative_time_helper_reports_reading_today() {
+        let data = serde_json::json!({"verified": "2026-06-01", "name": "Anchor"});
+        let (text, read) = reads_today(|| render("{{relative_time verified}}", data.clone()));
+        assert_eq!((text.as_str(), read), ("2 weeks ago", true));
+        let (text, read) = reads_today(|| render("{{name}}", data));
+        assert_eq!((text.as_str(), read), ("Anchor", false));
+    }
+
+    #[test]
+    fn truncates_multibyte_text_on_char_boundaries() {
+        assert_eq!(truncate("  short  ", 20, "…"), "short");
+        assert_eq!(
+            truncate("Rust für Einsteiger und Fortgeschrittene", 22, "…"),
+            "Rust für Einsteiger…"
+        );
+        // No whitespace to cut at: the cut falls between characters.
+        assert_eq!(truncate("日本語のテキストです", 5, "…"), "日本語の…");
+        assert_eq!(truncate("ÅÅÅÅÅÅ", 4, "..."), "Å...");
+    }
+
+    #[test]
+    fn pluralizes() {
+        assert_eq!(pluralize(1, "hour", None), "1 hour");
+        assert_eq!(pluralize(0, "hour", None), "0 hours");
+        assert_eq!(pluralize(2, "match", None), "2 matches");
+        assert_eq!(pluralize(3, "box", None), "3 boxes");
+        assert_eq!(pluralize(2, "entry", Some("entries")), "2 entries");
+        let data = serde_json::json!({ "tags": ["a", "b"], "n": "1" });
+        assert_eq!(
+            render(r#"{{pluralize tags "tag"}}"#, data.clone()),
+            "2 tags"
+        );
+        assert_eq!(
+            render(r#"{{pluralize n "entry" plural="entries"}}"#, data),
+            "1 entry"
+        );
+    }
+
+    #[test]
+    fn markdown_escapes_raw_html() {
+        let html = markdown_to_html("Hi <script>alert(1)</script> **there**");
+        assert!(!html.contains("<script>"), "{html}");
+        assert!(html.contains("&lt;script&gt;"), "{html}");
+        assert!(html.contains("<strong>there</strong>"), "{html}");
+
+        let block = markdown_to_html("<div onclick=\"x()\">\nhi\n</div>\n");
+        assert!(!block.contains("<div"), "{block}");
+    }
+
+    #[test]
+    fn markdown_drops_script_urls() {
+        for url in [
+            "javascript:alert(1)",
+            "JavaScript:alert(1)",
+            "java\tscript:alert(1)",
+            "javascript&#58;alert(1)",
+            "data:text/html;base64,PHNjcmlwdD4=",
+            "vbscript:msgbox(1)",
+        ] {
+            let link = markdown_to_html(&format!("[x](<{url}>)"));
+            assert!(link.contains(r##"<a href="#">x</a>"##), "{url}: {link}");
+            let image = markdown_to_html(&format!("![x](<{url}>)"));
+            assert!(image.contains(r#"<img src="""#), "{url}: {image}");
+        }
+    }
+
+    #[test]
+    fn markdown_keeps_web_mail_relative_and_fragment_urls() {
+        for url in [
+            "https://rust.dev/tools",
+            "http://example.com/a:b",
+            "mailto:team@rust.dev",
+            "/tools/anchor",
+            "anchor?tab=a:b",
+            "#install",
+        ] {
+            let link = markdown_to_html(&format!("[x]({url})"));
+            assert!(link.contains(&format!(r#"href="{url}""#)), "{url}: {link}");
+        }
+    }
+}
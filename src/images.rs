@@ -0,0 +1,663 @@
+use actix_web::{
+    http::header::{self, HeaderMap},
+    web, HttpRequest, HttpResponse,
+};
+use image::{
+    codecs::{jpeg::JpegEncoder, webp::WebPEncoder},
+    imageops::FilterType,
+    DynamicImage, ExtendedColorType, ImageFormat, ImageReader, Limits,
+};
+use serde::Deserialize;
+use std::{
+    fs,
+    io::{self, Cursor},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use url::Url;
+
+use crate::{
+    conditional::content_hash, conditional::Validators, is_allowed_host, logging, metrics,
+};
+
+/// Origins third-party media is fetched from when `IMG_ALLOWED_HOSTS` is unset.
+pub(crate) const DEFAULT_ALLOWED_HOSTS: [&str; 8] = [
+    "logo.clearbit.com",
+    "image.thum.io",
+    "github.com",
+    "avatars.githubusercontent.com",
+    "opengraph.githubassets.com",
+    "i.ytimg.com",
+    "yt3.googleusercontent.com",
+    "placehold.co",
+];
+pub(crate) const DEFAULT_CACHE_DIR: &str = "cache/img";
+pub(crate) const DEFAULT_CACHE_MAX_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Requested widths are rounded up to one of these so the disk cache holds a
+/// bounded number of variants per source.
+const WIDTHS: [u32; 10] = [32, 64, 96, 128, 192, 256, 384, 512, 1024, 1600];
+const MAX_SOURCE_BYTES: usize = 8 * 1024 * 1024;
+const MAX_SOURCE_DIMENSION: u32 = 8192;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_REDIRECTS: usize = 5;
+const JPEG_QUALITY: u8 = 82;
+const IMAGE_CACHE_CONTROL: &str = "public, max-age=604800";
+const PLACEHOLDER_CACHE_CONTROL: &str = "public, max-age=60";
+
+/// Output encodings. WebP can only be encoded losslessly, which suits
+/// logos and screenshots but bloats photos, so lossy sources are always
+/// re-encoded as JPEG.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Output {
+    WebP,
+    Jpeg,
+}
+
+impl Output {
+    /// WebP for lossless sources when the client accepts it, JPEG otherwise.
+    fn for_source(format: Option<ImageFormat>, accepts_webp: bool) -> Self {
+        let lossless = matches!(format, Some(ImageFormat::Png | ImageFormat::Gif));
+        if lossless && accepts_webp {
+            Output::WebP
+        } else {
+            Output::Jpeg
+        }
+    }
+
+    /// The encoding of a cached body.
+    fn of(bytes: &[u8]) -> Self {
+        if matches!(image::guess_format(bytes), Ok(ImageFormat::WebP)) {
+            Output::WebP
+        } else {
+            Output::Jpeg
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Output::WebP => "image/webp",
+            Output::Jpeg => "image/jpeg",
+        }
+    }
+}
+
+fn accepts_webp(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("image/webp"))
+}
+
+#[derive(Debug, Deserialize)]
+struct ImageParams {
+    src: String,
+    w: Option<u32>,
+}
+
+/// Origins media may be fetched from. Entries match a URL's host, or its
+/// `host:port` for non-default ports.
+#[derive(Clone, Debug)]
+pub(crate) struct AllowedHosts(Arc<[String]>);
+
+impl AllowedHosts {
+    pub(crate) fn new<I, S>(hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self(
+            hosts
+                .into_iter()
+                .map(|host| host.as_ref().trim().to_ascii_lowercase())
+                .filter(|host| !host.is_empty())
+                .collect(),
+        )
+    }
+
+    pub(crate) fn allows(&self, url: &Url) -> bool {
+        if !matches!(url.scheme(), "http" | "https") {
+            return false;
+        }
+        let Some(host) = url.host_str().map(str::to_ascii_lowercase) else {
+            return false;
+        };
+        let with_port = url.port().map(|port| format!("{host}:{port}"));
+        self.0
+            .iter()
+            .any(|allowed| *allowed == host || Some(allowed) == with_port.as_ref())
+    }
+}
+
+/// `/img` fetches allowlisted third-party images, resizes and re-encodes
+/// them, and keeps the results in a size-capped disk cache.
+pub(crate) struct ImageProxy {
+    client: reqwest::Client,
+    allowed: AllowedHosts,
+    cache_dir: PathBuf,
+    max_cache_bytes: u64,
+}
+
+impl ImageProxy {
+    pub(crate) fn new(
+        allowed: AllowedHosts,
+        cache_dir: PathBuf,
+        max_cache_bytes: u64,
+    ) -> io::Result<Self> {
+        let redirect_hosts = allowed.clone();
+        let client = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+                if attempt.previous().len() < MAX_REDIRECTS && redirect_hosts.allows(attempt.url())
+                {
+                    attempt.follow()
+                } else {
+                    attempt.stop()
+                }
+            }))
+            .build()
+            .map_err(io::Error::other)?;
+        fs::create_dir_all(&cache_dir)?;
+        Ok(Self {
+            client,
+            allowed,
+            cache_dir,
+            max_cache_bytes,
+        })
+    }
+
+    fn cache_path(&self, src: &Url, width: Option<u32>, accepts_webp: bool) -> PathBuf {
+        let key = format!("{src}\n{}\n{accepts_webp}", width.unwrap_or(0));
+        self.cache_dir
+            .join(format!("{}.img", content_hash(key.as_bytes(), 32)))
+    }
+
+    async fn fetch(&self, src: &Url) -> Result<Vec<u8>, String> {
+        let mut response = self
+            .client
+            .get(src.clone())
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("origin answered {}", response.status()));
+        }
+        if response.content_length().unwrap_or(0) > MAX_SOURCE_BYTES as u64 {
+            return Err("source image too large".to_string());
+        }
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|err| err.to_string())? {
+            if body.len() + chunk.len() > MAX_SOURCE_BYTES {
+                return Err("source image too large".to_string());
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok(body)
+    }
+
+    /// The processed image and its encoding, from the disk cache or freshly
+    /// fetched.
+    async fn load(
+        &self,
+        src: &Url,
+        width: Option<u32>,
+        accepts_webp: bool,
+    ) -> Result<(Vec<u8>, Output), String> {
+        let path = self.cache_path(src, width, accepts_webp);
+        let cached = path.clone();
+        if let Ok(Ok(bytes)) = web::block(move || read_cached(&cached)).await {
+            metrics::cache_lookup("images", true);
+            let output = Output::of(&bytes);
+            return Ok((bytes, output));
+        }
+        metrics::cache_lookup("images", false);
+
+        let source = self.fetch(src).await?;
+        let cache_dir = self.cache_dir.clone();
+        let max_cache_bytes = self.max_cache_bytes;
+        web::block(move || {
+            let (bytes, output) = transform(&source, width, accepts_webp)?;
+            if let Err(err) = store(&cache_dir, &path, &bytes, max_cache_bytes) {
+                tracing::warn!(path = %path.display(), %err, "image cache write failed");
+            }
+            Ok((bytes, output))
+        })
+        .await
+        .map_err(|err| err.to_string())?
+    }
+}
+
+/// Rounds a requested width up to the nearest bucket.
+fn bucket_width(requested: u32) -> u32 {
+    WIDTHS
+        .iter()
+        .copied()
+        .find(|width| *width >= requested)
+        .unwrap_or(WIDTHS[WIDTHS.len() - 1])
+}
+
+/// Decodes `source`, shrinks it to `width` (never enlarging) and encodes it.
+fn transform(
+    source: &[u8],
+    width: Option<u32>,
+    accepts_webp: bool,
+) -> Result<(Vec<u8>, Output), String> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+    let mut reader = ImageReader::new(Cursor::new(source))
+        .with_guessed_format()
+        .map_err(|err| err.to_string())?;
+    reader.limits(limits);
+    let output = Output::for_source(reader.format(), accepts_webp);
+    let mut image = reader.decode().map_err(|err| err.to_string())?;
+
+    let target = width.unwrap_or(WIDTHS[WIDTHS.len() - 1]);
+    if image.width() > target {
+        image = image.resize(target, u32::MAX, FilterType::CatmullRom);
+    }
+
+    let mut out = Vec::new();
+    match output {
+        Output::WebP => {
+            let rgba = image.to_rgba8();
+            WebPEncoder::new_lossless(&mut out)
+                .encode(
+                    rgba.as_raw(),
+                    rgba.width(),
+                    rgba.height(),
+                    ExtendedColorType::Rgba8,
+                )
+                .map_err(|err| err.to_string())?;
+        }
+        Output::Jpeg => {
+            let rgb = flatten(&image);
+            JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY)
+                .encode_image(&rgb)
+                .map_err(|err| err.to_string())?;
+        }
+    }
+    Ok((out, output))
+}
+
+/// JPEG has no alpha channel; transparent pixels are composited onto white.
+fn flatten(image: &DynamicImage) -> DynamicImage {
+    if !image.color().has_alpha() {
+        return DynamicImage::ImageRgb8(image.to_rgb8());
+    }
+    let rgba = image.to_rgba8();
+    let rgb = image::RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let blend = |c: u8| ((c as u32 * a as u32 + 255 * (255 - a as u32)) / 255) as u8;
+        image::Rgb([blend(r), blend(g), blend(b)])
+    });
+    DynamicImage::ImageRgb8(rgb)
+}
+
+/// Reads a cache entry and marks it as just used, since eviction goes by
+/// modification time.
+fn read_cached(path: &Path) -> io::Result<Vec<u8>> {
+    let bytes = fs::read(path)?;
+    if let Err(err) = fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::now()))
+    {
+        tracing::debug!(path = %path.display(), %err, "image cache touch failed");
+    }
+    Ok(bytes)
+}
+
+/// Writes `bytes` to `path` via a temporary file of its own, then evicts
+/// the least recently used entries until the cache fits in `max_bytes`.
+fn store(cache_dir: &Path, path: &Path, bytes: &[u8], max_bytes: u64) -> io::Result<()> {
+    let tmp = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4().simple()));
+    fs::write(&tmp, bytes)?;
+    if let Err(err) = fs::rename(&tmp, path) {
+        let _ = fs::remove_file(&tmp);
+        return Err(err);
+    }
+
+    let mut entries = Vec::new();
+    let mut total = 0;
+    for entry in fs::read_dir(cache_dir)? {
+        let entry = entry?;
+        // Other requests' temporary files are still being written.
+        if entry.path().extension().is_some_and(|ext| ext == "tmp") {
+            continue;
+        }
+        // Entries renamed or evicted by a concurrent store since the
+        // listing are gone.
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        total += metadata.len();
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        entries.push((modified, metadata.len(), entry.path()));
+    }
+    if total <= max_bytes {
+        return Ok(());
+    }
+
+    entries.sort_by_key(|(modified, _, _)| *modified);
+    for (_, len, entry) in entries {
+        if total <= max_bytes {
+            break;
+        }
+        if entry != path && fs::remove_file(&entry).is_ok() {
+            total -= len;
+        }
+    }
+    Ok(())
+}
+
+/// A neutral box shown in place of images that could not be fetched or
+/// decoded, so pages never render a broken-image icon.
+fn placeholder(width: Option<u32>) -> HttpResponse {
+    let size = width.unwrap_or(64);
+    let svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 1 1"><rect width="1" height="1" fill="#21262d"/></svg>"##
+    );
+    HttpResponse::Ok()
+        .append_header((header::CONTENT_TYPE, "image/svg+xml"))
+        .append_header((header::CACHE_CONTROL, PLACEHOLDER_CACHE_CONTROL))
+        .body(svg)
+}
+
+fn bad_request(message: impl Into<String>) -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({
+        "error": message.into(),
+        "code": 400,
+    }))
+}
+
+/// `GET /img?src=...&w=...`.
+pub(crate) async fn serve(proxy: web::Data<ImageProxy>, req: HttpRequest) -> HttpResponse {
+    if !is_allowed_host(&req) {
+        return HttpResponse::NotFound().finish();
+    }
+    let params = match web::Query::<ImageParams>::from_query(req.query_string()) {
+        Ok(params) => params.into_inner(),
+        Err(err) => return bad_request(err.to_string()),
+    };
+    let src = match Url::parse(&params.src) {
+        Ok(src) => src,
+        Err(err) => return bad_request(format!("Invalid `src`: {err}")),
+    };
+    if !proxy.allowed.allows(&src) {
+        return bad_request("`src` is not on an allowed image origin");
+    }
+    let width = match params.w {
+        Some(0) => return bad_request("`w` must be positive"),
+        Some(w) => Some(bucket_width(w)),
+        None => None,
+    };
+
+    let (bytes, output) = match proxy.load(&src, width, accepts_webp(req.headers())).await {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            tracing::warn!(
+                request_id = %logging::request_id(&req),
+                %src,
+                %err,
+                "image proxy failed"
+            );
+            return placeholder(width);
+        }
+    };
+
+    let validators = Validators::for_body(&bytes, None);
+    if validators.is_fresh(&req) {
+        return validators.not_modified(IMAGE_CACHE_CONTROL, Some("Accept"));
+    }
+    let mut builder = HttpResponse::Ok();
+    validators.apply(&mut builder);
+    builder
+        .append_header((header::CONTENT_TYPE, output.content_type()))
+        .append_header((header::CACHE_CONTROL, IMAGE_CACHE_CONTROL))
+        .append_header((header::VARY, "Accept"))
+        .body(bytes)
+}
+
+/// The `/img` URL for `src` at `width`, if `src` is on an allowed origin.
+pub(crate) fn proxy_url(allowed: &AllowedHosts, src: &str, width: Option<u64>) -> Option<String> {
+    let url = Url::parse(src.trim()).ok()?;
+    if !allowed.allows(&url) {
+        return None;
+    }
+    let encoded: String = url::form_urlencoded::byte_serialize(url.as_str().as_bytes()).collect();
+    Some(match width {
+        Some(width) => format!("/img?src={encoded}&w={width}"),
+        None => format!("/img?src={encoded}"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        test::{call_service, init_service, read_body, TestRequest},
+        App,
+    };
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    /// An origin on a local port serving a photo, a logo and redirects.
+    /// Counts the requests it answers.
+    struct Origin {
+        port: u16,
+        requests: Arc<AtomicUsize>,
+    }
+
+    impl Origin {
+        fn start() -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let requests = Arc::new(AtomicUsize::new(0));
+            let counter = requests.clone();
+            let photo = encode(DynamicImage::new_rgb8(200, 100), ImageFormat::Jpeg);
+            let logo = encode(DynamicImage::new_rgba8(100, 100), ImageFormat::Png);
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut request_line = String::new();
+                    reader.read_line(&mut request_line).unwrap();
+                    let mut line = String::new();
+                    while reader.read_line(&mut line).unwrap() > 2 {
+                        line.clear();
+                    }
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    let path = request_line.split(' ').nth(1).unwrap_or_default();
+                    let (status, headers, body) = match path {
+                        "/photo.jpg" => ("200 OK", "Content-Type: image/jpeg\r\n".into(), &photo),
+                        "/logo.png" => ("200 OK", "Content-Type: image/png\r\n".into(), &logo),
+                        "/moved" => ("302 Found", "Location: /photo.jpg\r\n".into(), &Vec::new()),
+                        "/elsewhere" => (
+                            "302 Found",
+                            format!("Location: http://localhost:{port}/photo.jpg\r\n"),
+                            &Vec::new(),
+                        ),
+                        _ => ("404 Not Found", String::new(), &Vec::new()),
+                    };
+                    let head = format!(
+                        "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    let _ = stream.write_all(head.as_bytes());
+                    let _ = stream.write_all(body);
+                }
+            });
+            Self { port, requests }
+        }
+
+        fn url(&self, path: &str) -> String {
+            format!("http://127.0.0.1:{}{path}", self.port)
+        }
+
+        fn requests(&self) -> usize {
+            self.requests.load(Ordering::SeqCst)
+        }
+    }
+
+    fn encode(image: DynamicImage, format: ImageFormat) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, format).unwrap();
+        bytes.into_inner()
+    }
+
+    /// Requests `/img` for `src` through `proxy`, returning the response's
+    /// content type, or its status for a client error, and its body.
+    async fn get(
+        proxy: &web::Data<ImageProxy>,
+        src: &str,
+        query: &str,
+        accept: &str,
+    ) -> (String, Vec<u8>) {
+        let app = init_service(
+            App::new()
+                .app_data(proxy.clone())
+                .route("/img", web::get().to(serve)),
+        )
+        .await;
+        let encoded: String = url::form_urlencoded::byte_serialize(src.as_bytes()).collect();
+        let req = TestRequest::get()
+            .uri(&format!("/img?src={encoded}{query}"))
+            .insert_header((header::HOST, "127.0.0.1"))
+            .insert_header((header::ACCEPT, accept))
+            .to_request();
+        let res = call_service(&app, req).await;
+        let content_type = res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        let status = res.status();
+        let body = read_body(res).await.to_vec();
+        if status.is_client_error() {
+            return (status.to_string(), body);
+        }
+        (content_type, body)
+    }
+
+    fn proxy(origin: &Origin, cache_dir: &Path) -> web::Data<ImageProxy> {
+        let allowed = AllowedHosts::new([format!("127.0.0.1:{}", origin.port)]);
+        web::Data::new(ImageProxy::new(allowed, cache_dir.into(), DEFAULT_CACHE_MAX_BYTES).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn resizes_photos_as_jpeg_and_serves_repeats_from_the_cache() {
+        let origin = Origin::start();
+        let dir = tempfile::tempdir().unwrap();
+        let proxy = proxy(&origin, dir.path());
+        let src = origin.url("/photo.jpg");
+
+        let (content_type, body) = get(&proxy, &src, "&w=60", "image/webp,*/*").await;
+        assert_eq!(content_type, "image/jpeg");
+        let image = image::load_from_memory(&body).unwrap();
+        assert_eq!((image.width(), image.height()), (64, 32));
+
+        let (content_type, cached) = get(&proxy, &src, "&w=60", "image/webp,*/*").await;
+        assert_eq!(content_type, "image/jpeg");
+        assert_eq!(cached, body);
+        assert_eq!(origin.requests(), 1);
+    }
+
+    #[actix_web::test]
+    async fn encodes_lossless_sources_as_webp_when_accepted() {
+        let origin = Origin::start();
+        let dir = tempfile::tempdir().unwrap();
+        let proxy = proxy(&origin, dir.path());
+        let src = origin.url("/logo.png");
+
+        let (content_type, body) = get(&proxy, &src, "&w=64", "image/webp,*/*").await;
+        assert_eq!(content_type, "image/webp");
+        assert_eq!(image::load_from_memory(&body).unwrap().width(), 64);
+        let (content_type, _) = get(&proxy, &src, "&w=64", "*/*").await;
+        assert_eq!(content_type, "image/jpeg");
+        // Each representation is cached under its own key, and hits keep
+        // their encoding.
+        let (content_type, _) = get(&proxy, &src, "&w=64", "image/webp,*/*").await;
+        assert_eq!(content_type, "image/webp");
+        assert_eq!(origin.requests(), 2);
+    }
+
+    #[actix_web::test]
+    async fn refuses_origins_off_the_allowlist() {
+        let origin = Origin::start();
+        let dir = tempfile::tempdir().unwrap();
+        let proxy = proxy(&origin, dir.path());
+        let src = format!("http://localhost:{}/photo.jpg", origin.port);
+
+        let (status, _) = get(&proxy, &src, "", "*/*").await;
+        assert_eq!(status, "400 Bad Request");
+        assert_eq!(origin.requests(), 0);
+    }
+
+    #[actix_web::test]
+    async fn follows_redirects_only_to_allowed_origins() {
+        let origin = Origin::start();
+        let dir = tempfile::tempdir().unwrap();
+        let proxy = proxy(&origin, dir.path());
+
+        let (content_type, _) = get(&proxy, &origin.url("/moved"), "", "*/*").await;
+        assert_eq!(content_type, "image/jpeg");
+        assert_eq!(origin.requests(), 2);
+
+        let (content_type, _) = get(&proxy, &origin.url("/elsewhere"), "", "*/*").await;
+        assert_eq!(content_type, "image/svg+xml");
+        assert_eq!(origin.requests(), 3);
+    }
+
+    #[test]
+    fn eviction_drops_the_least_recently_used_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = |name: &str, age: u64| {
+            let path = dir.path().join(name);
+            fs::write(&path, [0; 10]).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(age))
+                .unwrap();
+            path
+        };
+        let read = entry("read.img", 200);
+        let unread = entry("unread.img", 100);
+
+        read_cached(&read).unwrap();
+        let new = dir.path().join("new.img");
+        store(dir.path(), &new, &[0; 10], 25).unwrap();
+
+        assert!(read.exists());
+        assert!(!unread.exists());
+        assert!(new.exists());
+    }
+
+    #[test]
+    fn concurrent_writes_of_one_entry_do_not_collide() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shared.img");
+        std::thread::scope(|scope| {
+            let writers: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| store(dir.path(), &path, &[1; 1024], u64::MAX)))
+                .collect();
+            for writer in writers {
+                writer.join().unwrap().unwrap();
+            }
+        });
+        assert_eq!(fs::read(&path).unwrap(), [1; 1024]);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
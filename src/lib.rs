@@ -0,0 +1,2259 @@
+#[cfg(not(feature = "rust-dev"))]
+compile_error!("This crate only supports the `rust-dev` feature (enabled by default).");
+
+mod admin;
+mod admin_ui;
+mod api;
+mod assets;
+mod audit;
+mod auth;
+#[doc(hidden)]
+pub mod bench;
+mod cache;
+mod compression;
+mod conditional;
+mod content;
+mod directory;
+mod formats;
+mod git;
+mod health;
+mod helpers;
+mod images;
+mod lint;
+mod logging;
+mod metrics;
+mod migrate;
+mod ratelimit;
+mod schema;
+mod security;
+mod sqlite;
+mod store;
+mod telemetry;
+mod tls;
+
+use actix_web::{
+    http::{header, StatusCode},
+    web, App, HttpRequest, HttpResponse, HttpServer,
+};
+use assets::AssetManifest;
+use cache::{PageCache, PageSource};
+use formats::Format;
+use handlebars::Handlebars;
+use images::{AllowedHosts, ImageProxy};
+use schemars::JsonSchema;
+use security::CspConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    env,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError, RwLock},
+    time::{Duration, SystemTime},
+};
+use store::{ContentStore, StoreVersion};
+use time::{Date, OffsetDateTime};
+use tokio::fs;
+
+const DEFAULT_PORT: u16 = 80;
+const DEFAULT_SITE_URL: &str = "https://rust.dev";
+const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+const HTML_CACHE_CONTROL: &str = "public, max-age=120, stale-while-revalidate=60";
+/// Pages vary by representation and by the precompressed variant picked.
+const PAGE_VARY: &str = "Accept, Accept-Encoding";
+
+const DATA_PATH: &str = "static/rustdev-hub-seed-v3.json";
+const PROMO_PATH: &str = "static/promo.json";
+const DEFAULT_ASSETS_DIR: &str = "static/assets";
+/// Templates of the `/admin` editor, under `static/rustdev/templates/admin`.
+const ADMIN_TEMPLATES: [&str; 9] = [
+    "layout",
+    "login",
+    "index",
+    "collection",
+    "edit",
+    "history",
+    "log",
+    "health",
+    "message",
+];
+/// Largest editor form accepted; long posts exceed the default 16 KiB.
+const ADMIN_FORM_LIMIT: usize = 1 << 20;
+/// How often the content files are checked for changes; `CONTENT_RELOAD_SECS=0`
+/// turns reloading off.
+const DEFAULT_RELOAD_SECS: u64 = 10;
+
+const FALLBACK_ERROR_HTML: &str = r#"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <meta name="viewport" content="width=device-width,initial-scale=1">
+  <title>Error — rust.dev</title>
+  <style>
+    :root { color-scheme: dark; }
+    body { margin: 0; min-height: 100vh; display: grid; place-items: center; background: #0d1117; color: #c9d1d9; font: 14px/1.6 -apple-system, BlinkMacSystemFont, "Segoe UI", Helvetica, Arial, sans-serif; }
+    main { width: min(520px, calc(100% - 40px)); border: 1px solid #30363d; border-radius: 12px; background: #161b22; padding: 22px 24px; }
+    h1 { margin: 0 0 6px; font-size: 18px; }
+    p { margin: 0; color: #8b949e; }
+    a { color: #58a6ff; text-decoration: none; }
+    a:hover { text-decoration: underline; }
+  </style>
+</head>
+<body>
+  <main>
+    <h1>Something went wrong</h1>
+    <p>Go <a href="/">home</a>.</p>
+  </main>
+</body>
+</html>
+"#;
+
+/// Collections served under `/{collection}/{slug}`, used to suggest slugs on 404s.
+const SLUG_COLLECTIONS: [&str; 6] = ["ecosystems", "tools", "events", "learn", "creators", "news"];
+const MAX_SUGGESTIONS: usize = 3;
+/// Host names the site answers to, besides loopback addresses. Each one is
+/// also an SNI name a TLS certificate is picked for.
+const SITE_HOSTS: [&str; 3] = ["rust.dev", "www.rust.dev", "localhost"];
+
+/// Fallback 406 body listing what the route can produce.
+fn not_acceptable(available: &[Format]) -> HttpResponse {
+    let formats: Vec<Value> = available
+        .iter()
+        .map(|format| json!({ "format": format.name(), "media_type": format.media_type() }))
+        .collect();
+    HttpResponse::NotAcceptable()
+        .append_header((header::VARY, "Accept"))
+        .json(json!({
+            "error": "Not acceptable",
+            "code": StatusCode::NOT_ACCEPTABLE.as_u16(),
+            "available": formats,
+        }))
+}
+
+fn error_response(
+    hb: &Handlebars<'_>,
+    req: &HttpRequest,
+    status: StatusCode,
+    collection: Option<&str>,
+    suggestions: Vec<Value>,
+) -> HttpResponse {
+    let code = status.as_u16();
+    let (error, title, message) = if status == StatusCode::NOT_FOUND {
+        ("Not found", "Page not found", "Nothing lives at")
+    } else {
+        (
+            "Internal server error",
+            "Something went wrong",
+            "We could not render",
+        )
+    };
+
+    let format = formats::negotiate(req, &formats::ERROR_FORMATS).unwrap_or(Format::Html);
+    logging::record_format(req, format);
+    if format.is_json() {
+        return HttpResponse::build(status)
+            .append_header((header::VARY, "Accept"))
+            .json(json!({
+            "error": error,
+            "code": code,
+            "suggestions": suggestions,
+            }));
+    }
+
+    let nonce = security::CspNonce::new();
+    let context = json!({
+        "code": code,
+        "title": title,
+        "message": message,
+        "path": req.path(),
+        "collection": collection,
+        "suggestions": suggestions,
+        "csp_nonce": nonce.as_str(),
+    });
+    let rendered = tracing::info_span!("render", template = "error", format = format.name())
+        .in_scope(|| hb.render("error", &context));
+    let body = match rendered {
+        Ok(body) => body,
+        Err(err) => {
+            tracing::error!(
+                request_id = %logging::request_id(req),
+                template = "error",
+                %err,
+                "template render failed"
+            );
+            metrics::render_error("error");
+            FALLBACK_ERROR_HTML.to_string()
+        }
+    };
+    let mut res = HttpResponse::build(status)
+        .append_header((header::CONTENT_TYPE, HTML_CONTENT_TYPE))
+        .append_header((header::CACHE_CONTROL, "no-store"))
+        .append_header((header::VARY, "Accept"))
+        .body(body);
+    nonce.attach(&mut res);
+    res
+}
+
+/// Renders a 404 for the current request. Under a known collection such as
+/// `/tools/{slug}`, the closest slugs by edit distance are suggested.
+fn not_found_for_request(
+    hb: &Handlebars<'_>,
+    rustdev: &RustDevContent,
+    req: &HttpRequest,
+) -> HttpResponse {
+    let mut segments = req.path().trim_matches('/').split('/');
+    let collection = segments
+        .next()
+        .and_then(|first| SLUG_COLLECTIONS.iter().find(|c| **c == first).copied());
+    let slug = segments.next().unwrap_or("");
+
+    let suggestions = match collection {
+        Some(collection) if !slug.is_empty() && segments.next().is_none() => rustdev
+            .suggest_slugs(collection, slug, MAX_SUGGESTIONS)
+            .into_iter()
+            .map(|(slug, title)| {
+                json!({
+                    "slug": slug,
+                    "title": title,
+                    "href": format!("/{collection}/{slug}"),
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    metrics::not_found(collection);
+    error_response(hb, req, StatusCode::NOT_FOUND, collection, suggestions)
+}
+
+async fn not_found_fallback(state: web::Data<SiteState>, req: HttpRequest) -> HttpResponse {
+    let Some(site) = state.snapshot() else {
+        return starting_up();
+    };
+    not_found_for_request(&site.hb, &site.rustdev, &req)
+}
+
+#[tracing::instrument(name = "host_check", skip_all)]
+fn is_allowed_host(req: &HttpRequest) -> bool {
+    let host = req.connection_info().host().to_ascii_lowercase();
+    let hostname = host.split(':').next().unwrap_or(&host);
+    SITE_HOSTS.contains(&hostname) || hostname.starts_with("127.")
+}
+
+/// Renders one representation of a page: the handlebars template for HTML,
+/// the raw context for JSON, and derived JSON-LD, Markdown or plain text
+/// otherwise. JSON-LD identifiers are absolute on `site_url`.
+#[tracing::instrument(
+    name = "render",
+    skip_all,
+    fields(template = template, format = format.name(), path = path)
+)]
+fn render_template_or_json(
+    hb: &Handlebars<'_>,
+    template: &str,
+    context: &Value,
+    format: Format,
+    site_url: &str,
+    path: &str,
+) -> Result<String, handlebars::RenderError> {
+    Ok(match format {
+        Format::Html => hb.render(template, context)?,
+        Format::Json => context.to_string(),
+        Format::JsonLd => formats::json_ld(template, context, site_url, path).to_string(),
+        Format::Markdown => formats::markdown(template, context),
+        Format::Text => formats::plain_text(template, context),
+    })
+}
+
+/// Serves every page route from the pre-rendered cache of the current
+/// `Site`, negotiating the representation from `?format=` and `Accept`.
+async fn page(state: web::Data<SiteState>, req: HttpRequest) -> HttpResponse {
+    let Some(site) = state.snapshot() else {
+        return starting_up();
+    };
+    if !is_allowed_host(&req) {
+        return not_found_for_request(&site.hb, &site.rustdev, &req);
+    }
+
+    let path = match req.path() {
+        "/index.html" => "/",
+        path => path,
+    };
+    let Some(cached) = tracing::info_span!("slug_lookup", path).in_scope(|| site.pages.get(path))
+    else {
+        metrics::cache_lookup("pages", false);
+        return not_found_for_request(&site.hb, &site.rustdev, &req);
+    };
+    let format = match formats::negotiate(&req, &formats::PAGE_FORMATS) {
+        Ok(format) => format,
+        Err(_) => return not_acceptable(&formats::PAGE_FORMATS),
+    };
+    logging::record_format(&req, format);
+    security::record_policy(&req, cached.policy());
+    let Some(representation) = cached.get(format) else {
+        metrics::cache_lookup("pages", false);
+        tracing::error!(
+            request_id = %logging::request_id(&req),
+            template = cached.template(),
+            format = format.name(),
+            path,
+            "representation missing after failed render"
+        );
+        return error_response(
+            &site.hb,
+            &req,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            None,
+            Vec::new(),
+        );
+    };
+
+    metrics::cache_lookup("pages", true);
+    let (encoding, body) = representation.select(req.headers());
+    let validators = &body.validators;
+    if validators.is_fresh(&req) {
+        return validators.not_modified(HTML_CACHE_CONTROL, Some(PAGE_VARY));
+    }
+
+    let mut builder = HttpResponse::Ok();
+    validators.apply(&mut builder);
+    if let Some(encoding) = encoding {
+        builder.append_header((header::CONTENT_ENCODING, encoding.token()));
+    }
+    builder
+        .append_header((header::CONTENT_TYPE, format.content_type()))
+        .append_header((header::CACHE_CONTROL, HTML_CACHE_CONTROL))
+        .append_header((header::VARY, PAGE_VARY))
+        .body(body.bytes.clone())
+}
+
+/// Serves files from the asset directory. Fingerprinted names are cached
+/// as immutable; anything not in the manifest is a 404.
+async fn asset(
+    path: web::Path<String>,
+    state: web::Data<SiteState>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let Some(site) = state.snapshot() else {
+        return starting_up();
+    };
+    if is_allowed_host(&req) {
+        if let Some(res) = site.assets.serve(path.as_str(), &req).await {
+            return res;
+        }
+    }
+    not_found_for_request(&site.hb, &site.rustdev, &req)
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct Pages {
+    #[serde(default)]
+    tools: ToolPage,
+    #[serde(default)]
+    learn: LearnPage,
+    #[serde(default)]
+    work: WorkPage,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct ToolPage {
+    #[serde(default)]
+    categories: Vec<ToolCategory>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct LearnPage {
+    #[serde(default)]
+    tracks: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct WorkPage {
+    #[serde(default)]
+    job_sources: Vec<String>,
+    #[serde(default)]
+    role_archetypes: Vec<RoleArchetype>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+struct ToolCategory {
+    slug: String,
+    title: String,
+    #[serde(default)]
+    items: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+struct RoleArchetype {
+    title: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+struct MediaItem {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct FeaturedMedia {
+    #[serde(default)]
+    youtube: Option<MediaItem>,
+    #[serde(default)]
+    twitter: Option<MediaItem>,
+    #[serde(default)]
+    x: Option<MediaItem>,
+    #[serde(default)]
+    article: Option<MediaItem>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct MediaAsset {
+    #[serde(default)]
+    logo_url: Option<String>,
+    #[serde(default)]
+    avatar_url: Option<String>,
+    #[serde(default)]
+    background_url: Option<String>,
+    #[serde(default)]
+    card_url: Option<String>,
+    #[serde(default)]
+    teaser_thumb_url: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct Updates {
+    #[serde(default)]
+    github_releases: Option<String>,
+    #[serde(default)]
+    github_tags: Option<String>,
+    #[serde(default)]
+    github_issues: Option<String>,
+    #[serde(default)]
+    site: Option<String>,
+    #[serde(default)]
+    twitter: Option<String>,
+    #[serde(default)]
+    youtube: Option<String>,
+    #[serde(default)]
+    schedule: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+struct Ecosystem {
+    slug: String,
+    name: String,
+    #[serde(default)]
+    one_liner: String,
+    #[serde(default)]
+    featured_media: Option<FeaturedMedia>,
+    #[serde(default)]
+    media: Option<MediaAsset>,
+    #[serde(default)]
+    topics: Vec<String>,
+    #[serde(default)]
+    official_links: HashMap<String, String>,
+    #[serde(default)]
+    featured_tools: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+struct Tool {
+    slug: String,
+    name: String,
+    #[serde(default)]
+    category: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    featured_media: Option<FeaturedMedia>,
+    #[serde(default)]
+    media: Option<MediaAsset>,
+    #[serde(default)]
+    #[schemars(with = "Vec<schema::LabelSlug>")]
+    labels: Vec<String>,
+    #[serde(default)]
+    #[schemars(with = "Option<schema::LabelSlug>")]
+    primary_label: Option<String>,
+    #[serde(default)]
+    tier: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    links: HashMap<String, String>,
+    #[serde(default)]
+    updates: Option<Updates>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+struct Event {
+    slug: String,
+    title: String,
+    #[serde(default)]
+    href: Option<String>,
+    #[serde(default)]
+    teaser: Option<String>,
+    #[serde(default)]
+    schedule_note: Option<String>,
+    #[serde(default)]
+    featured_media: Option<FeaturedMedia>,
+    #[serde(default)]
+    media: Option<MediaAsset>,
+    #[serde(default)]
+    #[schemars(with = "Vec<schema::LabelSlug>")]
+    labels: Vec<String>,
+    #[serde(default)]
+    #[schemars(with = "Option<schema::LabelSlug>")]
+    primary_label: Option<String>,
+    #[serde(default)]
+    #[schemars(schema_with = "schema::event_status")]
+    status: String,
+    #[serde(default)]
+    starts_on: Option<String>,
+    #[serde(default)]
+    ends_on: Option<String>,
+    #[serde(default)]
+    location: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    url: String,
+    #[serde(default)]
+    updates: Option<Updates>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+struct LearningPath {
+    slug: String,
+    title: String,
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    featured_media: Option<FeaturedMedia>,
+    #[serde(default)]
+    media: Option<MediaAsset>,
+    #[serde(default)]
+    difficulty: String,
+    #[serde(default)]
+    duration_hours: Option<u32>,
+    #[serde(default)]
+    milestones: Vec<String>,
+    #[serde(default)]
+    resources: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct BestStart {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    url: Option<String>,
+}
+
+impl BestStart {
+    fn is_valid(&self) -> bool {
+        self.title
+            .as_ref()
+            .map(|s| !s.trim().is_empty())
+            .unwrap_or(false)
+            && self
+                .url
+                .as_ref()
+                .map(|s| !s.trim().is_empty())
+                .unwrap_or(false)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+struct Creator {
+    slug: String,
+    name: String,
+    #[serde(rename = "type")]
+    #[schemars(schema_with = "schema::creator_type")]
+    r#type: String,
+    #[serde(default)]
+    featured_media: Option<FeaturedMedia>,
+    #[serde(default)]
+    media: Option<MediaAsset>,
+    #[serde(default)]
+    #[schemars(with = "Vec<schema::LabelSlug>")]
+    labels: Vec<String>,
+    #[serde(default)]
+    #[schemars(with = "Option<schema::LabelSlug>")]
+    primary_label: Option<String>,
+    #[serde(default)]
+    focus: Vec<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    links: HashMap<String, String>,
+    #[serde(default)]
+    about: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    video_id: Option<String>,
+    #[serde(default)]
+    best_start: Option<BestStart>,
+    #[serde(default)]
+    thumbnail: Option<String>,
+    #[serde(default)]
+    updates: Option<Updates>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+struct PostLink {
+    label: String,
+    url: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct PostRelated {
+    #[serde(default)]
+    tools: Vec<String>,
+    #[serde(default)]
+    events: Vec<String>,
+    #[serde(default)]
+    protocols: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+struct Post {
+    slug: String,
+    title: String,
+    #[serde(default)]
+    featured_media: Option<FeaturedMedia>,
+    #[serde(default)]
+    about: Option<String>,
+    #[serde(default)]
+    media: Option<MediaAsset>,
+    #[serde(default)]
+    #[schemars(with = "Vec<schema::LabelSlug>")]
+    labels: Vec<String>,
+    #[serde(default)]
+    #[schemars(with = "Option<schema::LabelSlug>")]
+    primary_label: Option<String>,
+    #[serde(default)]
+    deck: String,
+    #[serde(default)]
+    kind: String,
+    #[serde(default)]
+    published_on: String,
+    #[serde(default)]
+    author_handle: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    cover_image: Option<String>,
+    #[serde(default)]
+    links: Vec<PostLink>,
+    #[serde(default)]
+    body_md: String,
+    #[serde(default)]
+    sources: Vec<String>,
+    #[serde(default)]
+    related: Option<PostRelated>,
+    #[serde(default)]
+    updates: Option<Updates>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+struct Resource {
+    #[serde(default)]
+    slug: Option<String>,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    url: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+struct JobSource {
+    slug: String,
+    name: String,
+    url: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct JobCompany {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    domain: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct Job {
+    #[serde(default)]
+    slug: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    company: JobCompany,
+    #[serde(default)]
+    #[schemars(with = "Vec<schema::LabelSlug>")]
+    labels: Vec<String>,
+    #[serde(default)]
+    #[schemars(with = "Option<schema::LabelSlug>")]
+    primary_label: Option<String>,
+    #[serde(default)]
+    about: String,
+    #[serde(default)]
+    apply_url: String,
+    #[serde(default)]
+    last_verified: Option<String>,
+    #[serde(default)]
+    media: Option<MediaAsset>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct PromoSlide {
+    /// The site section the slide links into, unless it has an `href`.
+    #[serde(default)]
+    #[schemars(schema_with = "schema::promo_slide_type")]
+    r#type: String,
+    #[serde(default)]
+    slug: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    deck: String,
+    #[serde(default)]
+    kind: String,
+    #[serde(default)]
+    published_on: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    href: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct PromoContent {
+    #[serde(default)]
+    slides: Vec<PromoSlide>,
+    #[serde(skip)]
+    last_modified: Option<SystemTime>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct Label {
+    slug: String,
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct Taxonomy {
+    #[serde(default)]
+    labels: Vec<Label>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+struct RustDevSeed {
+    /// `rustdev-hub-seed-v<major>.<minor>`. Older seeds are migrated when
+    /// loaded; `rustdev migrate` rewrites them at the current version.
+    #[serde(default)]
+    version: Option<String>,
+    #[serde(default)]
+    generated_at: Option<String>,
+    #[serde(default)]
+    pages: Pages,
+    #[serde(default)]
+    ecosystems: Vec<Ecosystem>,
+    #[serde(default)]
+    tools: Vec<Tool>,
+    #[serde(default)]
+    events: Vec<Event>,
+    #[serde(default)]
+    learning_paths: Vec<LearningPath>,
+    #[serde(default)]
+    creators: Vec<Creator>,
+    #[serde(default)]
+    posts: Vec<Post>,
+    #[serde(default)]
+    resources: Vec<Resource>,
+    #[serde(default)]
+    job_sources: Vec<JobSource>,
+    #[serde(default)]
+    taxonomy: Taxonomy,
+    #[serde(default)]
+    jobs: Vec<Job>,
+}
+
+#[derive(Clone)]
+struct RustDevContent {
+    /// The seed's `version` string, e.g. `rustdev-hub-seed-v6.2.tagged-expanded`.
+    seed_version: Option<String>,
+    generated_at: Option<String>,
+    /// Later of the seed file's mtime and its `generated_at` date.
+    last_modified: Option<SystemTime>,
+    /// Hash of the seed; changes whenever any of it does.
+    content_version: String,
+    ecosystems: Vec<Ecosystem>,
+    tools: Vec<Tool>,
+    events: Vec<Event>,
+    learning_paths: Vec<LearningPath>,
+    creators: Vec<Creator>,
+    posts: Vec<Post>,
+    resources: HashMap<String, Resource>,
+    job_sources: HashMap<String, JobSource>,
+    jobs: Vec<Job>,
+    tool_categories: Vec<ToolCategory>,
+    learn_tracks: Vec<String>,
+    role_archetypes: Vec<RoleArchetype>,
+    job_source_slugs: Vec<String>,
+    labels: Vec<Label>,
+    tools_index: HashMap<String, usize>,
+    ecosystems_index: HashMap<String, usize>,
+    events_index: HashMap<String, usize>,
+    learning_index: HashMap<String, usize>,
+    creators_index: HashMap<String, usize>,
+    posts_index: HashMap<String, usize>,
+}
+
+fn build_index<T, F>(items: &[T], key: F) -> HashMap<String, usize>
+where
+    F: Fn(&T) -> &str,
+{
+    items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| (key(item).to_owned(), idx))
+        .collect()
+}
+
+fn slugify(input: &str) -> String {
+    let mut slug = String::new();
+    for ch in input.chars() {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch.to_ascii_lowercase());
+        } else if (ch.is_whitespace() || ch == '-' || ch == '_' || ch == '/')
+            && !slug.ends_with('-')
+        {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
+
+/// Levenshtein distance between two strings, counted in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b_chars.len()).collect();
+    let mut curr = vec![0; b_chars.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b_chars.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            curr[j + 1] = (prev[j + 1] + 1).min(curr[j] + 1).min(prev[j] + cost);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b_chars.len()]
+}
+
+fn derive_resource_slug(res: &Resource) -> Option<String> {
+    if let Some(slug) = res.slug.clone() {
+        return Some(slug);
+    }
+    if let Some(url) = res.url.split('/').rev().find(|segment| !segment.is_empty()) {
+        let cleaned = url.split('.').next().unwrap_or(url);
+        if !cleaned.is_empty() {
+            return Some(slugify(cleaned));
+        }
+    }
+    if !res.title.is_empty() {
+        return Some(slugify(&res.title));
+    }
+    None
+}
+
+impl RustDevContent {
+    fn from_seed(seed: RustDevSeed) -> Self {
+        let content_version =
+            conditional::content_hash(&serde_json::to_vec(&seed).unwrap_or_default(), 16);
+        let tools_index = build_index(&seed.tools, |t| t.slug.as_str());
+        let ecosystems_index = build_index(&seed.ecosystems, |e| e.slug.as_str());
+        let events_index = build_index(&seed.events, |e| e.slug.as_str());
+        let learning_index = build_index(&seed.learning_paths, |p| p.slug.as_str());
+        let creators_index = build_index(&seed.creators, |c| c.slug.as_str());
+        let posts_index = build_index(&seed.posts, |p| p.slug.as_str());
+
+        let mut resources = HashMap::new();
+        for mut res in seed.resources {
+            let slug = derive_resource_slug(&res);
+            if let Some(slug_val) = slug {
+                res.slug = Some(slug_val.clone());
+                resources.insert(slug_val, res);
+            }
+        }
+
+        let job_sources = seed
+            .job_sources
+            .into_iter()
+            .map(|src| (src.slug.clone(), src))
+            .collect();
+
+        Self {
+            seed_version: seed.version,
+            generated_at: seed.generated_at,
+            last_modified: None,
+            content_version,
+            ecosystems: seed.ecosystems,
+            tools: seed.tools,
+            events: seed.events,
+            learning_paths: seed.learning_paths,
+            creators: seed.creators,
+            posts: seed.posts,
+            resources,
+            job_sources,
+            jobs: seed.jobs,
+            tool_categories: seed.pages.tools.categories,
+            learn_tracks: seed.pages.learn.tracks,
+            role_archetypes: seed.pages.work.role_archetypes,
+            job_source_slugs: seed.pages.work.job_sources,
+            labels: seed.taxonomy.labels,
+            tools_index,
+            ecosystems_index,
+            events_index,
+            learning_index,
+            creators_index,
+            posts_index,
+        }
+    }
+
+    fn ecosystem_by_slug(&self, slug: &str) -> Option<&Ecosystem> {
+        self.ecosystems_index
+            .get(slug)
+            .and_then(|idx| self.ecosystems.get(*idx))
+    }
+
+    fn tool_by_slug(&self, slug: &str) -> Option<&Tool> {
+        self.tools_index
+            .get(slug)
+            .and_then(|idx| self.tools.get(*idx))
+    }
+
+    fn event_by_slug(&self, slug: &str) -> Option<&Event> {
+        self.events_index
+            .get(slug)
+            .and_then(|idx| self.events.get(*idx))
+    }
+
+    fn learning_path_by_slug(&self, slug: &str) -> Option<&LearningPath> {
+        self.learning_index
+            .get(slug)
+            .and_then(|idx| self.learning_paths.get(*idx))
+    }
+
+    fn creator_by_slug(&self, slug: &str) -> Option<&Creator> {
+        self.creators_index
+            .get(slug)
+            .and_then(|idx| self.creators.get(*idx))
+    }
+
+    fn post_by_slug(&self, slug: &str) -> Option<&Post> {
+        self.posts_index
+            .get(slug)
+            .and_then(|idx| self.posts.get(*idx))
+    }
+
+    /// `(slug, title)` pairs for a collection, keyed by its URL prefix.
+    fn collection_entries(&self, collection: &str) -> Vec<(&str, &str)> {
+        match collection {
+            "ecosystems" => self
+                .ecosystems
+                .iter()
+                .map(|e| (e.slug.as_str(), e.name.as_str()))
+                .collect(),
+            "tools" => self
+                .tools
+                .iter()
+                .map(|t| (t.slug.as_str(), t.name.as_str()))
+                .collect(),
+            "events" => self
+                .events
+                .iter()
+                .map(|e| (e.slug.as_str(), e.title.as_str()))
+                .collect(),
+            "learn" => self
+                .learning_paths
+                .iter()
+                .map(|p| (p.slug.as_str(), p.title.as_str()))
+                .collect(),
+            "creators" => self
+                .creators
+                .iter()
+                .map(|c| (c.slug.as_str(), c.name.as_str()))
+                .collect(),
+            "news" => self
+                .posts
+                .iter()
+                .map(|p| (p.slug.as_str(), p.title.as_str()))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Slugs in `collection` closest to `slug` by edit distance. Candidates
+    /// that contain the requested slug (or vice versa) always qualify.
+    fn suggest_slugs(&self, collection: &str, slug: &str, limit: usize) -> Vec<(String, String)> {
+        let wanted = slug.to_ascii_lowercase();
+        let threshold = (wanted.chars().count() / 3).max(2);
+
+        let mut scored: Vec<(usize, &str, &str)> = self
+            .collection_entries(collection)
+            .into_iter()
+            .filter_map(|(candidate, title)| {
+                let distance = edit_distance(&wanted, candidate);
+                let related = candidate.contains(wanted.as_str()) || wanted.contains(candidate);
+                (distance <= threshold || related).then_some((distance, candidate, title))
+            })
+            .collect();
+        scored.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(b.1)));
+
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, slug, title)| (slug.to_string(), title.to_string()))
+            .collect()
+    }
+
+    fn tools_for(&self, slugs: &[String]) -> Vec<Tool> {
+        slugs
+            .iter()
+            .filter_map(|slug| self.tool_by_slug(slug))
+            .cloned()
+            .collect()
+    }
+
+    fn resources_for(&self, slugs: &[String]) -> Vec<Resource> {
+        slugs
+            .iter()
+            .filter_map(|slug| self.resources.get(slug).cloned())
+            .collect()
+    }
+
+    fn job_sources_for(&self, slugs: &[String]) -> Vec<JobSource> {
+        slugs
+            .iter()
+            .filter_map(|slug| self.job_sources.get(slug).cloned())
+            .collect()
+    }
+
+    fn job_sources_in_order(&self) -> Vec<JobSource> {
+        let ordered = self.job_sources_for(&self.job_source_slugs);
+        if !ordered.is_empty() {
+            return ordered;
+        }
+
+        let mut fallback: Vec<JobSource> = self.job_sources.values().cloned().collect();
+        fallback.sort_by(|a, b| a.name.cmp(&b.name));
+        fallback
+    }
+
+    fn learning_paths_for_tracks(&self) -> Vec<LearningPath> {
+        if self.learn_tracks.is_empty() {
+            return self.learning_paths.clone();
+        }
+
+        let ordered: Vec<LearningPath> = self
+            .learn_tracks
+            .iter()
+            .filter_map(|slug| self.learning_path_by_slug(slug))
+            .cloned()
+            .collect();
+
+        if ordered.is_empty() {
+            self.learning_paths.clone()
+        } else {
+            ordered
+        }
+    }
+}
+
+async fn file_mtime(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).await.ok()?.modified().ok()
+}
+
+#[tracing::instrument(skip_all, fields(source = %store.describe()))]
+async fn load_rust_dev_content(store: Arc<dyn ContentStore>) -> std::io::Result<RustDevContent> {
+    let (seed, modified) = tokio::task::spawn_blocking(move || store.seed())
+        .await
+        .map_err(std::io::Error::other)??;
+    let generated = seed
+        .generated_at
+        .as_deref()
+        .and_then(helpers::parse_date)
+        .map(|date| SystemTime::from(date.midnight().assume_utc()));
+    let mut content = RustDevContent::from_seed(seed);
+    content.last_modified = modified.max(generated);
+    Ok(content)
+}
+
+async fn load_promo_content(path: impl AsRef<Path>) -> std::io::Result<PromoContent> {
+    let bytes = fs::read(path.as_ref()).await?;
+    let mut content: PromoContent = serde_json::from_slice(&bytes)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    content.last_modified = file_mtime(path.as_ref()).await;
+    Ok(content)
+}
+
+fn build_handlebars(
+    labels: &[Label],
+    config: &SiteConfig,
+    assets: &AssetManifest,
+    today: Date,
+) -> std::io::Result<Handlebars<'static>> {
+    let mut hb = Handlebars::new();
+
+    hb.register_template_file("index-rust", "static/index_rust_dev.html")
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file(
+        "ecosystems-list",
+        "static/rustdev/templates/ecosystems-list.html",
+    )
+    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file(
+        "ecosystem-single",
+        "static/rustdev/templates/ecosystem-single.html",
+    )
+    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file("tools-list", "static/rustdev/templates/tools-list.html")
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file("tool-single", "static/rustdev/templates/tool-single.html")
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file("events-list", "static/rustdev/templates/events-list.html")
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file("event-single", "static/rustdev/templates/event-single.html")
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file("learn-list", "static/rustdev/templates/learn-list.html")
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file(
+        "learning-single",
+        "static/rustdev/templates/learning-single.html",
+    )
+    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file(
+        "creators-list",
+        "static/rustdev/templates/creators-list.html",
+    )
+    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file(
+        "creator-single",
+        "static/rustdev/templates/creator-single.html",
+    )
+    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file("news-list", "static/rustdev/templates/news-list.html")
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file("post-single", "static/rustdev/templates/post-single.html")
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file("jobs-list", "static/rustdev/templates/jobs-list.html")
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file("error", "static/rustdev/templates/error.html")
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    for name in ADMIN_TEMPLATES {
+        hb.register_template_file(
+            &format!("admin/{name}"),
+            format!("static/rustdev/templates/admin/{name}.html"),
+        )
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    }
+    hb.register_template_file(
+        "component/youtube-embed",
+        "static/rustdev/templates/components/youtube-embed.html",
+    )
+    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+    hb.register_template_file(
+        "component/twitter-embed",
+        "static/rustdev/templates/components/twitter-embed.html",
+    )
+    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+
+    helpers::register(
+        &mut hb,
+        labels,
+        &config.site_url,
+        assets,
+        &config.image_hosts,
+        today,
+    );
+
+    Ok(hb)
+}
+
+fn extract_youtube_id(url: &str) -> Option<String> {
+    if let Some(id) = url.split("youtu.be/").nth(1) {
+        return Some(id.split(&['?', '&', '#'][..]).next()?.to_string());
+    }
+    if let Some(pos) = url.find("watch?v=") {
+        return Some(
+            url[(pos + 8)..]
+                .split(&['&', '#'][..])
+                .next()
+                .unwrap_or("")
+                .to_string(),
+        );
+    }
+    if let Some(pos) = url.find("embed/") {
+        return Some(
+            url[(pos + 6)..]
+                .split(&['?', '&', '#'][..])
+                .next()
+                .unwrap_or("")
+                .to_string(),
+        );
+    }
+    None
+}
+
+struct EmbedFragments {
+    youtube: Option<String>,
+    twitter: Option<String>,
+    has_twitter: bool,
+    section_title: Option<String>,
+}
+
+#[tracing::instrument(skip_all)]
+fn build_embed_fragments(hb: &Handlebars<'_>, media: Option<&FeaturedMedia>) -> EmbedFragments {
+    let mut youtube_html = None;
+    let mut twitter_html = None;
+    let mut title = None;
+
+    if let Some(media) = media {
+        if let Some(yt) = media.youtube.as_ref().and_then(|m| m.url.as_ref()) {
+            if let Some(id) = extract_youtube_id(yt) {
+                let video_title = media.youtube.as_ref().and_then(|m| m.title.clone());
+                if title.is_none() {
+                    title = video_title.clone();
+                }
+                if let Ok(html) = hb.render(
+                    "component/youtube-embed",
+                    &json!({ "video_id": id, "video_title": video_title }),
+                ) {
+                    youtube_html = Some(html);
+                }
+            }
+        }
+
+        let twitter_url = media
+            .twitter
+            .as_ref()
+            .and_then(|m| m.url.as_ref())
+            .or_else(|| media.x.as_ref().and_then(|m| m.url.as_ref()))
+            .cloned();
+        if let Some(url) = twitter_url {
+            if title.is_none() {
+                title = media
+                    .twitter
+                    .as_ref()
+                    .and_then(|m| m.title.clone())
+                    .or_else(|| media.x.as_ref().and_then(|m| m.title.clone()));
+            }
+            if let Ok(html) = hb.render("component/twitter-embed", &json!({ "tweet_url": url })) {
+                twitter_html = Some(html);
+            }
+        }
+    }
+
+    EmbedFragments {
+        youtube: youtube_html,
+        has_twitter: twitter_html.is_some(),
+        twitter: twitter_html,
+        section_title: title,
+    }
+}
+
+fn promo_slide_to_value(slide: &PromoSlide) -> Value {
+    json!({
+        "type": slide.r#type,
+        "slug": slide.slug,
+        "title": slide.title,
+        "deck": slide.deck,
+        "kind": if slide.kind.is_empty() { slide.r#type.clone() } else { slide.kind.clone() },
+        "published_on": slide.published_on,
+        "tags": slide.tags,
+        "href": slide.href,
+    })
+}
+
+fn build_carousel_items(promo: &PromoContent, rustdev: &RustDevContent) -> Vec<Value> {
+    let mut items = Vec::new();
+
+    // Promo slides go first (pinned to top of carousel).
+    for slide in &promo.slides {
+        items.push(promo_slide_to_value(slide));
+    }
+
+    // Then latest news.
+    for post in rustdev.posts.iter().take(4) {
+        items.push(json!({
+            "type": "news",
+            "slug": post.slug,
+            "title": post.title,
+            "deck": post.deck,
+            "kind": post.kind.clone(),
+            "published_on": post.published_on,
+            "tags": post.tags,
+        }));
+    }
+
+    // Add upcoming events.
+    for event in rustdev
+        .events
+        .iter()
+        .filter(|e| e.status == "upcoming")
+        .take(3)
+    {
+        let deck = format!(
+            "{}{}{}",
+            event.location,
+            if event.location.is_empty() {
+                ""
+            } else {
+                " • "
+            },
+            event
+                .starts_on
+                .clone()
+                .unwrap_or_else(|| "Date TBA".to_string())
+        );
+        items.push(json!({
+            "type": "events",
+            "slug": event.slug,
+            "title": event.title,
+            "deck": deck,
+            "kind": "event",
+            "published_on": event.starts_on.clone().unwrap_or_default(),
+            "tags": event.tags,
+        }));
+    }
+
+    items.truncate(7);
+    items
+}
+
+fn home_context(promo: &PromoContent, rustdev: &RustDevContent) -> Value {
+    let carousel_items = build_carousel_items(promo, rustdev);
+    json!({ "carousel_items": carousel_items })
+}
+
+fn ecosystems_list_context(rustdev: &RustDevContent) -> Value {
+    json!({ "ecosystems": rustdev.ecosystems.clone() })
+}
+
+fn ecosystem_page_context(
+    hb: &Handlebars<'_>,
+    rustdev: &RustDevContent,
+    ecosystem: &Ecosystem,
+) -> Value {
+    let embeds = build_embed_fragments(hb, ecosystem.featured_media.as_ref());
+    let media = if embeds.youtube.is_some() || embeds.twitter.is_some() {
+        Some(json!({
+            "section_title": embeds.section_title.unwrap_or_else(|| "Featured media".to_string())
+        }))
+    } else {
+        None
+    };
+    json!({
+        "slug": ecosystem.slug,
+        "name": ecosystem.name,
+        "one_liner": ecosystem.one_liner,
+        "topics": ecosystem.topics,
+        "official_links": ecosystem.official_links,
+        "featured_tools": rustdev.tools_for(&ecosystem.featured_tools),
+        "media": media,
+        "embed_youtube": embeds.youtube,
+        "embed_twitter": embeds.twitter,
+        "has_twitter": embeds.has_twitter,
+    })
+}
+
+fn tools_list_context(rustdev: &RustDevContent) -> Value {
+    let categories: Vec<Value> = if rustdev.tool_categories.is_empty() {
+        vec![json!({
+            "title": "All tools",
+            "slug": "all",
+            "tools": rustdev.tools.clone(),
+        })]
+    } else {
+        rustdev
+            .tool_categories
+            .iter()
+            .map(|cat| {
+                let tools = cat
+                    .items
+                    .iter()
+                    .filter_map(|slug| rustdev.tool_by_slug(slug))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                json!({
+                    "title": cat.title,
+                    "slug": cat.slug,
+                    "tools": tools,
+                })
+            })
+            .collect()
+    };
+
+    json!({
+        "categories": categories,
+        "labels": rustdev.labels,
+    })
+}
+
+fn tool_page_context(hb: &Handlebars<'_>, tool: &Tool) -> Value {
+    let embeds = build_embed_fragments(hb, tool.featured_media.as_ref());
+    let media = if embeds.youtube.is_some() || embeds.twitter.is_some() {
+        Some(json!({
+            "section_title": embeds.section_title.unwrap_or_else(|| "Featured media".to_string())
+        }))
+    } else {
+        None
+    };
+    let mut context = serde_json::to_value(tool).unwrap_or_else(|_| json!({}));
+    context["media"] = media.unwrap_or(Value::Null);
+    context["embed_youtube"] = embeds.youtube.map(Value::String).unwrap_or(Value::Null);
+    context["embed_twitter"] = embeds.twitter.map(Value::String).unwrap_or(Value::Null);
+    context["has_twitter"] = Value::Bool(embeds.has_twitter);
+    context
+}
+
+fn events_list_context(rustdev: &RustDevContent) -> Value {
+    let mut upcoming = Vec::new();
+    let mut past = Vec::new();
+
+    for event in &rustdev.events {
+        if event.status == "past" {
+            past.push(event.clone());
+        } else {
+            upcoming.push(event.clone());
+        }
+    }
+
+    json!({
+        "upcoming": upcoming,
+        "past": past,
+        "labels": rustdev.labels,
+    })
+}
+
+fn event_page_context(hb: &Handlebars<'_>, event: &Event) -> Value {
+    let embeds = build_embed_fragments(hb, event.featured_media.as_ref());
+    let media = if embeds.youtube.is_some() || embeds.twitter.is_some() {
+        Some(json!({
+            "section_title": embeds.section_title.unwrap_or_else(|| "Featured media".to_string())
+        }))
+    } else {
+        None
+    };
+    let mut context = serde_json::to_value(event).unwrap_or_else(|_| json!({}));
+    context["media"] = media.unwrap_or(Value::Null);
+    context["embed_youtube"] = embeds.youtube.map(Value::String).unwrap_or(Value::Null);
+    context["embed_twitter"] = embeds.twitter.map(Value::String).unwrap_or(Value::Null);
+    context["has_twitter"] = Value::Bool(embeds.has_twitter);
+    context
+}
+
+fn learn_list_context(rustdev: &RustDevContent) -> Value {
+    let sections = vec![json!({
+        "title": "Learning paths",
+        "paths": rustdev.learning_paths_for_tracks(),
+    })];
+    json!({ "sections": sections })
+}
+
+fn learning_page_context(
+    hb: &Handlebars<'_>,
+    rustdev: &RustDevContent,
+    path: &LearningPath,
+) -> Value {
+    let resources_data = rustdev.resources_for(&path.resources);
+    let embeds = build_embed_fragments(hb, path.featured_media.as_ref());
+    let media = if embeds.youtube.is_some() || embeds.twitter.is_some() {
+        Some(json!({
+            "section_title": embeds.section_title.unwrap_or_else(|| "Featured media".to_string())
+        }))
+    } else {
+        None
+    };
+    json!({
+        "slug": path.slug,
+        "title": path.title,
+        "summary": path.summary,
+        "difficulty": path.difficulty,
+        "duration_hours": path.duration_hours,
+        "milestones": path.milestones,
+        "resources_data": resources_data,
+        "media": media,
+        "embed_youtube": embeds.youtube,
+        "embed_twitter": embeds.twitter,
+        "has_twitter": embeds.has_twitter,
+    })
+}
+
+fn creator_section_title(kind: &str) -> String {
+    match kind {
+        "youtube" => "YouTube".to_string(),
+        "podcast" => "Podcasts".to_string(),
+        "newsletter" => "Newsletters".to_string(),
+        "playlist" => "Playlists".to_string(),
+        other => {
+            let mut chars = other.chars();
+            if let Some(first) = chars.next() {
+                format!("{}{}", first.to_uppercase(), chars.as_str())
+            } else {
+                "Creators".to_string()
+            }
+        }
+    }
+}
+
+fn creators_list_context(rustdev: &RustDevContent) -> Value {
+    let mut grouped: HashMap<String, Vec<Creator>> = HashMap::new();
+    for creator in &rustdev.creators {
+        grouped
+            .entry(creator.r#type.clone())
+            .or_default()
+            .push(creator.clone());
+    }
+
+    let mut sections: Vec<Value> = Vec::new();
+
+    // Order: playlist -> youtube -> newsletter -> everything else (alphabetical).
+    if let Some(creators) = grouped.remove("playlist") {
+        sections.push(json!({
+            "title": creator_section_title("playlist"),
+            "creators": creators,
+        }));
+    }
+    if let Some(creators) = grouped.remove("youtube") {
+        sections.push(json!({
+            "title": creator_section_title("youtube"),
+            "creators": creators,
+        }));
+    }
+    if let Some(creators) = grouped.remove("newsletter") {
+        sections.push(json!({
+            "title": creator_section_title("newsletter"),
+            "creators": creators,
+        }));
+    }
+
+    let mut remaining: Vec<_> = grouped.into_iter().collect();
+    remaining.sort_by(|a, b| a.0.cmp(&b.0));
+    for (kind, creators) in remaining {
+        sections.push(json!({
+            "title": creator_section_title(&kind),
+            "creators": creators,
+        }));
+    }
+
+    json!({
+        "sections": sections,
+        "labels": rustdev.labels,
+    })
+}
+
+fn creator_page_context(hb: &Handlebars<'_>, creator: &Creator) -> Value {
+    let embeds = build_embed_fragments(hb, creator.featured_media.as_ref());
+    let media = if embeds.youtube.is_some() || embeds.twitter.is_some() {
+        Some(json!({
+            "section_title": embeds.section_title.unwrap_or_else(|| "Featured media".to_string())
+        }))
+    } else {
+        None
+    };
+    let best_start = creator.best_start.as_ref().and_then(|bs| {
+        if bs.is_valid() {
+            Some(bs.clone())
+        } else {
+            None
+        }
+    });
+    json!({
+        "slug": creator.slug,
+        "name": creator.name,
+        "type": creator.r#type,
+        "focus": creator.focus,
+        "links": creator.links,
+        "about": creator.about,
+        "description": creator.description,
+        "video_id": creator.video_id,
+        "best_start": best_start,
+        "thumbnail": creator.thumbnail,
+        "media": media,
+        "embed_youtube": embeds.youtube,
+        "embed_twitter": embeds.twitter,
+        "has_twitter": embeds.has_twitter,
+    })
+}
+
+fn news_list_context(rustdev: &RustDevContent) -> Value {
+    json!({
+        "posts": rustdev.posts.clone(),
+        "labels": rustdev.labels,
+    })
+}
+
+fn post_page_context(hb: &Handlebars<'_>, post: &Post) -> Value {
+    let embeds = build_embed_fragments(hb, post.featured_media.as_ref());
+    let media = if embeds.youtube.is_some() || embeds.twitter.is_some() {
+        Some(json!({
+            "section_title": embeds.section_title.unwrap_or_else(|| "Featured media".to_string())
+        }))
+    } else {
+        None
+    };
+    let mut context = serde_json::to_value(post).unwrap_or_else(|_| json!({}));
+    // Fallback body from about/deck if body_md is missing.
+    if context
+        .get("body_md")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().is_empty())
+        .unwrap_or(true)
+    {
+        if let Some(about) = context.get("about").and_then(|v| v.as_str()) {
+            context["body_md"] = Value::String(about.to_string());
+        } else if let Some(deck) = context.get("deck").and_then(|v| v.as_str()) {
+            context["body_md"] = Value::String(deck.to_string());
+        }
+    }
+    context["media"] = media.unwrap_or(Value::Null);
+    context["embed_youtube"] = embeds.youtube.map(Value::String).unwrap_or(Value::Null);
+    context["embed_twitter"] = embeds.twitter.map(Value::String).unwrap_or(Value::Null);
+    context["has_twitter"] = Value::Bool(embeds.has_twitter);
+    context
+}
+
+fn jobs_list_context(rustdev: &RustDevContent) -> Value {
+    json!({
+        "job_sources": rustdev.job_sources_in_order(),
+        "role_archetypes": rustdev.role_archetypes.clone(),
+        "labels": rustdev.labels,
+        "jobs": rustdev.jobs,
+    })
+}
+
+/// Every page route with the template and context it renders.
+fn page_sources(
+    hb: &Handlebars<'_>,
+    rustdev: &RustDevContent,
+    promo: &PromoContent,
+) -> Vec<PageSource> {
+    let modified = rustdev.last_modified;
+    let mut pages = vec![
+        PageSource::new(
+            "/",
+            "index-rust",
+            home_context(promo, rustdev),
+            modified.max(promo.last_modified),
+        ),
+        PageSource::new(
+            "/ecosystems",
+            "ecosystems-list",
+            ecosystems_list_context(rustdev),
+            modified,
+        ),
+        PageSource::new(
+            "/tools",
+            "tools-list",
+            tools_list_context(rustdev),
+            modified,
+        ),
+        PageSource::new(
+            "/events",
+            "events-list",
+            events_list_context(rustdev),
+            modified,
+        ),
+        PageSource::new(
+            "/learn",
+            "learn-list",
+            learn_list_context(rustdev),
+            modified,
+        ),
+        PageSource::new(
+            "/creators",
+            "creators-list",
+            creators_list_context(rustdev),
+            modified,
+        ),
+        PageSource::new("/news", "news-list", news_list_context(rustdev), modified),
+        PageSource::new("/jobs", "jobs-list", jobs_list_context(rustdev), modified),
+    ];
+
+    for ecosystem in &rustdev.ecosystems {
+        pages.push(PageSource::new(
+            format!("/ecosystems/{}", ecosystem.slug),
+            "ecosystem-single",
+            ecosystem_page_context(hb, rustdev, ecosystem),
+            modified,
+        ));
+    }
+    for tool in &rustdev.tools {
+        pages.push(PageSource::new(
+            format!("/tools/{}", tool.slug),
+            "tool-single",
+            tool_page_context(hb, tool),
+            modified,
+        ));
+    }
+    for event in &rustdev.events {
+        pages.push(PageSource::new(
+            format!("/events/{}", event.slug),
+            "event-single",
+            event_page_context(hb, event),
+            modified,
+        ));
+    }
+    for path in &rustdev.learning_paths {
+        pages.push(PageSource::new(
+            format!("/learn/{}", path.slug),
+            "learning-single",
+            learning_page_context(hb, rustdev, path),
+            modified,
+        ));
+    }
+    for creator in &rustdev.creators {
+        pages.push(PageSource::new(
+            format!("/creators/{}", creator.slug),
+            "creator-single",
+            creator_page_context(hb, creator),
+            modified,
+        ));
+    }
+    for post in &rustdev.posts {
+        pages.push(PageSource::new(
+            format!("/news/{}", post.slug),
+            "post-single",
+            post_page_context(hb, post),
+            modified,
+        ));
+    }
+
+    pages
+}
+
+/// Settings read from the environment at startup.
+struct SiteConfig {
+    seed: Arc<dyn ContentStore>,
+    site_url: String,
+    assets_dir: PathBuf,
+    image_hosts: AllowedHosts,
+    csp: CspConfig,
+}
+
+/// One consistent version of the site: content, the asset manifest, the
+/// templates built for them and every page pre-rendered from all three.
+struct Site {
+    /// When this version finished building.
+    loaded_at: SystemTime,
+    hb: Handlebars<'static>,
+    rustdev: RustDevContent,
+    assets: AssetManifest,
+    pages: PageCache,
+}
+
+impl Site {
+    fn build(
+        rustdev: RustDevContent,
+        promo: PromoContent,
+        config: &SiteConfig,
+        today: Date,
+    ) -> std::io::Result<Self> {
+        let assets = AssetManifest::build(&config.assets_dir)?;
+        let hb = build_handlebars(&rustdev.labels, config, &assets, today)?;
+        let pages = PageCache::build(
+            &hb,
+            page_sources(&hb, &rustdev, &promo),
+            &config.site_url,
+            today,
+        );
+        Ok(Self {
+            loaded_at: SystemTime::now(),
+            hb,
+            rustdev,
+            assets,
+            pages,
+        })
+    }
+}
+
+/// Loads the content files and pre-renders a `Site` from them off the
+/// async workers.
+#[tracing::instrument(name = "content_load", skip_all)]
+async fn load_site(config: Arc<SiteConfig>) -> std::io::Result<Site> {
+    let rustdev = load_rust_dev_content(config.seed.clone()).await?;
+    let promo = load_promo_content(PROMO_PATH).await.unwrap_or_default();
+    let span = tracing::info_span!("site_build");
+    let today = OffsetDateTime::now_utc().date();
+    tokio::task::spawn_blocking(move || {
+        span.in_scope(|| Site::build(rustdev, promo, &config, today))
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+/// Versions of the seed, the promo file and the asset directory.
+type ContentVersions = (StoreVersion, Option<SystemTime>, Option<String>);
+
+/// The `Site` currently being served. Requests take a snapshot, so a reload
+/// swaps versions without ever mixing two in one response.
+struct SiteState {
+    current: RwLock<Option<Arc<Site>>>,
+    config: Arc<SiteConfig>,
+    /// Content versions the current `Site` was loaded from.
+    loaded_from: Mutex<ContentVersions>,
+    /// Held for a whole reload, so a slow build of older files cannot
+    /// replace a newer one.
+    reloading: tokio::sync::Mutex<()>,
+}
+
+impl SiteState {
+    fn new(config: Arc<SiteConfig>) -> Self {
+        Self {
+            current: RwLock::new(None),
+            config,
+            loaded_from: Mutex::new((StoreVersion::Modified(None), None, None)),
+            reloading: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// `None` until the first `Site` has been built.
+    fn snapshot(&self) -> Option<Arc<Site>> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn replace(&self, site: Site) {
+        *self.current.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(site));
+    }
+
+    fn loaded_from(&self) -> ContentVersions {
+        self.loaded_from
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+/// 503 for requests that arrive before the first `Site` is ready.
+fn starting_up() -> HttpResponse {
+    HttpResponse::ServiceUnavailable()
+        .append_header((header::RETRY_AFTER, "1"))
+        .append_header((header::CACHE_CONTROL, "no-store"))
+        .json(json!({
+            "error": "Starting up",
+            "code": StatusCode::SERVICE_UNAVAILABLE.as_u16(),
+        }))
+}
+
+async fn content_versions(config: &SiteConfig) -> ContentVersions {
+    let store = config.seed.clone();
+    let assets_dir = config.assets_dir.clone();
+    (
+        tokio::task::spawn_blocking(move || store.version())
+            .await
+            .unwrap_or(StoreVersion::Modified(None)),
+        file_mtime(Path::new(PROMO_PATH)).await,
+        tokio::task::spawn_blocking(move || assets::dir_version(&assets_dir).ok())
+            .await
+            .ok()
+            .flatten(),
+    )
+}
+
+/// Rebuilds the `Site` from the content files and swaps it in. A failed
+/// rebuild keeps the previous version live.
+async fn reload_site(state: &SiteState) -> std::io::Result<()> {
+    let _reloading = state.reloading.lock().await;
+    *state
+        .loaded_from
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = content_versions(&state.config).await;
+    match load_site(state.config.clone()).await {
+        Ok(site) => {
+            tracing::info!(pages = site.pages.len(), "content reloaded");
+            metrics::content_reload(true);
+            state.replace(site);
+            Ok(())
+        }
+        Err(err) => {
+            tracing::warn!(%err, "content reload failed, keeping previous version");
+            metrics::content_reload(false);
+            Err(err)
+        }
+    }
+}
+
+/// Builds the first `Site` while the server is already accepting
+/// connections, then keeps it current. Failing to load at startup returns
+/// the error, which stops the server.
+async fn serve_content(
+    state: web::Data<SiteState>,
+    reload: Option<Duration>,
+) -> std::io::Result<()> {
+    *state
+        .loaded_from
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = content_versions(&state.config).await;
+    match load_site(state.config.clone()).await {
+        Ok(site) => {
+            tracing::info!(
+                pages = site.pages.len(),
+                assets = site.assets.len(),
+                "content pre-rendered"
+            );
+            metrics::content_reload(true);
+            state.replace(site);
+        }
+        Err(err) => {
+            tracing::error!(%err, "content load failed");
+            metrics::content_reload(false);
+            return Err(err);
+        }
+    }
+    let watch = {
+        let state = state.clone();
+        async move {
+            if let Some(interval) = reload {
+                watch_content(state, interval).await;
+            }
+        }
+    };
+    tokio::join!(watch, roll_over_days(state));
+    Ok(())
+}
+
+/// Rebuilds the `Site` after each UTC midnight, so pages showing relative
+/// dates such as "verified 3 days ago" do not keep the day they were
+/// rendered on.
+async fn roll_over_days(state: web::Data<SiteState>) {
+    loop {
+        let now = OffsetDateTime::now_utc();
+        let Some(tomorrow) = now.date().next_day() else {
+            return;
+        };
+        let until = tomorrow.midnight().assume_utc() - now;
+        tokio::time::sleep(until.try_into().unwrap_or_default()).await;
+        // Failures are logged and keep the previous version; the next
+        // midnight retries.
+        let _ = reload_site(&state).await;
+    }
+}
+
+/// Polls the content and rebuilds the `Site` when the seed, the promo file
+/// or an asset changes since the current version was loaded. A git seed
+/// changes when its branch moves.
+async fn watch_content(state: web::Data<SiteState>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        if content_versions(&state.config).await == state.loaded_from() {
+            continue;
+        }
+        // Failures are logged; the next change to the files retries.
+        let _ = reload_site(&state).await;
+    }
+}
+
+/// Runs an auxiliary listener when one is configured.
+async fn run_optional(server: Option<actix_web::dev::Server>) -> std::io::Result<()> {
+    match server {
+        Some(server) => server.await,
+        None => Ok(()),
+    }
+}
+
+/// Runs a maintenance subcommand instead of the server.
+fn run_command(args: &[String]) -> std::io::Result<()> {
+    match args[0].as_str() {
+        "hash-password" => auth::hash_password_command(),
+        "migrate-store" => store::migrate_store_command(&args[1..]),
+        "split" => directory::split_command(&args[1..]),
+        "lint" => lint::lint_command(&args[1..]),
+        "migrate" => migrate::migrate_command(&args[1..]),
+        "schema" => schema::schema_command(&args[1..]),
+        other => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("unknown command `{other}`; available: hash-password, lint, migrate, migrate-store, schema, split"),
+        )),
+    }
+}
+
+/// The `rustdev` binary: runs a command when given arguments, and serves
+/// the site otherwise.
+#[actix_web::main]
+pub async fn main() -> std::io::Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        return run_command(&args);
+    }
+    let telemetry = logging::init();
+    let seed: Arc<dyn ContentStore> = store::from_env()?.into();
+    tracing::info!(source = %seed.describe(), "serving content");
+    let config = Arc::new(SiteConfig {
+        seed,
+        site_url: env::var("SITE_URL").unwrap_or_else(|_| DEFAULT_SITE_URL.to_string()),
+        assets_dir: env::var("ASSETS_DIR")
+            .unwrap_or_else(|_| DEFAULT_ASSETS_DIR.to_string())
+            .into(),
+        image_hosts: match env::var("IMG_ALLOWED_HOSTS") {
+            Ok(hosts) => AllowedHosts::new(hosts.split(',')),
+            Err(_) => AllowedHosts::new(images::DEFAULT_ALLOWED_HOSTS),
+        },
+        csp: CspConfig {
+            report_only: env::var("CSP_REPORT_ONLY").is_ok_and(|v| v == "1" || v == "true"),
+            report_uri: env::var("CSP_REPORT_URI").ok().filter(|v| !v.is_empty()),
+        },
+    });
+    let image_proxy = web::Data::new(ImageProxy::new(
+        config.image_hosts.clone(),
+        env::var("IMG_CACHE_DIR")
+            .unwrap_or_else(|_| images::DEFAULT_CACHE_DIR.to_string())
+            .into(),
+        env::var("IMG_CACHE_MAX_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(images::DEFAULT_CACHE_MAX_BYTES),
+    )?);
+
+    let port: u16 = env::var("PORT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_PORT);
+    let addr: std::net::SocketAddr = format!("0.0.0.0:{port}")
+        .parse()
+        .expect("invalid listen addr");
+    let tls = tls::TlsConfig::from_env()?.map(Arc::new);
+    let rate_limiter = ratelimit::RateLimiter::from_env()?.map(web::Data::new);
+    let api_token = admin::ApiToken::from_env().map(web::Data::new);
+    let accounts = auth::Accounts::from_env()?.map(web::Data::new);
+    let lint_config = web::Data::new(lint::LintConfig::from_env()?);
+    let editor = (api_token.is_some() || accounts.is_some()).then(|| {
+        web::Data::new(admin::ContentEditor::new(
+            config.seed.clone(),
+            audit::AuditLog::from_env(),
+        ))
+    });
+    let metrics_addr = metrics::addr_from_env()?;
+
+    let state = web::Data::new(SiteState::new(config));
+    let reload_secs: u64 = env::var("CONTENT_RELOAD_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_RELOAD_SECS);
+    let reload = (reload_secs > 0).then(|| Duration::from_secs(reload_secs));
+    let content = serve_content(state.clone(), reload);
+
+    // With TLS on, the plain-HTTP listener only redirects, apart from the
+    // probes, which orchestrators send over plain HTTP.
+    let redirect_server = match &tls {
+        Some(tls_config) => {
+            let probe_state = state.clone();
+            let https_port = web::Data::new(tls::HttpsPort(tls_config.https_port));
+            tracing::info!(%addr, "redirecting to https");
+            Some(
+                HttpServer::new(move || {
+                    App::new()
+                        .app_data(probe_state.clone())
+                        .app_data(https_port.clone())
+                        .wrap_fn(logging::access_log)
+                        .service(web::resource("/healthz").route(web::get().to(health::healthz)))
+                        .service(web::resource("/readyz").route(web::get().to(health::readyz)))
+                        .default_service(web::route().to(tls::redirect))
+                })
+                .workers(1)
+                .bind(addr)?
+                .run(),
+            )
+        }
+        None => None,
+    };
+
+    let server = HttpServer::new(move || {
+        App::new()
+            .app_data(state.clone())
+            .app_data(image_proxy.clone())
+            .configure(|cfg| {
+                if let Some(rate_limiter) = &rate_limiter {
+                    cfg.app_data(rate_limiter.clone());
+                }
+            })
+            .wrap_fn(ratelimit::limit)
+            .wrap_fn(security::headers)
+            .wrap_fn(compression::compress_responses)
+            .wrap_fn(metrics::track_requests)
+            .wrap_fn(logging::access_log)
+            .service(web::resource("/healthz").route(web::get().to(health::healthz)))
+            .service(web::resource("/readyz").route(web::get().to(health::readyz)))
+            .service(web::resource("/version").route(web::get().to(health::version)))
+            .service(web::resource("/api/v1/openapi.json").route(web::get().to(api::openapi)))
+            .service(web::resource("/schema/seed.json").route(web::get().to(schema::seed)))
+            .service(web::resource("/schema/promo.json").route(web::get().to(schema::promo)))
+            .service(web::resource("/api/v1/{collection}").route(web::get().to(api::list)))
+            .service(web::resource("/api/v1/{collection}/{slug}").route(web::get().to(api::item)))
+            .configure(|cfg| {
+                // The admin API and editor only exist with credentials
+                // configured. The API scope goes first so `/admin` does not
+                // shadow it.
+                if let Some(editor) = &editor {
+                    cfg.app_data(editor.clone());
+                }
+                if let Some(api_token) = &api_token {
+                    cfg.service(
+                        web::scope("/admin/api")
+                            .app_data(api_token.clone())
+                            .wrap_fn(admin::require_token)
+                            .configure(admin::routes),
+                    );
+                }
+                if let Some(accounts) = &accounts {
+                    cfg.service(
+                        web::scope("/admin")
+                            .app_data(accounts.clone())
+                            .app_data(lint_config.clone())
+                            .app_data(web::FormConfig::default().limit(ADMIN_FORM_LIMIT))
+                            .wrap_fn(auth::require_session)
+                            .configure(admin_ui::routes),
+                    );
+                }
+            })
+            .service(web::resource("/img").route(web::get().to(images::serve)))
+            .service(web::resource("/assets/{path:.*}").route(web::get().to(asset)))
+            .service(web::resource("/").route(web::get().to(page)))
+            .service(web::resource("/index.html").route(web::get().to(page)))
+            .service(web::resource("/ecosystems").route(web::get().to(page)))
+            .service(web::resource("/ecosystems/{slug}").route(web::get().to(page)))
+            .service(web::resource("/tools").route(web::get().to(page)))
+            .service(web::resource("/tools/{slug}").route(web::get().to(page)))
+            .service(web::resource("/events").route(web::get().to(page)))
+            .service(web::resource("/events/{slug}").route(web::get().to(page)))
+            .service(web::resource("/learn").route(web::get().to(page)))
+            .service(web::resource("/learn/{slug}").route(web::get().to(page)))
+            .service(web::resource("/creators").route(web::get().to(page)))
+            .service(web::resource("/creators/{slug}").route(web::get().to(page)))
+            .service(web::resource("/news").route(web::get().to(page)))
+            .service(web::resource("/news/{slug}").route(web::get().to(page)))
+            .service(web::resource("/jobs").route(web::get().to(page)))
+            .default_service(web::route().to(not_found_fallback))
+    });
+    let server = match &tls {
+        Some(tls_config) => {
+            let resolver = tls::resolver(tls_config)?;
+            tokio::spawn(tls::watch_certs(resolver.clone(), tls_config.clone()));
+            let https_addr = std::net::SocketAddr::from(([0, 0, 0, 0], tls_config.https_port));
+            tracing::info!(addr = %https_addr, "listening with tls");
+            server.bind_rustls(https_addr, tls::server_config(resolver))?
+        }
+        None => {
+            tracing::info!(%addr, "listening");
+            server.bind(addr)?
+        }
+    }
+    .run();
+
+    tracing::info!(addr = %metrics_addr, "serving metrics");
+    let metrics_server = HttpServer::new(|| {
+        App::new().service(web::resource("/metrics").route(web::get().to(metrics::serve)))
+    })
+    .workers(1)
+    .bind(metrics_addr)?
+    .run();
+
+    let servers = async {
+        tokio::try_join!(server, metrics_server, run_optional(redirect_server)).map(|_| ())
+    };
+    // The servers run until stopped, unless the content fails to load at
+    // startup. Either way, queued spans are flushed before returning.
+    let result = tokio::select! {
+        result = servers => result,
+        Err(err) = content => Err(err),
+    };
+    if let Some(telemetry) = telemetry {
+        tokio::task::spawn_blocking(move || telemetry.shutdown()).await?;
+    }
+    result
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use actix_web::{
+        body::BoxBody,
+        cookie::Cookie,
+        dev::{ServiceFactory, ServiceRequest, ServiceResponse},
+    };
+
+    /// A `SiteState` serving `seed` from a JSON file at `path`, built the
+    /// way the server builds it.
+    pub(crate) async fn site_state(path: &Path, seed: &Value) -> web::Data<SiteState> {
+        site_state_with_assets(path, seed, Path::new(DEFAULT_ASSETS_DIR)).await
+    }
+
+    /// `site_state`, serving assets from `assets_dir`.
+    pub(crate) async fn site_state_with_assets(
+        path: &Path,
+        seed: &Value,
+        assets_dir: &Path,
+    ) -> web::Data<SiteState> {
+        std::fs::write(path, serde_json::to_vec_pretty(seed).unwrap()).unwrap();
+        let config = Arc::new(SiteConfig {
+            seed: Arc::new(store::JsonFileStore::new(path)),
+            site_url: DEFAULT_SITE_URL.to_string(),
+            assets_dir: assets_dir.to_path_buf(),
+            image_hosts: AllowedHosts::new(images::DEFAULT_ALLOWED_HOSTS),
+            csp: CspConfig::default(),
+        });
+        let state = web::Data::new(SiteState::new(config));
+        reload_site(&state).await.unwrap();
+        state
+    }
+
+    /// A `ContentEditor` writing to the store `state` serves, recording
+    /// edits in `audit_log`.
+    pub(crate) fn editor(state: &SiteState, audit_log: &Path) -> web::Data<admin::ContentEditor> {
+        web::Data::new(admin::ContentEditor::new(
+            state.config.seed.clone(),
+            audit::AuditLog::new(audit_log),
+        ))
+    }
+
+    /// The `/admin` scope the way the server mounts it, signing in
+    /// against `accounts`. The app needs the `SiteState` and an editor.
+    pub(crate) fn admin_scope(
+        accounts: web::Data<auth::Accounts>,
+    ) -> actix_web::Scope<
+        impl ServiceFactory<
+            ServiceRequest,
+            Config = (),
+            Response = ServiceResponse<BoxBody>,
+            Error = actix_web::Error,
+            InitError = (),
+        >,
+    > {
+        web::scope("/admin")
+            .app_data(accounts)
+            .app_data(web::Data::new(lint::LintConfig::default()))
+            .wrap_fn(auth::require_session)
+            .configure(admin_ui::routes)
+    }
+
+    /// A cookie for a new session of `user`, and the session's CSRF token.
+    pub(crate) fn sign_in(accounts: &auth::Accounts, user: &str) -> (Cookie<'static>, String) {
+        let id = accounts.start(user);
+        let csrf = accounts.session(&id).unwrap().csrf;
+        (Cookie::new(auth::SESSION_COOKIE, id), csrf)
+    }
+}
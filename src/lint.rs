@@ -0,0 +1,501 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    env, fmt, io,
+};
+
+use crate::{
+    content::Collection,
+    store::{self, invalid_data, invalid_input},
+    RustDevSeed,
+};
+
+/// How much a finding matters. Rules set to `off` are not run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Severity {
+    Off,
+    Note,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// The SARIF `level` a finding of this severity is reported at.
+    fn sarif_level(self) -> &'static str {
+        match self {
+            Severity::Off => "none",
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Off => "off",
+            Severity::Note => "note",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// An editorial quality check on the entities of one collection.
+struct Rule {
+    id: &'static str,
+    collection: Collection,
+    summary: &'static str,
+    default: Severity,
+    /// The slug of each entity the rule flags, with what is wrong with it.
+    check: fn(&RustDevSeed) -> Vec<(String, String)>,
+}
+
+const RULES: [Rule; 5] = [
+    Rule {
+        id: "tool-missing-logo",
+        collection: Collection::Tools,
+        summary: "Tools have a `media.logo_url` for their cards and pages.",
+        default: Severity::Warning,
+        check: tool_missing_logo,
+    },
+    Rule {
+        id: "post-empty-body",
+        collection: Collection::Posts,
+        summary:
+            "Posts have a `body_md` or an `about`; without either the page falls back to the deck.",
+        default: Severity::Warning,
+        check: post_empty_body,
+    },
+    Rule {
+        id: "creator-invalid-best-start",
+        collection: Collection::Creators,
+        summary: "A creator's `best_start` has both a title and a url, or is left out.",
+        default: Severity::Error,
+        check: creator_invalid_best_start,
+    },
+    Rule {
+        id: "event-missing-start",
+        collection: Collection::Events,
+        summary: "Events have a `starts_on` date.",
+        default: Severity::Warning,
+        check: event_missing_start,
+    },
+    Rule {
+        id: "learning-path-zero-duration",
+        collection: Collection::LearningPaths,
+        summary: "A learning path's `duration_hours` is above 0, or is left out.",
+        default: Severity::Warning,
+        check: learning_path_zero_duration,
+    },
+];
+
+fn is_blank(text: Option<&str>) -> bool {
+    text.is_none_or(|text| text.trim().is_empty())
+}
+
+fn tool_missing_logo(seed: &RustDevSeed) -> Vec<(String, String)> {
+    seed.tools
+        .iter()
+        .filter(|tool| is_blank(tool.media.as_ref().and_then(|m| m.logo_url.as_deref())))
+        .map(|tool| (tool.slug.clone(), format!("{} has no logo", tool.name)))
+        .collect()
+}
+
+fn post_empty_body(seed: &RustDevSeed) -> Vec<(String, String)> {
+    seed.posts
+        .iter()
+        .filter(|post| is_blank(Some(&post.body_md)) && is_blank(post.about.as_deref()))
+        .map(|post| {
+            (
+                post.slug.clone(),
+                format!("\"{}\" has no body_md or about", post.title),
+            )
+        })
+        .collect()
+}
+
+fn creator_invalid_best_start(seed: &RustDevSeed) -> Vec<(String, String)> {
+    seed.creators
+        .iter()
+        .filter_map(|creator| {
+            let best_start = creator.best_start.as_ref()?;
+            let missing = match (
+                is_blank(best_start.title.as_deref()),
+                is_blank(best_start.url.as_deref()),
+            ) {
+                (false, false) => return None,
+                (true, true) => "title or url",
+                (true, false) => "title",
+                (false, true) => "url",
+            };
+            Some((
+                creator.slug.clone(),
+                format!("`best_start` has no {missing}"),
+            ))
+        })
+        .collect()
+}
+
+fn event_missing_start(seed: &RustDevSeed) -> Vec<(String, String)> {
+    seed.events
+        .iter()
+        .filter(|event| is_blank(event.starts_on.as_deref()))
+        .map(|event| {
+            (
+                event.slug.clone(),
+                format!("{} has no start date", event.title),
+            )
+        })
+        .collect()
+}
+
+fn learning_path_zero_duration(seed: &RustDevSeed) -> Vec<(String, String)> {
+    seed.learning_paths
+        .iter()
+        .filter(|path| path.duration_hours == Some(0))
+        .map(|path| {
+            (
+                path.slug.clone(),
+                format!("\"{}\" takes 0 hours", path.title),
+            )
+        })
+        .collect()
+}
+
+/// The lint config file as written.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    #[serde(default)]
+    rules: HashMap<String, Severity>,
+    #[serde(default)]
+    suppress: HashMap<String, Vec<String>>,
+}
+
+/// Rule severities and the findings editors have chosen to accept.
+pub(crate) struct LintConfig {
+    source: Option<String>,
+    severities: HashMap<&'static str, Severity>,
+    /// `(collection/slug, rule)` pairs not to report.
+    suppressed: HashSet<(String, &'static str)>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            source: None,
+            severities: RULES.iter().map(|rule| (rule.id, rule.default)).collect(),
+            suppressed: HashSet::new(),
+        }
+    }
+}
+
+impl LintConfig {
+    /// `LINT_CONFIG` names a TOML file that overrides rule severities and
+    /// suppresses findings on particular entities:
+    ///
+    /// ```toml
+    /// [rules]
+    /// tool-missing-logo = "error"
+    /// learning-path-zero-duration = "off"
+    ///
+    /// [suppress]
+    /// "events/cosmoverse-2026" = ["event-missing-start"]
+    /// ```
+    ///
+    /// Without it every rule runs at its default severity.
+    pub(crate) fn from_env() -> io::Result<Self> {
+        let Ok(path) = env::var("LINT_CONFIG") else {
+            return Ok(Self::default());
+        };
+        let text = std::fs::read_to_string(&path)
+            .map_err(|err| io::Error::new(err.kind(), format!("{path}: {err}")))?;
+        let file: ConfigFile =
+            toml::from_str(&text).map_err(|err| invalid_data(format!("{path}: {err}")))?;
+        let rule = |id: &str| {
+            RULES
+                .iter()
+                .find(|rule| rule.id == id)
+                .ok_or_else(|| invalid_data(format!("{path}: unknown lint rule `{id}`")))
+        };
+
+        let mut config = Self {
+            source: Some(path.clone()),
+            ..Self::default()
+        };
+        for (id, severity) in &file.rules {
+            config.severities.insert(rule(id)?.id, *severity);
+        }
+        for (entity, ids) in &file.suppress {
+            let collection = entity
+                .split_once('/')
+                .filter(|(_, slug)| !slug.is_empty())
+                .and_then(|(collection, _)| Collection::from_path(collection))
+                .ok_or_else(|| {
+                    invalid_data(format!(
+                        "{path}: `{entity}` is not a `collection/slug`, as in `tools/anchor`"
+                    ))
+                })?;
+            for id in ids {
+                let rule = rule(id)?;
+                if rule.collection != collection {
+                    return Err(invalid_data(format!(
+                        "{path}: `{id}` checks {}, so it never applies to `{entity}`",
+                        rule.collection.path()
+                    )));
+                }
+                config.suppressed.insert((entity.clone(), rule.id));
+            }
+        }
+        Ok(config)
+    }
+
+    /// Where the config was read from, for the admin pages.
+    pub(crate) fn describe(&self) -> String {
+        match &self.source {
+            Some(path) => path.clone(),
+            None => "the default severities".to_string(),
+        }
+    }
+
+    fn severity(&self, rule: &Rule) -> Severity {
+        self.severities
+            .get(rule.id)
+            .copied()
+            .unwrap_or(rule.default)
+    }
+}
+
+/// One entity one rule flags.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct Finding {
+    pub(crate) rule: &'static str,
+    pub(crate) severity: Severity,
+    /// The collection's path, as in `/admin/<collection>/<slug>`.
+    pub(crate) collection: &'static str,
+    pub(crate) slug: String,
+    pub(crate) message: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct Report {
+    /// Most severe first, then in rule and content order.
+    pub(crate) findings: Vec<Finding>,
+    /// Findings the lint config suppresses.
+    pub(crate) suppressed: Vec<Finding>,
+}
+
+/// Runs every rule `config` leaves on over `seed`.
+pub(crate) fn lint(seed: &RustDevSeed, config: &LintConfig) -> Report {
+    let mut report = Report::default();
+    for rule in &RULES {
+        let severity = config.severity(rule);
+        if severity == Severity::Off {
+            continue;
+        }
+        for (slug, message) in (rule.check)(seed) {
+            let entity = format!("{}/{slug}", rule.collection.path());
+            let finding = Finding {
+                rule: rule.id,
+                severity,
+                collection: rule.collection.path(),
+                slug,
+                message,
+            };
+            if config.suppressed.contains(&(entity, rule.id)) {
+                report.suppressed.push(finding);
+            } else {
+                report.findings.push(finding);
+            }
+        }
+    }
+    report
+        .findings
+        .sort_by_key(|finding| Reverse(finding.severity));
+    report
+}
+
+impl Report {
+    pub(crate) fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+
+    /// `2 errors, 30 warnings, 0 notes, 1 suppressed`.
+    pub(crate) fn summary(&self) -> String {
+        let plural = |n: usize, word: &str| {
+            if n == 1 {
+                format!("{n} {word}")
+            } else {
+                format!("{n} {word}s")
+            }
+        };
+        format!(
+            "{}, {}, {}, {} suppressed",
+            plural(self.count(Severity::Error), "error"),
+            plural(self.count(Severity::Warning), "warning"),
+            plural(self.count(Severity::Note), "note"),
+            self.suppressed.len()
+        )
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "errors": self.count(Severity::Error),
+            "warnings": self.count(Severity::Warning),
+            "notes": self.count(Severity::Note),
+            "findings": self.findings,
+            "suppressed": self.suppressed,
+        })
+    }
+
+    /// A SARIF 2.1.0 log. Entities have no line to point at, so findings
+    /// carry a logical location, `collection/slug`; suppressed ones are
+    /// included and marked as such.
+    fn to_sarif(&self, config: &LintConfig) -> Value {
+        let rules: Vec<Value> = RULES
+            .iter()
+            .map(|rule| {
+                let severity = config.severity(rule);
+                json!({
+                    "id": rule.id,
+                    "shortDescription": { "text": rule.summary },
+                    "defaultConfiguration": {
+                        "enabled": severity != Severity::Off,
+                        "level": severity.sarif_level(),
+                    },
+                })
+            })
+            .collect();
+        let result = |finding: &Finding, suppressed: bool| {
+            let mut result = json!({
+                "ruleId": finding.rule,
+                "ruleIndex": RULES.iter().position(|rule| rule.id == finding.rule),
+                "level": finding.severity.sarif_level(),
+                "message": { "text": finding.message },
+                "locations": [{
+                    "logicalLocations": [{
+                        "name": finding.slug,
+                        "fullyQualifiedName": format!("{}/{}", finding.collection, finding.slug),
+                        "kind": "object",
+                    }],
+                }],
+            });
+            if suppressed {
+                result["suppressions"] = json!([{ "kind": "external" }]);
+            }
+            result
+        };
+        let results: Vec<Value> = self
+            .findings
+            .iter()
+            .map(|finding| result(finding, false))
+            .chain(self.suppressed.iter().map(|finding| result(finding, true)))
+            .collect();
+        json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "rustdev lint",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "rules": rules,
+                    },
+                },
+                "results": results,
+            }],
+        })
+    }
+}
+
+/// `rustdev lint [--format text|json|sarif]`: checks the content in the
+/// configured store against the editorial rules. Fails if anything is
+/// reported at `error`.
+pub(crate) fn lint_command(args: &[String]) -> io::Result<()> {
+    let usage = || invalid_input("usage: rustdev lint [--format text|json|sarif]");
+    let format = match args {
+        [] => "text",
+        [flag, format] if flag == "--format" => format.as_str(),
+        _ => return Err(usage()),
+    };
+    if !matches!(format, "text" | "json" | "sarif") {
+        return Err(usage());
+    }
+    let config = LintConfig::from_env()?;
+    let (seed, _) = store::from_env()?.seed()?;
+    let report = lint(&seed, &config);
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&report.to_json())?),
+        "sarif" => println!(
+            "{}",
+            serde_json::to_string_pretty(&report.to_sarif(&config))?
+        ),
+        _ => {
+            for finding in &report.findings {
+                println!(
+                    "{}: {}/{}: {} [{}]",
+                    finding.severity,
+                    finding.collection,
+                    finding.slug,
+                    finding.message,
+                    finding.rule
+                );
+            }
+            println!("{}", report.summary());
+        }
+    }
+    match report.count(Severity::Error) {
+        0 => Ok(()),
+        errors => Err(invalid_data(format!(
+            "the content has {errors} lint error{}",
+            if errors == 1 { "" } else { "s" }
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrate;
+
+    fn slugs(findings: Vec<(String, String)>) -> Vec<String> {
+        findings.into_iter().map(|(slug, _)| slug).collect()
+    }
+
+    #[test]
+    fn posts_with_an_about_have_a_body() {
+        let seed = migrate::read_seed(json!({
+            "version": "rustdev-hub-seed-v7.0",
+            "posts": [
+                {"slug": "written", "title": "Written", "body_md": "Text."},
+                {"slug": "about", "title": "About", "about": "Text."},
+                {"slug": "deck-only", "title": "Deck only", "deck": "A deck.", "about": " "},
+            ],
+        }))
+        .unwrap();
+        assert_eq!(slugs(post_empty_body(&seed)), ["deck-only"]);
+    }
+
+    #[test]
+    fn only_an_explicit_zero_duration_is_flagged() {
+        let seed = migrate::read_seed(json!({
+            "version": "rustdev-hub-seed-v7.0",
+            "learning_paths": [
+                {"slug": "unknown", "title": "Unknown"},
+                {"slug": "zero", "title": "Zero", "duration_hours": 0},
+                {"slug": "timed", "title": "Timed", "duration_hours": 6},
+            ],
+        }))
+        .unwrap();
+        assert_eq!(slugs(learning_path_zero_duration(&seed)), ["zero"]);
+    }
+}
@@ -0,0 +1,164 @@
+use actix_web::{
+    body::{BodySize, BoxBody, MessageBody},
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::header::{self, HeaderName, HeaderValue},
+    Error, HttpMessage, HttpRequest,
+};
+use std::{env, future::Future, time::Instant};
+use tracing::Instrument;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use crate::{formats::Format, telemetry::Telemetry};
+
+pub(crate) const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+/// Longest incoming `X-Request-Id` that is propagated rather than replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Installs the global subscriber. `LOG_LEVEL` takes a level or an
+/// `EnvFilter` directive (default `info`); `LOG_FORMAT` is `json` (default)
+/// or `text`. Spans are also exported over OTLP when a collector is
+/// configured; the returned handle flushes them on shutdown.
+pub(crate) fn init() -> Option<Telemetry> {
+    let filter = EnvFilter::try_new(env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()))
+        .unwrap_or_else(|err| {
+            eprintln!("Invalid LOG_LEVEL ({err}), using info");
+            EnvFilter::new("info")
+        });
+    let json = match env::var("LOG_FORMAT").as_deref() {
+        Ok("text") => false,
+        Ok("json") | Err(_) => true,
+        Ok(other) => {
+            eprintln!("Unknown LOG_FORMAT ({other}), using json");
+            true
+        }
+    };
+    let fmt = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+    let fmt = if json {
+        // Span context goes to the collector, not into every log line.
+        fmt.json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(false)
+            .boxed()
+    } else {
+        fmt.boxed()
+    };
+
+    let telemetry = Telemetry::from_env();
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt)
+        .with(telemetry.as_ref().map(Telemetry::layer))
+        .init();
+    if telemetry.is_some() {
+        tracing::info!("exporting spans over OTLP");
+    }
+    telemetry
+}
+
+/// The request's ID, stored in its extensions by `access_log`.
+#[derive(Clone, Debug)]
+struct RequestId(String);
+
+/// The ID `access_log` assigned to `req`, for correlating other log lines
+/// with its access log entry.
+pub(crate) fn request_id(req: &HttpRequest) -> String {
+    req.extensions()
+        .get::<RequestId>()
+        .map(|id| id.0.clone())
+        .unwrap_or_default()
+}
+
+/// Records the negotiated representation for the access log.
+pub(crate) fn record_format(req: &HttpRequest, format: Format) {
+    req.extensions_mut().insert(format);
+}
+
+/// Keeps a well-formed incoming ID so traces can span the proxy in front of
+/// the site; anything else is replaced by a fresh UUID.
+fn incoming_or_new(req: &ServiceRequest) -> String {
+    req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Middleware assigning every request an ID, echoed in `X-Request-Id`,
+/// opening the `request` span the handler's spans nest under, and emitting
+/// one structured access log line per response.
+pub(crate) fn access_log<S>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error>,
+{
+    let started = Instant::now();
+    let id = incoming_or_new(&req);
+    req.extensions_mut().insert(RequestId(id.clone()));
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+    let host = req.connection_info().host().to_string();
+    let span = tracing::info_span!(
+        "request",
+        otel.name = %method,
+        otel.kind = "server",
+        request_id = %id,
+        http.request.method = %method,
+        url.path = %path,
+        http.route = tracing::field::Empty,
+        http.response.status_code = tracing::field::Empty,
+    );
+    let fut = span.in_scope(|| srv.call(req));
+    async move {
+        let mut res = fut.await?;
+        if let Ok(value) = HeaderValue::from_str(&id) {
+            res.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+
+        let request = res.request();
+        let route = request.match_pattern();
+        let slug = request.match_info().get("slug").map(str::to_string);
+        let format = request.extensions().get::<Format>().map(|f| f.name());
+        let bytes = match res.response().body().size() {
+            BodySize::Sized(len) => Some(len),
+            _ => None,
+        };
+        let span = tracing::Span::current();
+        // Routing happens inside the app, after the span is named, so the
+        // pattern is only attached as an attribute.
+        if let Some(route) = &route {
+            span.record("http.route", route.as_str());
+        }
+        span.record("http.response.status_code", res.status().as_u16());
+        let encoding = res
+            .headers()
+            .get(header::CONTENT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        tracing::info!(
+            target: "access",
+            request_id = %id,
+            method = %method,
+            route = route.as_deref(),
+            path = %path,
+            slug = slug.as_deref(),
+            status = res.status().as_u16(),
+            latency_ms = started.elapsed().as_secs_f64() * 1000.0,
+            bytes,
+            format,
+            encoding = encoding.as_deref(),
+            host = %host,
+        );
+        Ok(res)
+    }
+    .instrument(span)
+}
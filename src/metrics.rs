@@ -0,0 +1,259 @@
+use actix_web::{
+    body::BoxBody,
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::header,
+    Error, HttpResponse,
+};
+use prometheus::{
+    core::Collector, Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry,
+    TextEncoder,
+};
+use std::{env, future::Future, io, net::SocketAddr, sync::LazyLock, time::Instant};
+
+use crate::store::invalid_input;
+
+/// Process-wide metrics, exposed at `/metrics` in the Prometheus text format.
+struct Metrics {
+    registry: Registry,
+    requests: IntCounterVec,
+    latency: HistogramVec,
+    render_errors: IntCounterVec,
+    not_found: IntCounterVec,
+    reloads: IntCounterVec,
+    cache: IntCounterVec,
+    rate_limited: IntCounterVec,
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Where `/metrics` is served unless `METRICS_ADDR` says otherwise. It has a
+/// listener of its own, on loopback, so it is not public.
+pub(crate) const DEFAULT_METRICS_ADDR: &str = "127.0.0.1:9464";
+
+/// Label used for requests no route pattern matched.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+impl Metrics {
+    fn new() -> Self {
+        let registry =
+            Registry::new_custom(Some("rustdev".to_string()), None).expect("metrics registry");
+        let requests = IntCounterVec::new(
+            Opts::new(
+                "http_requests_total",
+                "HTTP requests by route pattern and status.",
+            ),
+            &["method", "route", "status"],
+        )
+        .expect("http_requests_total");
+        let latency = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time to produce a response, by route pattern and status.",
+            )
+            .buckets(vec![
+                0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+            ]),
+            &["method", "route", "status"],
+        )
+        .expect("http_request_duration_seconds");
+        let render_errors = IntCounterVec::new(
+            Opts::new("template_render_errors_total", "Failed template renders."),
+            &["template"],
+        )
+        .expect("template_render_errors_total");
+        let not_found = IntCounterVec::new(
+            Opts::new("not_found_total", "404 responses by collection."),
+            &["collection"],
+        )
+        .expect("not_found_total");
+        let reloads = IntCounterVec::new(
+            Opts::new("content_reloads_total", "Content loads by outcome."),
+            &["result"],
+        )
+        .expect("content_reloads_total");
+        let cache = IntCounterVec::new(
+            Opts::new("cache_lookups_total", "Cache lookups by cache and outcome."),
+            &["cache", "result"],
+        )
+        .expect("cache_lookups_total");
+        let rate_limited = IntCounterVec::new(
+            Opts::new(
+                "rate_limited_total",
+                "Requests rejected with 429, by budget.",
+            ),
+            &["class"],
+        )
+        .expect("rate_limited_total");
+
+        for collector in [
+            Box::new(requests.clone()) as Box<dyn Collector>,
+            Box::new(latency.clone()),
+            Box::new(render_errors.clone()),
+            Box::new(not_found.clone()),
+            Box::new(reloads.clone()),
+            Box::new(cache.clone()),
+            Box::new(rate_limited.clone()),
+        ] {
+            registry.register(collector).expect("register collector");
+        }
+
+        Self {
+            registry,
+            requests,
+            latency,
+            render_errors,
+            not_found,
+            reloads,
+            cache,
+            rate_limited,
+        }
+    }
+
+    /// `cache_hit_ratio` per cache, derived from the lookup counters at
+    /// scrape time.
+    fn hit_ratios(&self) -> String {
+        let mut caches: Vec<(String, u64, u64)> = Vec::new();
+        for family in self.cache.collect() {
+            for metric in family.get_metric() {
+                let mut cache = "";
+                let mut hit = false;
+                for label in metric.get_label() {
+                    match label.name() {
+                        "cache" => cache = label.value(),
+                        "result" => hit = label.value() == "hit",
+                        _ => {}
+                    }
+                }
+                let count = metric.get_counter().get_value() as u64;
+                let entry = match caches.iter_mut().find(|(name, _, _)| name == cache) {
+                    Some(entry) => entry,
+                    None => {
+                        caches.push((cache.to_string(), 0, 0));
+                        caches.last_mut().expect("just pushed")
+                    }
+                };
+                if hit {
+                    entry.1 += count;
+                } else {
+                    entry.2 += count;
+                }
+            }
+        }
+
+        let mut out = String::from(
+            "# HELP rustdev_cache_hit_ratio Share of cache lookups that hit.\n\
+             # TYPE rustdev_cache_hit_ratio gauge\n",
+        );
+        for (cache, hits, misses) in caches {
+            let total = hits + misses;
+            let ratio = if total == 0 {
+                0.0
+            } else {
+                hits as f64 / total as f64
+            };
+            out.push_str(&format!(
+                "rustdev_cache_hit_ratio{{cache=\"{cache}\"}} {ratio}\n"
+            ));
+        }
+        out
+    }
+}
+
+pub(crate) fn render_error(template: &str) {
+    METRICS.render_errors.with_label_values(&[template]).inc();
+}
+
+pub(crate) fn not_found(collection: Option<&str>) {
+    METRICS
+        .not_found
+        .with_label_values(&[collection.unwrap_or("none")])
+        .inc();
+}
+
+pub(crate) fn content_reload(success: bool) {
+    let result = if success { "success" } else { "failure" };
+    METRICS.reloads.with_label_values(&[result]).inc();
+}
+
+/// Records a lookup in one of the named caches (`pages`, `images`).
+pub(crate) fn cache_lookup(cache: &str, hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    METRICS.cache.with_label_values(&[cache, result]).inc();
+}
+
+pub(crate) fn rate_limited(class: &str) {
+    METRICS.rate_limited.with_label_values(&[class]).inc();
+}
+
+/// Middleware counting and timing every request by its route pattern, so
+/// `/tools/anchor` and `/tools/solana` share the `/tools/{slug}` series.
+pub(crate) fn track_requests<S>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error>,
+{
+    let started = Instant::now();
+    let method = req.method().to_string();
+    let fut = srv.call(req);
+    async move {
+        let res = fut.await?;
+        let route = res
+            .request()
+            .match_pattern()
+            .unwrap_or_else(|| UNMATCHED_ROUTE.to_string());
+        let status = res.status().as_u16().to_string();
+        let labels = [method.as_str(), route.as_str(), status.as_str()];
+        METRICS.requests.with_label_values(&labels).inc();
+        METRICS
+            .latency
+            .with_label_values(&labels)
+            .observe(started.elapsed().as_secs_f64());
+        Ok(res)
+    }
+}
+
+/// The metrics listener's address: `METRICS_ADDR`, or
+/// `DEFAULT_METRICS_ADDR`.
+pub(crate) fn addr_from_env() -> io::Result<SocketAddr> {
+    parse_addr(env::var("METRICS_ADDR").ok().as_deref())
+}
+
+fn parse_addr(value: Option<&str>) -> io::Result<SocketAddr> {
+    let value = value.unwrap_or(DEFAULT_METRICS_ADDR);
+    value
+        .parse()
+        .map_err(|_| invalid_input(format!("METRICS_ADDR must be <ip>:<port>, got {value:?}")))
+}
+
+/// `GET /metrics`.
+pub(crate) async fn serve() -> HttpResponse {
+    let encoder = TextEncoder::new();
+    let mut body = Vec::new();
+    if let Err(err) = encoder.encode(&METRICS.registry.gather(), &mut body) {
+        tracing::error!(%err, "metrics encode failed");
+        return HttpResponse::InternalServerError().finish();
+    }
+    body.extend_from_slice(METRICS.hit_ratios().as_bytes());
+    HttpResponse::Ok()
+        .append_header((header::CONTENT_TYPE, encoder.format_type()))
+        .append_header((header::CACHE_CONTROL, "no-store"))
+        .body(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics_listen_on_loopback_unless_configured() {
+        assert!(parse_addr(None).unwrap().ip().is_loopback());
+        assert_eq!(
+            parse_addr(Some("0.0.0.0:9100")).unwrap(),
+            "0.0.0.0:9100".parse().unwrap()
+        );
+        let err = parse_addr(Some("9100")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
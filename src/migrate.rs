@@ -0,0 +1,210 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::{fmt, io};
+
+use crate::{
+    store::{self, invalid_data},
+    RustDevSeed,
+};
+
+/// What `version` strings start with; the rest is `<major>.<minor>`,
+/// optionally followed by a `.`-separated tag that does not affect the
+/// schema, as in `rustdev-hub-seed-v6.2.tagged-expanded`.
+const VERSION_PREFIX: &str = "rustdev-hub-seed-v";
+/// The schema `RustDevSeed` reads. Seeds are migrated up to it on load.
+pub(crate) const CURRENT: SeedVersion = SeedVersion { major: 7, minor: 0 };
+/// Who `rustdev migrate` attributes the upgraded content to.
+const MIGRATE_AUTHOR: &str = "rustdev";
+
+/// A seed schema version, compared by number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct SeedVersion {
+    major: u32,
+    minor: u32,
+}
+
+impl SeedVersion {
+    /// Parses a `version` string, returning the tag after `<major>.<minor>`
+    /// alongside the version.
+    fn parse_tagged(version: &str) -> Result<(Self, Option<&str>), String> {
+        let invalid = || format!("`{version}` is not a seed version like `{CURRENT}`");
+        let mut parts = version
+            .strip_prefix(VERSION_PREFIX)
+            .ok_or_else(invalid)?
+            .splitn(3, '.');
+        let major = parts.next().and_then(|major| major.parse().ok());
+        let minor = parts.next().map_or(Some(0), |minor| minor.parse().ok());
+        match (major, minor) {
+            (Some(major), Some(minor)) => Ok((Self { major, minor }, parts.next())),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for SeedVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{VERSION_PREFIX}{}.{}", self.major, self.minor)
+    }
+}
+
+/// One step of the chain: brings a seed older than `to` up to `to`.
+struct Migration {
+    to: SeedVersion,
+    apply: fn(&mut Map<String, Value>) -> Result<(), String>,
+}
+
+/// Every step, oldest first. A seed runs the steps newer than its version.
+const MIGRATIONS: &[Migration] = &[Migration {
+    to: SeedVersion { major: 7, minor: 0 },
+    apply: rename_legacy_collections,
+}];
+
+/// Collections v7.0 renamed, as `(old, new)`, to name them as the API does.
+pub(crate) const LEGACY_COLLECTIONS: [(&str, &str); 3] = [
+    ("protocols", "ecosystems"),
+    ("news", "posts"),
+    ("jobs_sources", "job_sources"),
+];
+
+fn rename_legacy_collections(seed: &mut Map<String, Value>) -> Result<(), String> {
+    for (old, new) in LEGACY_COLLECTIONS {
+        if seed.contains_key(old) && seed.contains_key(new) {
+            return Err(format!("the seed has both `{old}` and `{new}`"));
+        }
+    }
+    // Rebuilt rather than removed and reinserted, so keys keep their place.
+    *seed = std::mem::take(seed)
+        .into_iter()
+        .map(|(key, value)| {
+            let renamed = LEGACY_COLLECTIONS.iter().find(|(old, _)| *old == key);
+            (renamed.map_or(key, |(_, new)| new.to_string()), value)
+        })
+        .collect();
+    Ok(())
+}
+
+/// Runs the migrations `seed` needs to reach `CURRENT` and returns the
+/// `version` string it declared. A migrated seed is stamped with `CURRENT`,
+/// keeping the declared tag. A seed without a version is taken to predate
+/// all of them; one newer than `CURRENT` is refused, since fields this
+/// server does not know could be lost or misread.
+pub(crate) fn upgrade(seed: &mut Value) -> io::Result<Option<String>> {
+    let fields = seed
+        .as_object_mut()
+        .ok_or_else(|| invalid_data("the seed must be an object"))?;
+    let declared = match fields.get("version") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(version)) => Some(version.clone()),
+        Some(other) => return Err(invalid_data(format!("`version` {other} is not a string"))),
+    };
+    let (version, tag) = match &declared {
+        Some(declared) => {
+            let (version, tag) = SeedVersion::parse_tagged(declared).map_err(invalid_data)?;
+            (Some(version), tag)
+        }
+        None => (None, None),
+    };
+    if let Some(version) = version.filter(|version| *version > CURRENT) {
+        return Err(invalid_data(format!(
+            "the seed is at {version}, newer than {CURRENT}, the latest this server reads; \
+             upgrade rustdev to serve it"
+        )));
+    }
+    if version == Some(CURRENT) {
+        return Ok(declared);
+    }
+    let stamp = match tag {
+        Some(tag) => format!("{CURRENT}.{tag}"),
+        None => CURRENT.to_string(),
+    };
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| Some(migration.to) > version)
+    {
+        (migration.apply)(fields).map_err(|err| {
+            invalid_data(format!("migrating the seed to {}: {err}", migration.to))
+        })?;
+    }
+    if declared.is_none() {
+        // Where the seed file keeps it.
+        *fields = [("version".to_string(), Value::Null)]
+            .into_iter()
+            .chain(std::mem::take(fields))
+            .collect();
+    }
+    fields.insert("version".into(), stamp.into());
+    Ok(declared)
+}
+
+/// `seed` upgraded to `CURRENT` and read as `RustDevSeed`. Its `version`
+/// stays the one the content declared, which is what `/version` reports.
+pub(crate) fn read_seed(mut seed: Value) -> io::Result<RustDevSeed> {
+    let declared = upgrade(&mut seed)?;
+    let mut seed = RustDevSeed::deserialize(&seed).map_err(invalid_data)?;
+    seed.version = declared;
+    Ok(seed)
+}
+
+/// `rustdev migrate`: upgrades the content in the configured store to
+/// `CURRENT` and writes it back.
+pub(crate) fn migrate_command(args: &[String]) -> io::Result<()> {
+    if !args.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "usage: rustdev migrate",
+        ));
+    }
+    let store = store::from_env()?;
+    let mut seed = store.load()?.seed;
+    let declared = upgrade(&mut seed)?;
+    let stamp = seed["version"].as_str().unwrap_or_default().to_string();
+    if declared.as_deref() == Some(stamp.as_str()) {
+        println!("{} is already at {stamp}", store.describe());
+        return Ok(());
+    }
+    RustDevSeed::deserialize(&seed)
+        .map_err(|err| invalid_data(format!("the seed does not load after migrating: {err}")))?;
+    let declared = declared.unwrap_or_else(|| "an unversioned seed".to_string());
+    store.replace(
+        &seed,
+        MIGRATE_AUTHOR,
+        &format!("Migrate content from {declared} to {stamp}"),
+    )?;
+    println!("Migrated {} from {declared} to {stamp}", store.describe());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn upgrade_keeps_the_declared_version_and_its_tag() {
+        let mut seed = json!({"version": "rustdev-hub-seed-v6.2.tagged-expanded", "news": []});
+        let declared = upgrade(&mut seed).unwrap();
+        assert_eq!(
+            declared.as_deref(),
+            Some("rustdev-hub-seed-v6.2.tagged-expanded")
+        );
+        assert_eq!(seed["version"], "rustdev-hub-seed-v7.0.tagged-expanded");
+        assert!(seed.get("posts").is_some() && seed.get("news").is_none());
+    }
+
+    #[test]
+    fn read_seed_reports_the_declared_version() {
+        let seed = read_seed(json!({"version": "rustdev-hub-seed-v6.2.tagged-expanded"})).unwrap();
+        assert_eq!(
+            seed.version.as_deref(),
+            Some("rustdev-hub-seed-v6.2.tagged-expanded")
+        );
+    }
+
+    #[test]
+    fn upgrade_leaves_current_seeds_alone() {
+        let mut seed = json!({"version": "rustdev-hub-seed-v7.0", "posts": []});
+        let declared = upgrade(&mut seed).unwrap();
+        assert_eq!(declared.as_deref(), Some("rustdev-hub-seed-v7.0"));
+        assert_eq!(seed["version"], "rustdev-hub-seed-v7.0");
+    }
+}
@@ -0,0 +1,525 @@
+use actix_web::{
+    body::BoxBody,
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::{header, StatusCode},
+    web, Error, HttpRequest, HttpResponse,
+};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    env,
+    future::Future,
+    io,
+    net::IpAddr,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    formats::{self, Format},
+    metrics,
+};
+
+/// Buckets idle long enough to have refilled are dropped once the map grows
+/// past this many clients.
+const SWEEP_THRESHOLD: usize = 10_000;
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Requests are budgeted separately by what they cost to serve and how
+/// attractive they are to scrape: HTML pages, the machine-readable page
+/// variants, and the APIs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Class {
+    Html,
+    Json,
+    Api,
+}
+
+impl Class {
+    fn name(self) -> &'static str {
+        match self {
+            Class::Html => "html",
+            Class::Json => "json",
+            Class::Api => "api",
+        }
+    }
+
+    /// `None` for probes and static assets, which are never limited.
+    fn of(req: &HttpRequest) -> Option<Self> {
+        let path = req.path();
+        if matches!(path, "/healthz" | "/readyz" | "/version")
+            || path.starts_with(crate::assets::ASSETS_PREFIX)
+        {
+            return None;
+        }
+        if path.starts_with("/api/") || path.starts_with("/admin/api/") {
+            return Some(Class::Api);
+        }
+        // JSON, Markdown and text variants carry the whole dataset.
+        match formats::negotiate(req, &formats::PAGE_FORMATS) {
+            Ok(Format::Html) | Err(_) => Some(Class::Html),
+            Ok(_) => Some(Class::Json),
+        }
+    }
+}
+
+/// A token bucket's shape: up to `burst` requests at once, refilled
+/// continuously at the configured rate.
+#[derive(Clone, Copy, Debug)]
+struct Budget {
+    burst: f64,
+    per_second: f64,
+}
+
+impl Budget {
+    /// `RATE_LIMIT_<CLASS>` is `<per minute>` or `<per minute>:<burst>`;
+    /// `0` turns limiting off for the class.
+    fn from_env(class: Class, per_minute: u32, burst: u32) -> io::Result<Option<Self>> {
+        let var = format!("RATE_LIMIT_{}", class.name().to_ascii_uppercase());
+        let (per_minute, burst) = match env::var(&var) {
+            Ok(value) => {
+                let invalid = || {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{var} must be <per minute>[:<burst>], got {value:?}"),
+                    )
+                };
+                let (rate, burst) = match value.split_once(':') {
+                    Some((rate, burst)) => (rate, Some(burst)),
+                    None => (value.as_str(), None),
+                };
+                let rate: u32 = rate.trim().parse().map_err(|_| invalid())?;
+                let burst = match burst {
+                    Some(burst) => burst.trim().parse().map_err(|_| invalid())?,
+                    None => rate.max(1),
+                };
+                (rate, burst)
+            }
+            Err(_) => (per_minute, burst),
+        };
+        Ok((per_minute > 0).then(|| Self {
+            burst: f64::from(burst.max(1)),
+            per_second: f64::from(per_minute) / 60.0,
+        }))
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, budget: Budget, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * budget.per_second).min(budget.burst);
+        self.updated = now;
+    }
+}
+
+/// An IPv4 or IPv6 network in CIDR notation; a bare address is a /32 or
+/// /128.
+#[derive(Clone, Copy, Debug)]
+struct Cidr {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    fn parse(raw: &str) -> Option<Self> {
+        let (addr, prefix) = match raw.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (raw, None),
+        };
+        let network: IpAddr = addr.trim().parse().ok()?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.trim().parse().ok().filter(|p| *p <= max)?,
+            None => max,
+        };
+        Some(Self { network, prefix })
+    }
+
+    /// IPv4 addresses also match their IPv4-mapped IPv6 form, either way
+    /// round, since dual-stack listeners report IPv4 peers mapped.
+    fn contains(&self, ip: IpAddr) -> bool {
+        let ip = match (self.network, ip) {
+            (IpAddr::V4(_), IpAddr::V6(v6)) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            (IpAddr::V6(_), IpAddr::V4(v4)) => IpAddr::V6(v4.to_ipv6_mapped()),
+            _ => ip,
+        };
+        match (self.network, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix))
+                    .unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+fn cidr_list(var: &str) -> io::Result<Vec<Cidr>> {
+    let Ok(value) = env::var(var) else {
+        return Ok(Vec::new());
+    };
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            Cidr::parse(entry).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{var}: invalid address or CIDR {entry:?}"),
+                )
+            })
+        })
+        .collect()
+}
+
+fn listed(list: &[Cidr], ip: IpAddr) -> bool {
+    list.iter().any(|cidr| cidr.contains(ip))
+}
+
+struct Buckets {
+    by_client: HashMap<(IpAddr, Class), Bucket>,
+    swept: Instant,
+}
+
+/// Per-client token buckets. Clients are identified by IP: the peer address,
+/// or the nearest `X-Forwarded-For` hop that is not a trusted proxy when the
+/// peer is one.
+pub(crate) struct RateLimiter {
+    budgets: HashMap<Class, Budget>,
+    trusted_proxies: Vec<Cidr>,
+    allowlist: Vec<Cidr>,
+    buckets: Mutex<Buckets>,
+}
+
+impl RateLimiter {
+    /// Budgets default to 300/min (burst 60) for HTML, 60/min (burst 20)
+    /// for machine-readable page variants and 120/min (burst 30) for the
+    /// API. `TRUSTED_PROXIES` and `RATE_LIMIT_ALLOW` take comma-separated
+    /// addresses or CIDRs. `RATE_LIMIT=off` disables limiting.
+    pub(crate) fn from_env() -> io::Result<Option<Self>> {
+        if env::var("RATE_LIMIT").is_ok_and(|v| v == "off" || v == "0") {
+            return Ok(None);
+        }
+        let mut budgets = HashMap::new();
+        for (class, per_minute, burst) in [
+            (Class::Html, 300, 60),
+            (Class::Json, 60, 20),
+            (Class::Api, 120, 30),
+        ] {
+            if let Some(budget) = Budget::from_env(class, per_minute, burst)? {
+                budgets.insert(class, budget);
+            }
+        }
+        Ok(Some(Self {
+            budgets,
+            trusted_proxies: cidr_list("TRUSTED_PROXIES")?,
+            allowlist: cidr_list("RATE_LIMIT_ALLOW")?,
+            buckets: Mutex::new(Buckets {
+                by_client: HashMap::new(),
+                swept: Instant::now(),
+            }),
+        }))
+    }
+
+    /// The client's address. `X-Forwarded-For` is only read when the peer
+    /// is a trusted proxy, and is walked from the right so a client cannot
+    /// pick its own address by prepending entries.
+    fn client_ip(&self, req: &HttpRequest) -> Option<IpAddr> {
+        let mut client = req.peer_addr()?.ip();
+        if !listed(&self.trusted_proxies, client) {
+            return Some(client);
+        }
+        let hops = req
+            .headers()
+            .get_all("x-forwarded-for")
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        for hop in hops.into_iter().rev() {
+            let Ok(ip) = hop.parse::<IpAddr>() else {
+                break;
+            };
+            client = ip;
+            if !listed(&self.trusted_proxies, ip) {
+                break;
+            }
+        }
+        Some(client)
+    }
+
+    /// Takes a token, or returns how long until one is available.
+    fn take(&self, ip: IpAddr, class: Class, budget: Budget) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if buckets.by_client.len() > SWEEP_THRESHOLD
+            && now.duration_since(buckets.swept) > SWEEP_INTERVAL
+        {
+            let budgets = &self.budgets;
+            buckets.by_client.retain(|(_, class), bucket| {
+                let mut bucket = *bucket;
+                budgets
+                    .get(class)
+                    .map(|budget| {
+                        bucket.refill(*budget, now);
+                        bucket.tokens < budget.burst
+                    })
+                    .unwrap_or(false)
+            });
+            buckets.swept = now;
+        }
+
+        let bucket = buckets.by_client.entry((ip, class)).or_insert(Bucket {
+            tokens: budget.burst,
+            updated: now,
+        });
+        bucket.refill(budget, now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - bucket.tokens) / budget.per_second,
+            ))
+        }
+    }
+
+    /// The 429 to answer with when the request is over its budget.
+    fn check(&self, req: &HttpRequest) -> Option<HttpResponse> {
+        let class = Class::of(req)?;
+        let budget = self.budgets.get(&class).copied()?;
+        let ip = self.client_ip(req)?;
+        if listed(&self.allowlist, ip) {
+            return None;
+        }
+        let wait = self.take(ip, class, budget).err()?;
+        metrics::rate_limited(class.name());
+        Some(too_many_requests(class, wait))
+    }
+}
+
+fn too_many_requests(class: Class, wait: Duration) -> HttpResponse {
+    let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+    let mut builder = HttpResponse::build(StatusCode::TOO_MANY_REQUESTS);
+    builder
+        .append_header((header::RETRY_AFTER, retry_after.to_string()))
+        .append_header((header::CACHE_CONTROL, "no-store"));
+    match class {
+        Class::Html => builder
+            .append_header((header::CONTENT_TYPE, "text/plain; charset=utf-8"))
+            .body(format!(
+                "Too many requests. Try again in {retry_after} seconds.\n"
+            )),
+        Class::Json | Class::Api => builder.json(json!({
+            "error": "Too many requests",
+            "code": StatusCode::TOO_MANY_REQUESTS.as_u16(),
+            "retry_after": retry_after,
+        })),
+    }
+}
+
+/// Middleware answering over-budget clients with a 429 before the handler
+/// runs.
+pub(crate) fn limit<S>(
+    mut req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error>,
+{
+    let rejection = req
+        .app_data::<web::Data<RateLimiter>>()
+        .cloned()
+        .and_then(|limiter| limiter.check(req.parts_mut().0));
+    let outcome = match rejection {
+        None => Ok(srv.call(req)),
+        Some(res) => Err(req.into_response(res)),
+    };
+    async move {
+        match outcome {
+            Ok(fut) => fut.await,
+            Err(res) => Ok(res),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        test::{call_service, init_service, TestRequest},
+        App,
+    };
+
+    fn cidrs(list: &[&str]) -> Vec<Cidr> {
+        list.iter().map(|raw| Cidr::parse(raw).unwrap()).collect()
+    }
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().unwrap()
+    }
+
+    /// Every class gets `per_minute` with a burst of `burst`.
+    fn limiter(per_minute: u32, burst: u32, trusted: &[&str], allow: &[&str]) -> RateLimiter {
+        let budget = Budget {
+            burst: f64::from(burst),
+            per_second: f64::from(per_minute) / 60.0,
+        };
+        RateLimiter {
+            budgets: [Class::Html, Class::Json, Class::Api]
+                .into_iter()
+                .map(|class| (class, budget))
+                .collect(),
+            trusted_proxies: cidrs(trusted),
+            allowlist: cidrs(allow),
+            buckets: Mutex::new(Buckets {
+                by_client: HashMap::new(),
+                swept: Instant::now(),
+            }),
+        }
+    }
+
+    #[test]
+    fn cidrs_match_their_network() {
+        let net = Cidr::parse("10.1.0.0/16").unwrap();
+        assert!(net.contains(ip("10.1.255.3")));
+        assert!(!net.contains(ip("10.2.0.1")));
+        assert!(net.contains(ip("::ffff:10.1.0.9")));
+
+        let host = Cidr::parse("192.0.2.7").unwrap();
+        assert!(host.contains(ip("192.0.2.7")));
+        assert!(!host.contains(ip("192.0.2.8")));
+
+        let v6 = Cidr::parse("2001:db8::/32").unwrap();
+        assert!(v6.contains(ip("2001:db8:ffff::1")));
+        assert!(!v6.contains(ip("2001:db9::1")));
+        assert!(!v6.contains(ip("10.1.0.1")));
+
+        let mapped = Cidr::parse("::ffff:10.0.0.0/104").unwrap();
+        assert!(mapped.contains(ip("10.0.0.1")));
+        assert!(mapped.contains(ip("::ffff:10.200.0.1")));
+        assert!(!mapped.contains(ip("11.0.0.1")));
+
+        assert!(Cidr::parse("0.0.0.0/0")
+            .unwrap()
+            .contains(ip("203.0.113.1")));
+        assert!(Cidr::parse("::/0").unwrap().contains(ip("2001:db8::1")));
+
+        for invalid in ["10.0.0.0/33", "::/129", "10.0.0/8", "host/8", "10.0.0.0/x"] {
+            assert!(Cidr::parse(invalid).is_none(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn forwarded_for_is_only_read_from_trusted_proxies() {
+        let limiter = limiter(60, 1, &["10.0.0.0/8"], &[]);
+        let client = |peer: &str, forwarded: &str| {
+            let req = TestRequest::get()
+                .peer_addr(format!("{peer}:4000").parse().unwrap())
+                .insert_header(("x-forwarded-for", forwarded))
+                .to_http_request();
+            limiter.client_ip(&req).unwrap()
+        };
+
+        // An untrusted peer is keyed by its own address, whatever it claims.
+        assert_eq!(client("203.0.113.9", "198.51.100.1"), ip("203.0.113.9"));
+        // Behind trusted proxies, the nearest untrusted hop is the client;
+        // entries a client prepends are ignored.
+        assert_eq!(
+            client("10.0.0.2", "1.1.1.1, 198.51.100.1, 10.0.0.5"),
+            ip("198.51.100.1")
+        );
+        assert_eq!(client("10.0.0.2", "10.0.0.9, 10.0.0.5"), ip("10.0.0.9"));
+        // An unparseable hop stops the walk at the last trusted address.
+        assert_eq!(client("10.0.0.2", "198.51.100.1, junk"), ip("10.0.0.2"));
+    }
+
+    #[test]
+    fn buckets_refill_up_to_the_burst() {
+        let budget = Budget {
+            burst: 5.0,
+            per_second: 2.0,
+        };
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 0.0,
+            updated: start,
+        };
+        bucket.refill(budget, start + Duration::from_millis(1500));
+        assert_eq!(bucket.tokens, 3.0);
+        bucket.refill(budget, start + Duration::from_secs(60));
+        assert_eq!(bucket.tokens, 5.0);
+    }
+
+    #[test]
+    fn over_budget_clients_wait_for_the_next_token() {
+        // One request every ten seconds.
+        let limiter = limiter(6, 2, &[], &["192.0.2.0/24"]);
+        let client = ip("203.0.113.9");
+        let budget = limiter.budgets[&Class::Html];
+        assert!(limiter.take(client, Class::Html, budget).is_ok());
+        assert!(limiter.take(client, Class::Html, budget).is_ok());
+        let wait = limiter.take(client, Class::Html, budget).unwrap_err();
+        assert!(wait > Duration::from_secs(9) && wait <= Duration::from_secs(10));
+        // Other classes and clients have buckets of their own.
+        assert!(limiter.take(client, Class::Api, budget).is_ok());
+        assert!(limiter
+            .take(ip("203.0.113.10"), Class::Html, budget)
+            .is_ok());
+
+        let allowed = TestRequest::get()
+            .uri("/tools")
+            .peer_addr("192.0.2.1:4000".parse().unwrap())
+            .to_http_request();
+        for _ in 0..10 {
+            assert!(limiter.check(&allowed).is_none());
+        }
+    }
+
+    #[actix_web::test]
+    async fn over_budget_requests_get_a_429_with_retry_after() {
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(limiter(6, 1, &[], &[])))
+                .wrap_fn(limit)
+                .route("/api/v1/tools", web::get().to(HttpResponse::Ok))
+                .route("/healthz", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let request = |uri: &str| {
+            TestRequest::get()
+                .uri(uri)
+                .peer_addr("203.0.113.9:4000".parse().unwrap())
+                .to_request()
+        };
+
+        assert_eq!(
+            call_service(&app, request("/api/v1/tools")).await.status(),
+            200
+        );
+        let res = call_service(&app, request("/api/v1/tools")).await;
+        assert_eq!(res.status(), 429);
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "10");
+        let body: serde_json::Value = actix_web::test::read_body_json(res).await;
+        assert_eq!(body["retry_after"], 10);
+        // Probes are never limited.
+        assert_eq!(call_service(&app, request("/healthz")).await.status(), 200);
+    }
+}
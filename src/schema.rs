@@ -0,0 +1,175 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::{InstanceType, Schema, SchemaObject},
+    JsonSchema,
+};
+use serde_json::{json, Value};
+use std::io;
+
+use crate::{
+    api::{not_found, ok_json},
+    is_allowed_host,
+    migrate::{CURRENT, LEGACY_COLLECTIONS},
+    starting_up, store, PromoContent, RustDevSeed, SiteState,
+};
+
+/// `Creator.type` values in use. The creators page gives `youtube`,
+/// `podcast`, `newsletter` and `playlist` headings of their own.
+const CREATOR_TYPES: [&str; 10] = [
+    "youtube",
+    "playlist",
+    "podcast",
+    "newsletter",
+    "twitch",
+    "blog-youtube",
+    "community",
+    "conference",
+    "org",
+    "organization",
+];
+/// `Event.status` values. The events page lists `upcoming` and `past`
+/// events apart.
+const EVENT_STATUSES: [&str; 4] = ["upcoming", "past", "tba", "recurring"];
+/// Site sections a promo slide can link into as `/<type>/<slug>`.
+const PROMO_SLIDE_TYPES: [&str; 6] = ["news", "tools", "events", "ecosystems", "creators", "learn"];
+
+fn string_enum(values: &[&str]) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        enum_values: Some(values.iter().map(|value| json!(value)).collect()),
+        ..Default::default()
+    }
+    .into()
+}
+
+pub(crate) fn creator_type(_: &mut SchemaGenerator) -> Schema {
+    string_enum(&CREATOR_TYPES)
+}
+
+pub(crate) fn event_status(_: &mut SchemaGenerator) -> Schema {
+    string_enum(&EVENT_STATUSES)
+}
+
+pub(crate) fn promo_slide_type(_: &mut SchemaGenerator) -> Schema {
+    string_enum(&PROMO_SLIDE_TYPES)
+}
+
+/// A label referenced by slug. Labels are content, so the slugs it may take
+/// are filled in by `seed_schema` from the seed being described.
+pub(crate) struct LabelSlug;
+
+impl JsonSchema for LabelSlug {
+    fn schema_name() -> String {
+        "LabelSlug".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+fn document<T: JsonSchema>(title: &str) -> Value {
+    let schema = SchemaSettings::draft07()
+        .into_generator()
+        .into_root_schema_for::<T>();
+    let mut document = serde_json::to_value(schema).unwrap_or_else(|_| json!({}));
+    document["title"] = title.into();
+    document
+}
+
+/// JSON Schema for the seed at the current version, with label references
+/// limited to `labels`. Collections under their pre-v7.0 names are still
+/// described, as deprecated, since older seeds are migrated on load.
+pub(crate) fn seed_schema(labels: &[String]) -> Value {
+    let mut schema = document::<RustDevSeed>("rust.dev content seed");
+    if let Some(label_slug) = schema.pointer_mut("/definitions/LabelSlug") {
+        label_slug["enum"] = json!(labels);
+    }
+    if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+        for (old, new) in LEGACY_COLLECTIONS {
+            let Some(mut alias) = properties.get(new).cloned() else {
+                continue;
+            };
+            alias["description"] = format!("`{new}` before {CURRENT}.").into();
+            alias["deprecated"] = true.into();
+            properties.insert(old.to_string(), alias);
+        }
+    }
+    schema
+}
+
+pub(crate) fn promo_schema() -> Value {
+    document::<PromoContent>("rust.dev promo slides")
+}
+
+pub(crate) async fn seed(req: HttpRequest, state: web::Data<SiteState>) -> HttpResponse {
+    if !is_allowed_host(&req) {
+        return not_found("Not found");
+    }
+    let Some(site) = state.snapshot() else {
+        return starting_up();
+    };
+    let labels: Vec<String> = site
+        .rustdev
+        .labels
+        .iter()
+        .map(|label| label.slug.clone())
+        .collect();
+    ok_json(&req, &seed_schema(&labels), site.rustdev.last_modified)
+}
+
+pub(crate) async fn promo(req: HttpRequest) -> HttpResponse {
+    if !is_allowed_host(&req) {
+        return not_found("Not found");
+    }
+    ok_json(&req, &promo_schema(), None)
+}
+
+/// `rustdev schema [seed|promo]`: prints a schema. The seed's label slugs
+/// come from the content in the configured store.
+pub(crate) fn schema_command(args: &[String]) -> io::Result<()> {
+    let schema = match args.first().map(String::as_str) {
+        None | Some("seed") if args.len() <= 1 => {
+            let (seed, _) = store::from_env()?.seed()?;
+            let labels: Vec<String> = seed
+                .taxonomy
+                .labels
+                .into_iter()
+                .map(|label| label.slug)
+                .collect();
+            seed_schema(&labels)
+        }
+        Some("promo") if args.len() == 1 => promo_schema(),
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "usage: rustdev schema [seed|promo]",
+            ))
+        }
+    };
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_schema_describes_the_shipped_seeds_legacy_collections() {
+        let seed: Value =
+            serde_json::from_str(include_str!("../static/rustdev-hub-seed-v3.json")).unwrap();
+        let schema = seed_schema(&[]);
+        for (old, new) in LEGACY_COLLECTIONS {
+            assert!(seed.get(old).is_some(), "the shipped seed has no `{old}`");
+            let alias = &schema["properties"][old];
+            assert_eq!(alias["deprecated"], true);
+            assert_eq!(alias["items"], schema["properties"][new]["items"]);
+        }
+    }
+}
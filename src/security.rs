@@ -0,0 +1,344 @@
+use actix_web::{
+    body::BoxBody,
+    dev::{Service, ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderMap, HeaderName, HeaderValue},
+        StatusCode,
+    },
+    web, Error, HttpMessage, HttpRequest, HttpResponse,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256};
+use std::{future::Future, sync::Arc};
+
+use crate::SiteState;
+
+const HSTS: &str = "max-age=63072000; includeSubDomains";
+const REFERRER_POLICY: &str = "strict-origin-when-cross-origin";
+const PERMISSIONS_POLICY: &str =
+    "camera=(), microphone=(), geolocation=(), payment=(), usb=(), interest-cohort=()";
+/// Policy for everything that is not an HTML document: nothing may load.
+const NON_HTML_CSP: &str = "default-src 'none'; frame-ancestors 'none'";
+
+/// How the Content Security Policy is delivered.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CspConfig {
+    /// Send `Content-Security-Policy-Report-Only` instead of enforcing.
+    pub(crate) report_only: bool,
+    /// Where browsers send violation reports.
+    pub(crate) report_uri: Option<String>,
+}
+
+impl CspConfig {
+    fn header_name(&self) -> HeaderName {
+        if self.report_only {
+            header::CONTENT_SECURITY_POLICY_REPORT_ONLY
+        } else {
+            header::CONTENT_SECURITY_POLICY
+        }
+    }
+}
+
+/// Third-party embeds a page renders. Each one widens the policy by just the
+/// origins its player or widget needs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Embeds {
+    youtube: bool,
+    twitter: bool,
+}
+
+impl Embeds {
+    /// Looks for the markup of the click-to-play YouTube thumbnail and the
+    /// tweet blockquote the X widget upgrades.
+    fn detect(html: &str) -> Self {
+        Self {
+            youtube: html.contains("class=\"video-thumb"),
+            twitter: html.contains("class=\"twitter-tweet"),
+        }
+    }
+
+    fn script_sources(self) -> &'static [&'static str] {
+        if self.twitter {
+            &["https://platform.twitter.com"]
+        } else {
+            &[]
+        }
+    }
+
+    fn frame_sources(self) -> Vec<&'static str> {
+        let mut sources = Vec::new();
+        if self.youtube {
+            sources.extend([
+                "https://www.youtube.com",
+                "https://www.youtube-nocookie.com",
+            ]);
+        }
+        if self.twitter {
+            sources.extend([
+                "https://platform.twitter.com",
+                "https://syndication.twitter.com",
+            ]);
+        }
+        sources
+    }
+}
+
+/// What a page's policy allows beyond `'self'`: its inline scripts, by
+/// hash, and the origins of its embeds. Worked out once per cached page, so
+/// the body stays the same for every response.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct PagePolicy {
+    embeds: Embeds,
+    script_hashes: Arc<[String]>,
+}
+
+impl PagePolicy {
+    pub(crate) fn for_html(html: &str) -> Self {
+        Self {
+            embeds: Embeds::detect(html),
+            script_hashes: inline_scripts(html).map(script_hash).collect(),
+        }
+    }
+}
+
+/// The text of every `<script>` without a `src`, exactly as the browser
+/// hashes it.
+fn inline_scripts(html: &str) -> impl Iterator<Item = &str> {
+    let mut rest = html;
+    std::iter::from_fn(move || loop {
+        let start = rest.find("<script")?;
+        let tag_end = start + rest[start..].find('>')?;
+        let tag = &rest[start..tag_end];
+        let body_start = tag_end + 1;
+        let body_end = body_start + rest[body_start..].find("</script>")?;
+        let script = &rest[body_start..body_end];
+        rest = &rest[body_end..];
+        if !tag.contains(" src=") {
+            return Some(script);
+        }
+    })
+}
+
+/// A `script-src` hash source for `script`.
+fn script_hash(script: &str) -> String {
+    format!("'sha256-{}'", STANDARD.encode(Sha256::digest(script)))
+}
+
+/// A nonce for the inline scripts of one response. Cached pages cannot carry
+/// one, since their body is shared, so they allow scripts by hash; pages
+/// rendered per request, such as the editor and error pages, get a fresh
+/// nonce as `csp_nonce` in their template context and can write
+/// `<script nonce="{{csp_nonce}}">`.
+#[derive(Clone, Debug)]
+pub(crate) struct CspNonce(String);
+
+impl CspNonce {
+    pub(crate) fn new() -> Self {
+        Self(STANDARD.encode(uuid::Uuid::new_v4().as_bytes()))
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Lets `res`'s policy allow scripts carrying this nonce.
+    pub(crate) fn attach(self, res: &mut HttpResponse) {
+        res.extensions_mut().insert(self);
+    }
+}
+
+/// Records the policy of the cached page being served.
+pub(crate) fn record_policy(req: &HttpRequest, policy: &PagePolicy) {
+    req.extensions_mut().insert(policy.clone());
+}
+
+/// The policy for an HTML document. Inline scripts are allowed by hash, or
+/// by nonce when the response has one; inline styles stay allowed because
+/// the templates use `style` attributes.
+fn html_policy(page: &PagePolicy, nonce: Option<&CspNonce>, config: &CspConfig) -> String {
+    let embeds = page.embeds;
+    let mut script_src = String::from("'self'");
+    if let Some(nonce) = nonce {
+        script_src.push_str(&format!(" 'nonce-{}'", nonce.as_str()));
+    }
+    for source in page.script_hashes.iter() {
+        script_src.push(' ');
+        script_src.push_str(source);
+    }
+    for source in embeds.script_sources() {
+        script_src.push(' ');
+        script_src.push_str(source);
+    }
+    let frame_sources = embeds.frame_sources();
+    let frame_src = if frame_sources.is_empty() {
+        "'none'".to_string()
+    } else {
+        frame_sources.join(" ")
+    };
+
+    let mut policy = format!(
+        "default-src 'self'; script-src {script_src}; style-src 'self' 'unsafe-inline'; \
+         img-src 'self' data: https:; font-src 'self' data:; connect-src 'self'; \
+         frame-src {frame_src}; object-src 'none'; base-uri 'self'; form-action 'self'; \
+         frame-ancestors 'none'"
+    );
+    if let Some(uri) = &config.report_uri {
+        policy.push_str("; report-uri ");
+        policy.push_str(uri);
+    }
+    policy
+}
+
+fn is_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|ct| ct.starts_with("text/html"))
+        .unwrap_or(false)
+}
+
+/// Middleware setting the security headers on every response, and a
+/// Content Security Policy on HTML documents. 304s carry no policy; the
+/// browser keeps the one stored with the body it already has.
+pub(crate) fn headers<S>(
+    req: ServiceRequest,
+    srv: &S,
+) -> impl Future<Output = Result<ServiceResponse<BoxBody>, Error>>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<BoxBody>, Error = Error>,
+{
+    let https = req.connection_info().scheme() == "https";
+    let csp = req
+        .app_data::<web::Data<SiteState>>()
+        .map(|state| state.config.csp.clone())
+        .unwrap_or_default();
+    let fut = srv.call(req);
+    async move {
+        let mut res = fut.await?;
+        let page = res
+            .request()
+            .extensions()
+            .get::<PagePolicy>()
+            .cloned()
+            .unwrap_or_default();
+        let nonce = res.response().extensions().get::<CspNonce>().cloned();
+
+        let headers = res.headers_mut();
+        headers.insert(
+            header::X_CONTENT_TYPE_OPTIONS,
+            HeaderValue::from_static("nosniff"),
+        );
+        headers.insert(
+            header::REFERRER_POLICY,
+            HeaderValue::from_static(REFERRER_POLICY),
+        );
+        headers.insert(
+            HeaderName::from_static("permissions-policy"),
+            HeaderValue::from_static(PERMISSIONS_POLICY),
+        );
+        if https {
+            headers.insert(
+                header::STRICT_TRANSPORT_SECURITY,
+                HeaderValue::from_static(HSTS),
+            );
+        }
+        if res.status() == StatusCode::NOT_MODIFIED {
+            return Ok(res);
+        }
+
+        if !is_html(res.headers()) {
+            res.headers_mut()
+                .insert(csp.header_name(), HeaderValue::from_static(NON_HTML_CSP));
+            return Ok(res);
+        }
+        if let Ok(value) = HeaderValue::from_str(&html_policy(&page, nonce.as_ref(), &csp)) {
+            res.headers_mut().insert(csp.header_name(), value);
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_inline_scripts_only() {
+        let html = r#"<script>alert(1)</script>
+<script async src="https://platform.twitter.com/widgets.js"></script>
+<script></script>"#;
+        let policy = PagePolicy::for_html(html);
+        assert_eq!(
+            policy.script_hashes.as_ref(),
+            [
+                script_hash("alert(1)"),
+                "'sha256-47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU='".to_string(),
+            ]
+        );
+        let header = html_policy(&policy, None, &CspConfig::default());
+        assert!(header.contains("script-src 'self' 'sha256-"));
+    }
+
+    #[actix_web::test]
+    async fn responses_rendered_per_request_allow_their_nonce() {
+        use actix_web::{
+            test::{call_service, init_service, read_body, TestRequest},
+            App,
+        };
+
+        let app = init_service(
+            App::new()
+                .wrap_fn(headers)
+                .route(
+                    "/admin",
+                    web::get().to(|| async {
+                        let nonce = CspNonce::new();
+                        let mut res = HttpResponse::Ok().content_type("text/html").body(format!(
+                            "<script nonce=\"{}\">go()</script>",
+                            nonce.as_str()
+                        ));
+                        nonce.attach(&mut res);
+                        res
+                    }),
+                )
+                .route(
+                    "/cached",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .content_type("text/html")
+                            .body("<p>hi</p>")
+                    }),
+                ),
+        )
+        .await;
+
+        let policy = |res: &ServiceResponse| {
+            res.headers()
+                .get(header::CONTENT_SECURITY_POLICY)
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+        let mut nonces = Vec::new();
+        for _ in 0..2 {
+            let res = call_service(&app, TestRequest::get().uri("/admin").to_request()).await;
+            let header = policy(&res);
+            let body = read_body(res).await;
+            let nonce = std::str::from_utf8(&body)
+                .unwrap()
+                .split('"')
+                .nth(1)
+                .unwrap();
+            assert!(
+                header.contains(&format!("script-src 'self' 'nonce-{nonce}'")),
+                "{header}"
+            );
+            nonces.push(nonce.to_string());
+        }
+        assert_ne!(nonces[0], nonces[1]);
+
+        let res = call_service(&app, TestRequest::get().uri("/cached").to_request()).await;
+        assert!(!policy(&res).contains("'nonce-"));
+    }
+}
@@ -0,0 +1,331 @@
+use rusqlite::{params, Connection, OptionalExtension, Transaction, TransactionBehavior};
+use serde_json::Value;
+use std::{collections::HashMap, io, path::PathBuf, time::Duration};
+
+use crate::{
+    content::{slug_of, COLLECTIONS},
+    store::{invalid_data, ContentStore, Mutation, Snapshot, StoreVersion},
+};
+
+/// `document` holds the seed with every collection's array emptied, and
+/// `entities` one row per entity, so an edit rewrites a single row.
+/// `revision` counts changes, for refusing edits made against stale reads.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS document (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    seed TEXT NOT NULL,
+    revision INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS entities (
+    collection TEXT NOT NULL,
+    slug TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    body TEXT NOT NULL,
+    PRIMARY KEY (collection, slug)
+);
+CREATE INDEX IF NOT EXISTS entities_by_position ON entities (collection, position);
+";
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn db_error(err: rusqlite::Error) -> io::Error {
+    io::Error::other(err.to_string())
+}
+
+/// The seed kept in an SQLite database, one row per entity.
+pub(crate) struct SqliteStore {
+    path: PathBuf,
+}
+
+impl SqliteStore {
+    /// Opens the database at `path`, creating it and its tables if needed.
+    pub(crate) fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let store = Self { path: path.into() };
+        store.connect()?.execute_batch(SCHEMA).map_err(db_error)?;
+        Ok(store)
+    }
+
+    fn connect(&self) -> io::Result<Connection> {
+        let conn = Connection::open(&self.path).map_err(db_error)?;
+        conn.busy_timeout(BUSY_TIMEOUT).map_err(db_error)?;
+        Ok(conn)
+    }
+
+    /// Writes take the database's write lock up front. A deferred
+    /// transaction would read under a shared lock first, and SQLite fails a
+    /// later upgrade at once, without waiting out the busy timeout, when
+    /// another connection is writing.
+    fn write(conn: &mut Connection) -> io::Result<Transaction<'_>> {
+        conn.transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(db_error)
+    }
+
+    fn revision(conn: &Connection) -> io::Result<Option<i64>> {
+        conn.query_row("SELECT revision FROM document WHERE id = 1", [], |row| {
+            row.get(0)
+        })
+        .optional()
+        .map_err(db_error)
+    }
+}
+
+impl ContentStore for SqliteStore {
+    fn describe(&self) -> String {
+        format!("sqlite:{}", self.path.display())
+    }
+
+    fn version(&self) -> StoreVersion {
+        StoreVersion::Modified(
+            std::fs::metadata(&self.path)
+                .and_then(|meta| meta.modified())
+                .ok(),
+        )
+    }
+
+    fn load(&self) -> io::Result<Snapshot> {
+        let conn = self.connect()?;
+        let modified = std::fs::metadata(&self.path)?.modified().ok();
+        let (document, revision): (String, i64) = conn
+            .query_row(
+                "SELECT seed, revision FROM document WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(db_error)?
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "{} holds no content; import some with `rustdev migrate-store`",
+                        self.describe()
+                    ),
+                )
+            })?;
+        let mut seed: Value = serde_json::from_str(&document).map_err(invalid_data)?;
+
+        let mut by_collection: HashMap<String, Vec<Value>> = HashMap::new();
+        let mut rows = conn
+            .prepare("SELECT collection, body FROM entities ORDER BY collection, position")
+            .map_err(db_error)?;
+        let rows = rows
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(db_error)?;
+        for row in rows {
+            let (collection, body) = row.map_err(db_error)?;
+            let entity = serde_json::from_str(&body).map_err(invalid_data)?;
+            by_collection.entry(collection).or_default().push(entity);
+        }
+        for collection in COLLECTIONS {
+            let entities = by_collection.remove(collection.path());
+            // Collections the seed never had stay absent until one is added.
+            if entities.is_none() && !collection.is_in(&seed) {
+                continue;
+            }
+            *collection.items_mut(&mut seed)? = entities.unwrap_or_default();
+        }
+        if let Some(unknown) = by_collection.keys().next() {
+            return Err(invalid_data(format!(
+                "{} has entities in unknown collection `{unknown}`",
+                self.describe()
+            )));
+        }
+
+        Ok(Snapshot {
+            seed,
+            modified,
+            revision: Some(revision.to_string()),
+        })
+    }
+
+    fn apply(&self, mutation: &Mutation) -> io::Result<Option<String>> {
+        let mut conn = self.connect()?;
+        let tx = Self::write(&mut conn)?;
+        let revision = Self::revision(&tx)?
+            .ok_or_else(|| io::Error::other(format!("{} holds no content", self.describe())))?;
+        if mutation
+            .base
+            .is_some_and(|base| base != revision.to_string())
+        {
+            return Err(io::Error::other(format!(
+                "{} changed since it was read; try again",
+                self.describe()
+            )));
+        }
+
+        let collection = mutation.collection.path();
+        match mutation.entity {
+            // Updates keep their place; new entities go last.
+            Some(entity) => tx.execute(
+                "INSERT INTO entities (collection, slug, position, body)
+                 VALUES (?1, ?2,
+                     (SELECT COALESCE(MAX(position), -1) + 1 FROM entities WHERE collection = ?1),
+                     ?3)
+                 ON CONFLICT (collection, slug) DO UPDATE SET body = excluded.body",
+                params![
+                    collection,
+                    mutation.slug,
+                    serde_json::to_string(entity).map_err(invalid_data)?
+                ],
+            ),
+            None => tx.execute(
+                "DELETE FROM entities WHERE collection = ?1 AND slug = ?2",
+                params![collection, mutation.slug],
+            ),
+        }
+        .map_err(db_error)?;
+        tx.execute(
+            "UPDATE document SET revision = revision + 1 WHERE id = 1",
+            [],
+        )
+        .map_err(db_error)?;
+        tx.commit().map_err(db_error)?;
+        Ok(Some((revision + 1).to_string()))
+    }
+
+    fn replace(&self, seed: &Value, _author: &str, _message: &str) -> io::Result<()> {
+        let mut document = seed.clone();
+        let mut conn = self.connect()?;
+        let tx = Self::write(&mut conn)?;
+        tx.execute("DELETE FROM entities", []).map_err(db_error)?;
+        {
+            let mut insert = tx
+                .prepare(
+                    "INSERT INTO entities (collection, slug, position, body)
+                     VALUES (?1, ?2, ?3, ?4)",
+                )
+                .map_err(db_error)?;
+            for collection in COLLECTIONS {
+                if !collection.is_in(&document) {
+                    continue;
+                }
+                let entities = std::mem::take(collection.items_mut(&mut document)?);
+                for (position, entity) in entities.iter().enumerate() {
+                    let slug = slug_of(entity).ok_or_else(|| {
+                        invalid_data(format!("{} #{position} has no slug", collection.path()))
+                    })?;
+                    insert
+                        .execute(params![
+                            collection.path(),
+                            slug,
+                            position as i64,
+                            serde_json::to_string(entity).map_err(invalid_data)?
+                        ])
+                        .map_err(db_error)?;
+                }
+            }
+        }
+        tx.execute(
+            "INSERT INTO document (id, seed, revision) VALUES (1, ?1, 1)
+             ON CONFLICT (id) DO UPDATE SET seed = excluded.seed, revision = revision + 1",
+            params![serde_json::to_string(&document).map_err(invalid_data)?],
+        )
+        .map_err(db_error)?;
+        tx.commit().map_err(db_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        content::Collection,
+        store::{self, JsonFileStore},
+        DATA_PATH,
+    };
+    use serde_json::json;
+
+    fn seed() -> Value {
+        json!({
+            "version": "rustdev-hub-seed-v7.0",
+            "tools": [
+                {"slug": "anchor", "name": "Anchor"},
+                {"slug": "borsh", "name": "Borsh"},
+            ],
+        })
+    }
+
+    fn mutation<'a>(entity: Option<&'a Value>, base: Option<&'a str>) -> Mutation<'a> {
+        Mutation {
+            collection: Collection::Tools,
+            slug: "anchor",
+            entity,
+            seed: &Value::Null,
+            base,
+            author: "ed",
+            message: "Update tools/anchor",
+        }
+    }
+
+    #[test]
+    fn edits_against_a_stale_revision_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SqliteStore::open(dir.path().join("content.db")).unwrap();
+        store.replace(&seed(), "ed", "Import").unwrap();
+        let read = store.load().unwrap().revision.unwrap();
+
+        let first = json!({"slug": "anchor", "name": "Anchor 1"});
+        let written = store.apply(&mutation(Some(&first), Some(&read))).unwrap();
+        assert_ne!(written.as_deref(), Some(read.as_str()));
+
+        let second = json!({"slug": "anchor", "name": "Anchor 2"});
+        let err = store
+            .apply(&mutation(Some(&second), Some(&read)))
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("changed since it was read"),
+            "{err}"
+        );
+        let stored = store.load().unwrap();
+        assert_eq!(stored.revision, written);
+        assert_eq!(stored.seed["tools"][0], first);
+        // Updates keep their place.
+        assert_eq!(stored.seed["tools"][1]["slug"], "borsh");
+    }
+
+    #[test]
+    fn writes_wait_for_another_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("content.db");
+        let store = SqliteStore::open(&path).unwrap();
+        store.replace(&seed(), "ed", "Import").unwrap();
+
+        let (locked, is_locked) = std::sync::mpsc::channel();
+        let writer = std::thread::spawn(move || {
+            let other = Connection::open(&path).unwrap();
+            other.execute_batch("BEGIN IMMEDIATE").unwrap();
+            locked.send(()).unwrap();
+            std::thread::sleep(Duration::from_millis(200));
+            other.execute_batch("COMMIT").unwrap();
+        });
+        is_locked.recv().unwrap();
+        store.apply(&mutation(None, None)).unwrap();
+        writer.join().unwrap();
+        assert_eq!(
+            store.load().unwrap().seed["tools"]
+                .as_array()
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn the_seed_survives_a_round_trip_through_sqlite() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = JsonFileStore::new(DATA_PATH);
+        let db = SqliteStore::open(dir.path().join("content.db")).unwrap();
+        let json = JsonFileStore::new(dir.path().join("seed.json"));
+
+        store::copy(&source, &db, false).unwrap();
+        store::copy(&db, &json, false).unwrap();
+        assert_eq!(json.load().unwrap().seed, source.load().unwrap().seed);
+        assert_eq!(
+            std::fs::read(dir.path().join("seed.json")).unwrap(),
+            std::fs::read(DATA_PATH).unwrap()
+        );
+        // Without --force, content is not replaced.
+        assert!(store::copy(&source, &db, false).is_err());
+    }
+}
@@ -0,0 +1,384 @@
+use serde_json::{
+    ser::{Formatter, PrettyFormatter},
+    Value,
+};
+use std::{
+    env,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{
+    content::{Collection, COLLECTIONS},
+    directory::DirectoryStore,
+    git::GitContent,
+    migrate,
+    sqlite::SqliteStore,
+    RustDevSeed, DATA_PATH,
+};
+
+/// Where the SQLite store lives unless `CONTENT_DB` says otherwise.
+const DEFAULT_CONTENT_DB: &str = "static/rustdev-content.sqlite3";
+/// Where the content directory is unless `CONTENT_DIR` says otherwise.
+pub(crate) const DEFAULT_CONTENT_DIR: &str = "content";
+/// Who `migrate-store` attributes imported content to.
+const IMPORT_AUTHOR: &str = "rustdev";
+
+pub(crate) fn invalid_data(err: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+/// Identifies the content's current version, so the watcher can tell when
+/// it changes: a file's modification time, or the commit it is read at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum StoreVersion {
+    Modified(Option<SystemTime>),
+    Commit(Option<git2::Oid>),
+}
+
+/// The whole seed as a store holds it.
+pub(crate) struct Snapshot {
+    /// Raw JSON, keeping fields `RustDevSeed` does not know about.
+    pub(crate) seed: Value,
+    /// When the content last changed.
+    pub(crate) modified: Option<SystemTime>,
+    /// Marks the version read. Handing it back with a `Mutation` lets the
+    /// store refuse to overwrite anything changed since.
+    pub(crate) revision: Option<String>,
+}
+
+/// A change to one entity.
+pub(crate) struct Mutation<'a> {
+    pub(crate) collection: Collection,
+    pub(crate) slug: &'a str,
+    /// The entity as it should be stored, or `None` to delete it.
+    pub(crate) entity: Option<&'a Value>,
+    /// The whole seed with the change made, for stores that keep a single
+    /// document.
+    pub(crate) seed: &'a Value,
+    /// The `Snapshot::revision` the change was made against.
+    pub(crate) base: Option<&'a str>,
+    pub(crate) author: &'a str,
+    pub(crate) message: &'a str,
+}
+
+/// Somewhere the seed is kept. Every method blocks, so call them off the
+/// async workers.
+pub(crate) trait ContentStore: Send + Sync {
+    /// Where the content is, for logs and the admin pages.
+    fn describe(&self) -> String;
+
+    /// Cheap enough to poll; changes whenever the content does.
+    fn version(&self) -> StoreVersion;
+
+    fn load(&self) -> io::Result<Snapshot>;
+
+    /// The content as the site renders it, upgraded to the current seed
+    /// version, and when it last changed.
+    fn seed(&self) -> io::Result<(RustDevSeed, Option<SystemTime>)> {
+        let snapshot = self.load()?;
+        Ok((migrate::read_seed(snapshot.seed)?, snapshot.modified))
+    }
+
+    /// Applies one entity change, or fails without changing anything if the
+    /// content moved on from `mutation.base`. Returns the new revision.
+    fn apply(&self, mutation: &Mutation) -> io::Result<Option<String>>;
+
+    /// Replaces all content with `seed`.
+    fn replace(&self, seed: &Value, author: &str, message: &str) -> io::Result<()>;
+
+    /// Why the content cannot be edited, if it cannot.
+    fn read_only(&self) -> Option<String> {
+        None
+    }
+
+    /// The repository behind a git store.
+    fn git(&self) -> Option<&GitContent> {
+        None
+    }
+}
+
+/// Writes the seed the way it is kept in the repository: two-space indent,
+/// non-ASCII escaped as `\uXXXX`, so an edit through the API only changes
+/// the lines it touched.
+struct SeedFormatter(PrettyFormatter<'static>);
+
+impl Formatter for SeedFormatter {
+    fn begin_array<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.begin_array(writer)
+    }
+
+    fn end_array<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.end_array(writer)
+    }
+
+    fn begin_array_value<W: ?Sized + Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.0.begin_array_value(writer, first)
+    }
+
+    fn end_array_value<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.end_array_value(writer)
+    }
+
+    fn begin_object<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.begin_object(writer)
+    }
+
+    fn end_object<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.end_object(writer)
+    }
+
+    fn begin_object_key<W: ?Sized + Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.0.begin_object_key(writer, first)
+    }
+
+    fn begin_object_value<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.begin_object_value(writer)
+    }
+
+    fn end_object_value<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.0.end_object_value(writer)
+    }
+
+    fn write_string_fragment<W: ?Sized + Write>(
+        &mut self,
+        writer: &mut W,
+        fragment: &str,
+    ) -> io::Result<()> {
+        let mut units = [0; 2];
+        for ch in fragment.chars() {
+            if ch.is_ascii() {
+                writer.write_all(&[ch as u8])?;
+            } else {
+                for unit in ch.encode_utf16(&mut units) {
+                    write!(writer, "\\u{unit:04x}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn to_seed_json(seed: &Value) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut serializer =
+        serde_json::Serializer::with_formatter(&mut bytes, SeedFormatter(PrettyFormatter::new()));
+    serde::Serialize::serialize(seed, &mut serializer)?;
+    bytes.push(b'\n');
+    Ok(bytes)
+}
+
+fn dir_and_name(path: &Path) -> io::Result<(&Path, String)> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let name = path
+        .file_name()
+        .ok_or_else(|| invalid_input(format!("{} has no file name", path.display())))?
+        .to_string_lossy()
+        .into_owned();
+    Ok((dir, name))
+}
+
+/// Replaces `path` without readers ever seeing a partial file: the new
+/// contents are synced to a temporary file in the same directory and
+/// renamed over it.
+pub(crate) fn replace_file(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let (dir, name) = dir_and_name(path)?;
+    let tmp = dir.join(format!(".{name}.tmp"));
+    let mut file = std::fs::File::create(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+
+    std::fs::rename(&tmp, path)?;
+    // Persist the rename itself.
+    std::fs::File::open(dir)?.sync_all()
+}
+
+/// `replace_file`, keeping the previous version as `<name>.bak`.
+fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    if path.exists() {
+        let (dir, name) = dir_and_name(path)?;
+        let backup_tmp = dir.join(format!(".{name}.bak.tmp"));
+        std::fs::copy(path, &backup_tmp)?;
+        std::fs::rename(&backup_tmp, dir.join(format!("{name}.bak")))?;
+    }
+    replace_file(path, bytes)
+}
+
+/// A revision for stores versioned by modification time, in nanoseconds.
+pub(crate) fn mtime_revision(modified: Option<SystemTime>) -> Option<String> {
+    modified
+        .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|since| since.as_nanos().to_string())
+}
+
+fn file_mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).ok()?.modified().ok()
+}
+
+/// The seed kept as one JSON file, as in the repository.
+pub(crate) struct JsonFileStore {
+    path: PathBuf,
+}
+
+impl JsonFileStore {
+    pub(crate) fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl ContentStore for JsonFileStore {
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn version(&self) -> StoreVersion {
+        StoreVersion::Modified(file_mtime(&self.path))
+    }
+
+    fn load(&self) -> io::Result<Snapshot> {
+        let modified = file_mtime(&self.path);
+        let bytes = std::fs::read(&self.path)?;
+        Ok(Snapshot {
+            seed: serde_json::from_slice(&bytes).map_err(invalid_data)?,
+            modified,
+            revision: mtime_revision(modified),
+        })
+    }
+
+    fn apply(&self, mutation: &Mutation) -> io::Result<Option<String>> {
+        let current = mtime_revision(file_mtime(&self.path));
+        if mutation.base.is_some() && mutation.base != current.as_deref() {
+            return Err(io::Error::other(format!(
+                "{} changed on disk since it was read; try again",
+                self.describe()
+            )));
+        }
+        write_atomically(&self.path, &to_seed_json(mutation.seed)?)?;
+        Ok(mtime_revision(file_mtime(&self.path)))
+    }
+
+    fn replace(&self, seed: &Value, _author: &str, _message: &str) -> io::Result<()> {
+        write_atomically(&self.path, &to_seed_json(seed)?)
+    }
+}
+
+pub(crate) fn invalid_input(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+
+/// Opens the store named `kind`, configured from the environment: `json`
+/// is the seed file, `sqlite` the database at `CONTENT_DB`, `dir` the
+/// directory of per-entity files at `CONTENT_DIR`, and `git` the
+/// repository at `CONTENT_GIT_REPO`.
+pub(crate) fn open(kind: &str) -> io::Result<Box<dyn ContentStore>> {
+    match kind {
+        "json" => Ok(Box::new(JsonFileStore::new(DATA_PATH))),
+        "sqlite" => Ok(Box::new(SqliteStore::open(
+            env::var("CONTENT_DB").unwrap_or_else(|_| DEFAULT_CONTENT_DB.to_string()),
+        )?)),
+        "dir" => Ok(Box::new(DirectoryStore::new(
+            env::var("CONTENT_DIR").unwrap_or_else(|_| DEFAULT_CONTENT_DIR.to_string()),
+        ))),
+        "git" => match GitContent::from_env(DATA_PATH)? {
+            Some(git) => Ok(Box::new(git)),
+            None => Err(invalid_input("the git store needs CONTENT_GIT_REPO")),
+        },
+        other => Err(invalid_input(format!(
+            "unknown content store `{other}`; expected json, sqlite, dir or git"
+        ))),
+    }
+}
+
+/// The store the server uses: `CONTENT_STORE` names it, and otherwise it
+/// is git when `CONTENT_GIT_REPO` is set and the JSON file when not.
+pub(crate) fn from_env() -> io::Result<Box<dyn ContentStore>> {
+    let kind = env::var("CONTENT_STORE").unwrap_or_else(|_| {
+        if env::var_os("CONTENT_GIT_REPO").is_some() {
+            "git".to_string()
+        } else {
+            "json".to_string()
+        }
+    });
+    open(&kind)
+}
+
+fn entity_count(seed: &Value) -> usize {
+    COLLECTIONS.iter().map(|c| c.items(seed).len()).sum()
+}
+
+/// `rustdev migrate-store --from <store> --to <store> [--force]`: copies
+/// all content from one store to another.
+pub(crate) fn migrate_store_command(args: &[String]) -> io::Result<()> {
+    let usage =
+        || invalid_input("usage: rustdev migrate-store --from <store> --to <store> [--force]");
+    let (mut from, mut to, mut force) = (None, None, false);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => from = args.next(),
+            "--to" => to = args.next(),
+            "--force" => force = true,
+            _ => return Err(usage()),
+        }
+    }
+    let (Some(from), Some(to)) = (from, to) else {
+        return Err(usage());
+    };
+    if from == to {
+        return Err(invalid_input("--from and --to name the same store"));
+    }
+
+    copy(&*open(from)?, &*open(to)?, force)
+}
+
+/// Copies all content from `source` to `target` and checks the copy reads
+/// back the same. A target that already holds content is only replaced
+/// with `force`.
+pub(crate) fn copy(
+    source: &dyn ContentStore,
+    target: &dyn ContentStore,
+    force: bool,
+) -> io::Result<()> {
+    let snapshot = source.load()?;
+    migrate::read_seed(snapshot.seed.clone())
+        .map_err(|err| invalid_data(format!("{} does not load: {err}", source.describe())))?;
+    if !force && target.load().is_ok() {
+        return Err(invalid_input(format!(
+            "{} already holds content; pass --force to replace it",
+            target.describe()
+        )));
+    }
+
+    target.replace(
+        &snapshot.seed,
+        IMPORT_AUTHOR,
+        &format!("Import content from {}", source.describe()),
+    )?;
+    let copied = target.load()?;
+    if copied.seed != snapshot.seed {
+        return Err(invalid_data(format!(
+            "{} does not read back what was written",
+            target.describe()
+        )));
+    }
+    println!(
+        "Copied {} entities from {} to {}",
+        entity_count(&snapshot.seed),
+        source.describe(),
+        target.describe()
+    );
+    Ok(())
+}
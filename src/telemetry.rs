@@ -0,0 +1,163 @@
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+use std::env;
+use tracing::Subscriber;
+use tracing_subscriber::{registry::LookupSpan, Layer};
+
+/// Setting either variable turns span export on. Both follow the OTLP
+/// exporter spec: the generic one gets `/v1/traces` appended, the traces
+/// one is used as-is.
+const ENDPOINT_VARS: [&str; 2] = [
+    "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+    "OTEL_EXPORTER_OTLP_ENDPOINT",
+];
+
+/// Exports spans over OTLP/HTTP to the collector configured through the
+/// standard `OTEL_*` variables. Tracing is off unless an endpoint is set.
+pub(crate) struct Telemetry {
+    provider: SdkTracerProvider,
+}
+
+impl Telemetry {
+    pub(crate) fn from_env() -> Option<Self> {
+        if !ENDPOINT_VARS.iter().any(|var| env::var_os(var).is_some()) {
+            return None;
+        }
+        let exporter = match SpanExporter::builder().with_http().build() {
+            Ok(exporter) => exporter,
+            Err(err) => {
+                eprintln!("OTLP exporter disabled: {err}");
+                return None;
+            }
+        };
+        let service_name =
+            env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string());
+        Some(Self::new(exporter, service_name))
+    }
+
+    /// Batches spans to `exporter` under `service_name`.
+    fn new(exporter: SpanExporter, service_name: String) -> Self {
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(service_name)
+                    .with_attribute(opentelemetry::KeyValue::new(
+                        "service.version",
+                        env!("CARGO_PKG_VERSION"),
+                    ))
+                    .build(),
+            )
+            .build();
+        Self { provider }
+    }
+
+    /// A subscriber layer turning `tracing` spans into OTLP spans.
+    pub(crate) fn layer<S>(&self) -> impl Layer<S>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        tracing_opentelemetry::layer().with_tracer(self.provider.tracer(env!("CARGO_PKG_NAME")))
+    }
+
+    /// Flushes spans still queued for export.
+    pub(crate) fn shutdown(self) {
+        if let Err(err) = self.provider.shutdown() {
+            tracing::warn!(%err, "OTLP exporter shutdown failed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry_otlp::WithExportConfig;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::mpsc,
+        time::Duration,
+    };
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// An export the stand-in collector received.
+    struct Export {
+        path: String,
+        content_type: String,
+        body: Vec<u8>,
+    }
+
+    /// Answers every OTLP/HTTP request with 200 and passes it on.
+    fn collector() -> (u16, mpsc::Receiver<Export>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let (mut content_type, mut length) = (String::new(), 0);
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let Some((name, value)) = line.trim_end().split_once(": ") else {
+                        break;
+                    };
+                    match name.to_ascii_lowercase().as_str() {
+                        "content-type" => content_type = value.to_string(),
+                        "content-length" => length = value.parse().unwrap(),
+                        _ => {}
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .unwrap();
+                let path = request_line.split(' ').nth(1).unwrap_or_default();
+                let _ = sender.send(Export {
+                    path: path.to_string(),
+                    content_type,
+                    body,
+                });
+            }
+        });
+        (port, receiver)
+    }
+
+    fn contains(haystack: &[u8], needle: &str) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle.as_bytes())
+    }
+
+    #[test]
+    fn exports_spans_to_the_collector_on_shutdown() {
+        let (port, exports) = collector();
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("http://127.0.0.1:{port}/v1/traces"))
+            .build()
+            .unwrap();
+        let telemetry = Telemetry::new(exporter, "rustdev-test".to_string());
+
+        let subscriber = tracing_subscriber::registry().with(telemetry.layer());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!("render", template = "tools-list").in_scope(|| {});
+        });
+        telemetry.shutdown();
+
+        let export = exports.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(export.path, "/v1/traces");
+        assert_eq!(export.content_type, "application/x-protobuf");
+        for expected in ["rustdev-test", "render", "template", "tools-list"] {
+            assert!(
+                contains(&export.body, expected),
+                "no {expected:?} in the export"
+            );
+        }
+    }
+}
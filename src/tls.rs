@@ -0,0 +1,444 @@
+use actix_web::{http::header, web, HttpRequest, HttpResponse};
+use rustls::{
+    server::{ClientHello, ResolvesServerCert, ResolvesServerCertUsingSni},
+    sign::{self, CertifiedKey},
+    Certificate, PrivateKey, ServerConfig,
+};
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use crate::{is_allowed_host, SITE_HOSTS};
+
+pub(crate) const DEFAULT_HTTPS_PORT: u16 = 443;
+const DEFAULT_TLS_RELOAD_SECS: u64 = 30;
+
+/// A PEM certificate chain and its private key.
+#[derive(Clone, Debug)]
+struct CertPaths {
+    cert: PathBuf,
+    key: PathBuf,
+}
+
+/// HTTPS settings. `TLS_CERT` and `TLS_KEY` take comma-separated PEM paths,
+/// paired by position; the first pair is served when no other certificate
+/// matches the SNI name.
+pub(crate) struct TlsConfig {
+    certs: Vec<CertPaths>,
+    pub(crate) https_port: u16,
+    pub(crate) reload: Option<Duration>,
+}
+
+impl TlsConfig {
+    /// `None` when `TLS_CERT` is unset, which leaves HTTPS off.
+    pub(crate) fn from_env() -> io::Result<Option<Self>> {
+        let Ok(certs) = env::var("TLS_CERT") else {
+            return Ok(None);
+        };
+        let keys = env::var("TLS_KEY").map_err(|_| invalid("TLS_CERT is set without TLS_KEY"))?;
+        let certs: Vec<&str> = certs.split(',').map(str::trim).collect();
+        let keys: Vec<&str> = keys.split(',').map(str::trim).collect();
+        if certs.len() != keys.len() {
+            return Err(invalid(
+                "TLS_CERT and TLS_KEY list different numbers of files",
+            ));
+        }
+
+        let reload_secs = env::var("TLS_RELOAD_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_TLS_RELOAD_SECS);
+        Ok(Some(Self {
+            certs: certs
+                .into_iter()
+                .zip(keys)
+                .map(|(cert, key)| CertPaths {
+                    cert: cert.into(),
+                    key: key.into(),
+                })
+                .collect(),
+            https_port: env::var("HTTPS_PORT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_HTTPS_PORT),
+            reload: (reload_secs > 0).then(|| Duration::from_secs(reload_secs)),
+        }))
+    }
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+
+fn load_certified_key(paths: &CertPaths) -> io::Result<CertifiedKey> {
+    let chain: Vec<Certificate> =
+        rustls_pemfile::certs(&mut io::BufReader::new(std::fs::File::open(&paths.cert)?))?
+            .into_iter()
+            .map(Certificate)
+            .collect();
+    if chain.is_empty() {
+        return Err(invalid(format!(
+            "no certificates in {}",
+            paths.cert.display()
+        )));
+    }
+
+    let mut reader = io::BufReader::new(std::fs::File::open(&paths.key)?);
+    let key = loop {
+        match rustls_pemfile::read_one(&mut reader)? {
+            Some(
+                rustls_pemfile::Item::PKCS8Key(der)
+                | rustls_pemfile::Item::RSAKey(der)
+                | rustls_pemfile::Item::ECKey(der),
+            ) => break PrivateKey(der),
+            Some(_) => continue,
+            None => {
+                return Err(invalid(format!(
+                    "no private key in {}",
+                    paths.key.display()
+                )))
+            }
+        }
+    };
+    let key = sign::any_supported_type(&key)
+        .map_err(|_| invalid(format!("unsupported key in {}", paths.key.display())))?;
+    Ok(CertifiedKey::new(chain, key))
+}
+
+/// One loaded generation of certificates.
+struct CertSet {
+    by_name: ResolvesServerCertUsingSni,
+    default: Arc<CertifiedKey>,
+}
+
+impl CertSet {
+    /// Loads every pair and maps each of the site's host names to the first
+    /// certificate valid for it.
+    fn load(certs: &[CertPaths]) -> io::Result<Self> {
+        let keys = certs
+            .iter()
+            .map(load_certified_key)
+            .collect::<io::Result<Vec<_>>>()?;
+        let default = Arc::new(
+            keys.first()
+                .cloned()
+                .ok_or_else(|| invalid("no TLS certificates configured"))?,
+        );
+
+        let mut by_name = ResolvesServerCertUsingSni::new();
+        for host in SITE_HOSTS {
+            if !keys
+                .iter()
+                .any(|key| by_name.add(host, key.clone()).is_ok())
+            {
+                tracing::warn!(host, "no certificate covers host, serving the default");
+            }
+        }
+        Ok(Self { by_name, default })
+    }
+}
+
+/// Picks a certificate by SNI name from the current `CertSet`. Reloads swap
+/// the set, so new handshakes get the new certificates while established
+/// connections carry on.
+pub(crate) struct CertResolver {
+    current: RwLock<Arc<CertSet>>,
+}
+
+impl CertResolver {
+    fn set(&self) -> Arc<CertSet> {
+        self.current
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    fn replace(&self, set: CertSet) {
+        *self
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(set);
+    }
+}
+
+impl ResolvesServerCert for CertResolver {
+    fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        let set = self.set();
+        set.by_name
+            .resolve(client_hello)
+            .or_else(|| Some(set.default.clone()))
+    }
+}
+
+/// Loads the configured certificates, failing on any unreadable file.
+pub(crate) fn resolver(config: &TlsConfig) -> io::Result<Arc<CertResolver>> {
+    Ok(Arc::new(CertResolver {
+        current: RwLock::new(Arc::new(CertSet::load(&config.certs)?)),
+    }))
+}
+
+pub(crate) fn server_config(resolver: Arc<CertResolver>) -> ServerConfig {
+    let mut config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_cert_resolver(resolver);
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    config
+}
+
+async fn mtimes(certs: &[CertPaths]) -> Vec<Option<SystemTime>> {
+    let mut times = Vec::with_capacity(certs.len() * 2);
+    for paths in certs {
+        for path in [&paths.cert, &paths.key] {
+            times.push(file_mtime(path).await);
+        }
+    }
+    times
+}
+
+async fn file_mtime(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path).await.ok()?.modified().ok()
+}
+
+/// Polls the certificate and key files and swaps in a reloaded set when
+/// any of them changes. A set that fails to load keeps the previous one.
+pub(crate) async fn watch_certs(resolver: Arc<CertResolver>, config: Arc<TlsConfig>) {
+    let Some(interval) = config.reload else {
+        return;
+    };
+    let mut seen = mtimes(&config.certs).await;
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        let current = mtimes(&config.certs).await;
+        if current == seen {
+            continue;
+        }
+        seen = current;
+        let certs = config.certs.clone();
+        match tokio::task::spawn_blocking(move || CertSet::load(&certs)).await {
+            Ok(Ok(set)) => {
+                resolver.replace(set);
+                tracing::info!("TLS certificates reloaded");
+            }
+            Ok(Err(err)) => {
+                tracing::warn!(%err, "TLS certificate reload failed, keeping previous")
+            }
+            Err(err) => tracing::warn!(%err, "TLS certificate reload failed, keeping previous"),
+        }
+    }
+}
+
+/// The HTTPS port, for redirects from the plain-HTTP listener.
+pub(crate) struct HttpsPort(pub(crate) u16);
+
+/// Plain-HTTP requests for the site's hosts are sent to the same URL over
+/// HTTPS with a permanent redirect.
+pub(crate) async fn redirect(req: HttpRequest, port: web::Data<HttpsPort>) -> HttpResponse {
+    if !is_allowed_host(&req) {
+        return HttpResponse::NotFound().finish();
+    }
+    let host = req.connection_info().host().to_string();
+    let hostname = host.split(':').next().unwrap_or(&host);
+    let authority = match port.0 {
+        DEFAULT_HTTPS_PORT => hostname.to_string(),
+        port => format!("{hostname}:{port}"),
+    };
+    let path_and_query = req
+        .uri()
+        .path_and_query()
+        .map(|pq| pq.as_str())
+        .unwrap_or("/");
+    HttpResponse::PermanentRedirect()
+        .append_header((
+            header::LOCATION,
+            format!("https://{authority}{path_and_query}"),
+        ))
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{
+        http::StatusCode,
+        test::{call_service, init_service, TestRequest},
+        App,
+    };
+    use rcgen::{BasicConstraints, CertificateParams, IsCa, KeyPair};
+    use rustls::{ClientConfig, ClientConnection, RootCertStore, ServerConnection};
+
+    /// A certificate authority issuing the test certificates, and the one
+    /// root the test client trusts.
+    struct Ca {
+        cert: rcgen::Certificate,
+        key: KeyPair,
+    }
+
+    impl Ca {
+        fn new() -> Self {
+            let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+            params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let key = KeyPair::generate().unwrap();
+            let cert = params.self_signed(&key).unwrap();
+            Self { cert, key }
+        }
+
+        /// Writes a certificate for `names` and its key to `<name>.pem` and
+        /// `<name>.key` in `dir`, returning the paths and the certificate.
+        fn issue(&self, dir: &Path, name: &str, names: &[&str]) -> (CertPaths, Vec<u8>) {
+            let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+            let key = KeyPair::generate().unwrap();
+            let cert = CertificateParams::new(names)
+                .unwrap()
+                .signed_by(&key, &self.cert, &self.key)
+                .unwrap();
+            let paths = CertPaths {
+                cert: dir.join(format!("{name}.pem")),
+                key: dir.join(format!("{name}.key")),
+            };
+            std::fs::write(&paths.cert, cert.pem()).unwrap();
+            std::fs::write(&paths.key, key.serialize_pem()).unwrap();
+            (paths, cert.der().to_vec())
+        }
+
+        /// The certificate a client asking for `sni` is served, after
+        /// verifying it against this authority.
+        fn served(&self, resolver: &Arc<CertResolver>, sni: &str) -> Vec<u8> {
+            let mut roots = RootCertStore::empty();
+            roots.add(&Certificate(self.cert.der().to_vec())).unwrap();
+            let client_config = ClientConfig::builder()
+                .with_safe_defaults()
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+            let mut client =
+                ClientConnection::new(Arc::new(client_config), sni.try_into().unwrap()).unwrap();
+            let mut server =
+                ServerConnection::new(Arc::new(server_config(resolver.clone()))).unwrap();
+
+            let mut buf = Vec::new();
+            while client.is_handshaking() || server.is_handshaking() {
+                while client.wants_write() {
+                    client.write_tls(&mut buf).unwrap();
+                }
+                if !buf.is_empty() {
+                    server.read_tls(&mut buf.as_slice()).unwrap();
+                    server.process_new_packets().unwrap();
+                    buf.clear();
+                }
+                while server.wants_write() {
+                    server.write_tls(&mut buf).unwrap();
+                }
+                if !buf.is_empty() {
+                    client.read_tls(&mut buf.as_slice()).unwrap();
+                    client.process_new_packets().unwrap();
+                    buf.clear();
+                }
+            }
+            client.peer_certificates().unwrap()[0].0.clone()
+        }
+    }
+
+    fn config(certs: Vec<CertPaths>) -> TlsConfig {
+        TlsConfig {
+            certs,
+            https_port: DEFAULT_HTTPS_PORT,
+            reload: Some(Duration::from_millis(20)),
+        }
+    }
+
+    #[test]
+    fn picks_certificates_by_sni_and_falls_back_to_the_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let ca = Ca::new();
+        let (site, site_der) = ca.issue(
+            dir.path(),
+            "site",
+            &["rust.dev", "www.rust.dev", "preview.test"],
+        );
+        let (local, local_der) = ca.issue(dir.path(), "local", &["localhost"]);
+        let resolver = resolver(&config(vec![site, local])).unwrap();
+
+        assert_eq!(ca.served(&resolver, "rust.dev"), site_der);
+        assert_eq!(ca.served(&resolver, "www.rust.dev"), site_der);
+        assert_eq!(ca.served(&resolver, "localhost"), local_der);
+        // Not one of the site's hosts, so it gets the default.
+        assert_eq!(ca.served(&resolver, "preview.test"), site_der);
+    }
+
+    #[test]
+    fn refuses_to_start_without_a_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut site, _) = Ca::new().issue(dir.path(), "site", &["rust.dev"]);
+        site.key = site.cert.clone();
+        assert!(resolver(&config(vec![site])).is_err());
+    }
+
+    #[actix_web::test]
+    async fn watch_certs_picks_up_a_rotated_certificate() {
+        let dir = tempfile::tempdir().unwrap();
+        let ca = Ca::new();
+        let (site, old_der) = ca.issue(dir.path(), "site", &["rust.dev"]);
+        let config = Arc::new(config(vec![site]));
+        let resolver = resolver(&config).unwrap();
+        tokio::spawn(watch_certs(resolver.clone(), config.clone()));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (_, new_der) = ca.issue(dir.path(), "site", &["rust.dev"]);
+        assert_ne!(new_der, old_der);
+        for _ in 0..100 {
+            if ca.served(&resolver, "rust.dev") == new_der {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("the rotated certificate was not picked up");
+    }
+
+    async fn redirect_location(https_port: u16, host: &str, uri: &str) -> (StatusCode, String) {
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(HttpsPort(https_port)))
+                .default_service(web::route().to(redirect)),
+        )
+        .await;
+        let req = TestRequest::get()
+            .uri(uri)
+            .insert_header((header::HOST, host))
+            .to_request();
+        let res = call_service(&app, req).await;
+        let location = res
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_string();
+        (res.status(), location)
+    }
+
+    #[actix_web::test]
+    async fn redirects_plain_http_to_https() {
+        assert_eq!(
+            redirect_location(443, "rust.dev", "/tools?tag=web").await,
+            (
+                StatusCode::PERMANENT_REDIRECT,
+                "https://rust.dev/tools?tag=web".to_string()
+            )
+        );
+        assert_eq!(
+            redirect_location(8443, "localhost:8080", "/").await,
+            (
+                StatusCode::PERMANENT_REDIRECT,
+                "https://localhost:8443/".to_string()
+            )
+        );
+        assert_eq!(
+            redirect_location(443, "evil.example", "/").await.0,
+            StatusCode::NOT_FOUND
+        );
+    }
+}